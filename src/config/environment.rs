@@ -1,19 +1,23 @@
 use crate::{
-    models::{Grouping, Method, OpenapiPath},
+    models::{EndpointSelector, Grouping, LogFormat, Method, Threshold},
+    parser::get_openapi_app_base_url,
     utils::Error,
 };
 use float_eq::float_eq;
 use lazy_static::lazy_static;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     env,
     path::Path,
     str::FromStr,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 use url::Url;
 
-use super::{CoveAPIConfig, OpenapiSource, Runtime};
+use super::{
+    CoveAPIConfig, DeprecatedPolicy, FetchConfig, HostRewrite, OpenapiSource, PathNormalization, Proxy, Runtime,
+};
 
 const ENV_VAR_APP_BASE_URL: &str = "COVEAPI_APP_BASE_URL";
 const ENV_VAR_DEBUG: &str = "COVEAPI_DEBUG";
@@ -26,12 +30,56 @@ const ENV_VAR_MAPPING: &str = "COVEAPI_MAPPING";
 const ENV_VAR_IS_MERGE: &str = "COVEAPI_IS_MERGE";
 const ENV_VAR_ONLY_ACCOUNT_MERGE: &str = "COVEAPI_ONLY_ACCOUNT_MERGE";
 const ENV_VAR_GROUPINGS: &str = "COVEAPI_GROUPINGS";
+const ENV_VAR_SERVER_VARIABLES: &str = "COVEAPI_SERVER_VARIABLES";
+const ENV_VAR_VALIDATE: &str = "COVEAPI_VALIDATE";
+const ENV_VAR_THRESHOLDS: &str = "COVEAPI_THRESHOLDS";
+const ENV_VAR_DEPRECATED: &str = "COVEAPI_DEPRECATED";
+const ENV_VAR_PARAMETER_VALUE_COVERAGE: &str = "COVEAPI_PARAMETER_VALUE_COVERAGE";
+const ENV_VAR_ACCOUNT_FOR_REQUEST_MEDIA_TYPES: &str = "COVEAPI_ACCOUNT_FOR_REQUEST_MEDIA_TYPES";
+const ENV_VAR_GRAPHQL_PATH: &str = "COVEAPI_GRAPHQL_PATH";
+const ENV_VAR_GRAPHQL_FIELDS: &str = "COVEAPI_GRAPHQL_FIELDS";
+const ENV_VAR_OPENAPI_HEADERS: &str = "COVEAPI_OPENAPI_HEADERS";
+const ENV_VAR_OPENAPI_TIMEOUT: &str = "COVEAPI_OPENAPI_TIMEOUT";
+const ENV_VAR_OPENAPI_RETRY_DEADLINE: &str = "COVEAPI_OPENAPI_RETRY_DEADLINE";
+const ENV_VAR_OPENAPI_CA_BUNDLE: &str = "COVEAPI_OPENAPI_CA_BUNDLE";
+const ENV_VAR_HOST_REWRITES: &str = "COVEAPI_HOST_REWRITES";
+const ENV_VAR_BASE_OPENAPI_SOURCE: &str = "COVEAPI_BASE_OPENAPI_SOURCE";
+const ENV_VAR_OPENAPI_SNAPSHOT: &str = "COVEAPI_OPENAPI_SNAPSHOT";
+const ENV_VAR_PROXY: &str = "COVEAPI_PROXY";
+const ENV_VAR_LOG_FORMAT: &str = "COVEAPI_LOG_FORMAT";
+const ENV_VAR_STRICT_LOGS: &str = "COVEAPI_STRICT_LOGS";
+const ENV_VAR_HAR_FILES: &str = "COVEAPI_HAR_FILES";
+const ENV_VAR_MOUNT_POINT: &str = "COVEAPI_MOUNT_POINT";
+const ENV_VAR_MERGE_SLASHES: &str = "COVEAPI_MERGE_SLASHES";
+const ENV_VAR_IGNORE_TRAILING_SLASH: &str = "COVEAPI_IGNORE_TRAILING_SLASH";
+const ENV_VAR_CASE_INSENSITIVE_PATHS: &str = "COVEAPI_CASE_INSENSITIVE_PATHS";
 
 const DEFAULT_TEST_COVERAGE: f32 = 0.7;
 const DEFAULT_PORT: u16 = 13750;
+const DEFAULT_GRAPHQL_PATH: &str = "/graphql";
+const DEFAULT_OPENAPI_TIMEOUT_SECS: u64 = 30;
+// the repository is mounted here within docker
+const DEFAULT_MOUNT_POINT: &str = "/repo";
+// the built-in proxy runs without docker, from within the repository
+const BUILTIN_PROXY_MOUNT_POINT: &str = ".";
+// the application isn't contacted while validating, so a broken spec doesn't need to provide it
+const VALIDATION_APP_BASE_URL: &str = "http://localhost";
+
+const DEPRECATED_INCLUDE: &str = "include";
+const DEPRECATED_EXCLUDE: &str = "exclude";
+const DEPRECATED_REPORT_ONLY: &str = "report-only";
+
+const PROXY_NGINX: &str = "nginx";
+const PROXY_BUILTIN: &str = "builtin";
+const PROXY_NONE: &str = "none";
+const HOST_REWRITES_NONE: &str = "none";
 
 const LIST_SEPERATOR: &str = "COVEAPI_LINE_SEPERATOR";
 const MAPPING_SUBDELIMITER: &str = ";";
+const OPENAPI_SOURCE_SEPERATOR: &str = ",";
+const GRAPHQL_EXTENSIONS: [&str; 3] = ["graphql", "graphqls", "gql"];
+const SNAPSHOT_EXTENSIONS: [&str; 3] = ["json", "yaml", "yml"];
+const ROUTES_SOURCE_PREFIX: &str = "routes:";
 
 lazy_static! {
     static ref IS_DEBUG: RwLock<bool> = RwLock::new(true);
@@ -39,17 +87,18 @@ lazy_static! {
 
 impl CoveAPIConfig {
     pub fn from_raw(env_vars: &HashMap<String, String>) -> Result<CoveAPIConfig, Error> {
-        // Check if all enviroment variables exist
+        // Check if all enviroment variables exist, the application URL can be derived from the spec
         if !key_exists_and_is_not_empty(ENV_VAR_MAPPING, env_vars)
-            && (!key_exists_and_is_not_empty(ENV_VAR_OPENAPI_SOURCE, env_vars)
-                || !key_exists_and_is_not_empty(ENV_VAR_APP_BASE_URL, env_vars))
+            && !key_exists_and_is_not_empty(ENV_VAR_OPENAPI_SOURCE, env_vars)
         {
             return Err(Error::MissingConfiguration);
         }
         if key_exists_and_is_not_empty(ENV_VAR_MAPPING, env_vars)
             && (key_exists_and_is_not_empty(ENV_VAR_PORT, env_vars)
                 || key_exists_and_is_not_empty(ENV_VAR_OPENAPI_SOURCE, env_vars)
-                || key_exists_and_is_not_empty(ENV_VAR_APP_BASE_URL, env_vars))
+                || key_exists_and_is_not_empty(ENV_VAR_APP_BASE_URL, env_vars)
+                || key_exists_and_is_not_empty(ENV_VAR_BASE_OPENAPI_SOURCE, env_vars)
+                || key_exists_and_is_not_empty(ENV_VAR_OPENAPI_SNAPSHOT, env_vars))
         {
             return Err(Error::ConflictingConfiguration);
         }
@@ -58,16 +107,64 @@ impl CoveAPIConfig {
         let debug = get_bool_env_var(ENV_VAR_DEBUG, env_vars);
         let security_accounts_for_forbidden = get_bool_env_var(ENV_VAR_ACCOUNT_FOR_FORBIDDEN, env_vars);
         let security_accounts_for_unautorized = get_bool_env_var(ENV_VAR_ACCOUNT_FOR_UNAUTORIZED, env_vars);
+        let account_for_request_media_types = get_bool_env_var(ENV_VAR_ACCOUNT_FOR_REQUEST_MEDIA_TYPES, env_vars);
         let test_coverage = match env_vars.get(ENV_VAR_TEST_COVERAGE) {
             Some(coverage_str) => translate_test_coverage(coverage_str)?,
             None => 0.7,
         };
+        // unlike the test coverage, the parameter value coverage is only enforced when configured
+        let parameter_value_coverage = match env_vars.get(ENV_VAR_PARAMETER_VALUE_COVERAGE) {
+            Some(coverage_str) if !coverage_str.trim().is_empty() => Some(translate_test_coverage(coverage_str)?),
+            _ => None,
+        };
         let is_merge = get_bool_env_var(ENV_VAR_IS_MERGE, env_vars);
         let only_account_for_merge = get_bool_env_var(ENV_VAR_ONLY_ACCOUNT_MERGE, env_vars);
+        let validate = get_bool_env_var(ENV_VAR_VALIDATE, env_vars);
+        let graphql_path = match env_vars.get(ENV_VAR_GRAPHQL_PATH) {
+            Some(graphql_path) if !graphql_path.trim().is_empty() => parse_graphql_path(graphql_path)?,
+            _ => DEFAULT_GRAPHQL_PATH.to_string(),
+        };
+        let graphql_fields = get_bool_env_var(ENV_VAR_GRAPHQL_FIELDS, env_vars);
+        let strict_logs = get_bool_env_var(ENV_VAR_STRICT_LOGS, env_vars);
+        let path_normalization = PathNormalization {
+            merge_slashes: get_bool_env_var(ENV_VAR_MERGE_SLASHES, env_vars),
+            ignore_trailing_slash: get_bool_env_var(ENV_VAR_IGNORE_TRAILING_SLASH, env_vars),
+            case_insensitive: get_bool_env_var(ENV_VAR_CASE_INSENSITIVE_PATHS, env_vars),
+        };
+        let fetch_config = parse_fetch_config(env_vars)?;
         let groupings = match env_vars.get(ENV_VAR_GROUPINGS) {
             Some(grouping_str) => parse_grouping(grouping_str)?,
             None => HashSet::new(),
         };
+        let thresholds = match env_vars.get(ENV_VAR_THRESHOLDS) {
+            Some(thresholds_str) => parse_thresholds(thresholds_str)?,
+            None => vec![],
+        };
+        let deprecated_policy = match env_vars.get(ENV_VAR_DEPRECATED) {
+            Some(deprecated_policy_str) => parse_deprecated_policy(deprecated_policy_str)?,
+            None => DeprecatedPolicy::Include,
+        };
+        let proxy = match env_vars.get(ENV_VAR_PROXY) {
+            Some(proxy_str) => parse_proxy(proxy_str)?,
+            None => Proxy::Nginx,
+        };
+        let har_files = parse_source_paths(env_vars.get(ENV_VAR_HAR_FILES).map_or("", |x| x.as_str()));
+        let mount_point = match env_vars.get(ENV_VAR_MOUNT_POINT) {
+            Some(mount_point) if !mount_point.trim().is_empty() => mount_point.trim().to_string(),
+            _ if proxy == Proxy::Builtin => BUILTIN_PROXY_MOUNT_POINT.to_string(),
+            _ => DEFAULT_MOUNT_POINT.to_string(),
+        };
+        if proxy == Proxy::None && har_files.is_empty() {
+            return Err(Error::MissingHarFiles);
+        }
+        let log_format = match env_vars.get(ENV_VAR_LOG_FORMAT) {
+            Some(log_format_str) if !log_format_str.trim().is_empty() => LogFormat::from_str(log_format_str)?,
+            _ => LogFormat::default(),
+        };
+        let server_variables = match env_vars.get(ENV_VAR_SERVER_VARIABLES) {
+            Some(server_variables_str) => parse_server_variables(server_variables_str)?,
+            None => BTreeMap::new(),
+        };
 
         let runtimes = if !key_exists_and_is_not_empty(ENV_VAR_MAPPING, env_vars) {
             let openapi_source_str = match env_vars.get(ENV_VAR_OPENAPI_SOURCE) {
@@ -75,8 +172,8 @@ impl CoveAPIConfig {
                 None => return Err(Error::MissingConfiguration),
             };
             let app_base_url_str = match env_vars.get(ENV_VAR_APP_BASE_URL) {
-                Some(openapi_source) => openapi_source,
-                None => return Err(Error::MissingConfiguration),
+                Some(app_base_url_str) => app_base_url_str,
+                None => "",
             };
             let port_str = match env_vars.get(ENV_VAR_PORT) {
                 Some(port_str) => {
@@ -88,13 +185,36 @@ impl CoveAPIConfig {
                 }
                 None => None,
             };
-            vec![parse_runtime(openapi_source_str, app_base_url_str, port_str)?]
+            let mut runtime = parse_runtime(
+                openapi_source_str,
+                app_base_url_str,
+                port_str,
+                &server_variables,
+                validate,
+                &fetch_config,
+                &mount_point,
+            )?;
+            runtime.base_openapi_source =
+                parse_base_openapi_source(env_vars.get(ENV_VAR_BASE_OPENAPI_SOURCE).map_or("", |x| x.as_str()));
+            runtime.openapi_snapshot = parse_openapi_snapshot(
+                env_vars.get(ENV_VAR_OPENAPI_SNAPSHOT).map_or("", |x| x.as_str()),
+                &runtime.openapi_source,
+            )?;
+            runtime.path_normalization = path_normalization;
+            vec![Arc::new(runtime)]
         } else {
             let mapping_str = match env_vars.get(ENV_VAR_MAPPING) {
                 Some(mapping_str) => mapping_str,
                 None => return Err(Error::MissingMapping),
             };
-            parse_complex_mapping(mapping_str)?
+            parse_complex_mapping(
+                mapping_str,
+                &server_variables,
+                validate,
+                &fetch_config,
+                &mount_point,
+                path_normalization,
+            )?
         };
 
         // adjust global debug setting
@@ -113,6 +233,19 @@ impl CoveAPIConfig {
             is_merge,
             only_account_for_merge,
             groupings,
+            thresholds,
+            deprecated_policy,
+            account_for_request_media_types,
+            parameter_value_coverage,
+            validate,
+            graphql_path,
+            graphql_fields,
+            fetch_config,
+            proxy,
+            log_format,
+            strict_logs,
+            har_files,
+            mount_point,
         })
     }
 
@@ -137,7 +270,14 @@ fn key_exists_and_is_not_empty(key: &str, env_vars: &HashMap<String, String>) ->
     }
 }
 
-fn parse_complex_mapping(mapping_str: &str) -> Result<Vec<Arc<Runtime>>, Error> {
+fn parse_complex_mapping(
+    mapping_str: &str,
+    server_variables: &BTreeMap<String, String>,
+    validate: bool,
+    fetch_config: &FetchConfig,
+    mount_point: &str,
+    path_normalization: PathNormalization,
+) -> Result<Vec<Arc<Runtime>>, Error> {
     let mut runtimes = vec![];
 
     for line in mapping_str.split(LIST_SEPERATOR) {
@@ -149,13 +289,30 @@ fn parse_complex_mapping(mapping_str: &str) -> Result<Vec<Arc<Runtime>>, Error>
 
         let (app_base_url_str, index) = parse_untill_mapping_subdelimiter(index, line)?;
         let (openapi_source_str, index) = parse_untill_mapping_subdelimiter(index, line)?;
-        let (port_str, _) = parse_untill_mapping_subdelimiter(index, line)?;
+        let (port_str, index) = parse_untill_mapping_subdelimiter(index, line)?;
+        // the base source and snapshot are optional
+        let (base_openapi_source_str, index) = parse_optional_mapping_field(index, line)?;
+        let (openapi_snapshot_str, _) = parse_optional_mapping_field(index, line)?;
 
         let app_base_url_str = replace_escaped_sequences(app_base_url_str);
         let openapi_source_str = replace_escaped_sequences(openapi_source_str);
         let port_str = replace_escaped_sequences(port_str);
-
-        runtimes.push(parse_runtime(&openapi_source_str, &app_base_url_str, Some(&port_str))?);
+        let base_openapi_source_str = replace_escaped_sequences(base_openapi_source_str);
+        let openapi_snapshot_str = replace_escaped_sequences(openapi_snapshot_str);
+
+        let mut runtime = parse_runtime(
+            &openapi_source_str,
+            &app_base_url_str,
+            Some(&port_str),
+            server_variables,
+            validate,
+            fetch_config,
+            mount_point,
+        )?;
+        runtime.base_openapi_source = parse_base_openapi_source(&base_openapi_source_str);
+        runtime.openapi_snapshot = parse_openapi_snapshot(&openapi_snapshot_str, &runtime.openapi_source)?;
+        runtime.path_normalization = path_normalization;
+        runtimes.push(Arc::new(runtime));
     }
     if runtimes.is_empty() {
         return Err(Error::MissingMapping);
@@ -192,6 +349,167 @@ fn parse_grouping(grouping_str: &str) -> Result<HashSet<Grouping>, Error> {
     Ok(groupings)
 }
 
+fn parse_thresholds(thresholds_str: &str) -> Result<Vec<Threshold>, Error> {
+    let mut thresholds = vec![];
+
+    for line in thresholds_str.split(LIST_SEPERATOR) {
+        if line.trim() == "" {
+            continue;
+        }
+
+        let index = 0;
+        let (selector_str, index) = parse_untill_mapping_subdelimiter(index, line)?;
+        let (coverage_str, _) = parse_untill_mapping_subdelimiter(index, line)?;
+
+        if coverage_str.trim().is_empty() {
+            return Err(Error::InvalidThreshold(line.to_string()));
+        }
+        thresholds.push(Threshold::new(
+            EndpointSelector::from_str(&replace_escaped_sequences(selector_str))?,
+            translate_test_coverage(coverage_str.trim())?,
+        ));
+    }
+
+    Ok(thresholds)
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        FetchConfig {
+            headers: vec![],
+            timeout: Duration::from_secs(DEFAULT_OPENAPI_TIMEOUT_SECS),
+            retry_deadline: Duration::ZERO,
+            ca_bundle: None,
+            host_rewrites: vec![],
+        }
+    }
+}
+
+fn parse_fetch_config(env_vars: &HashMap<String, String>) -> Result<FetchConfig, Error> {
+    let mut fetch_config = FetchConfig::default();
+    if let Some(headers_str) = env_vars.get(ENV_VAR_OPENAPI_HEADERS) {
+        fetch_config.headers = parse_openapi_headers(headers_str)?;
+    }
+    if let Some(timeout_str) = env_vars.get(ENV_VAR_OPENAPI_TIMEOUT).filter(|x| !x.trim().is_empty()) {
+        fetch_config.timeout = parse_duration(timeout_str)?;
+    }
+    if let Some(deadline_str) = env_vars
+        .get(ENV_VAR_OPENAPI_RETRY_DEADLINE)
+        .filter(|x| !x.trim().is_empty())
+    {
+        fetch_config.retry_deadline = parse_duration(deadline_str)?;
+    }
+    if let Some(ca_bundle_str) = env_vars.get(ENV_VAR_OPENAPI_CA_BUNDLE).filter(|x| !x.trim().is_empty()) {
+        fetch_config.ca_bundle = Some(Box::from(Path::new(ca_bundle_str.trim())));
+    }
+    if let Some(host_rewrites_str) = env_vars.get(ENV_VAR_HOST_REWRITES) {
+        fetch_config.host_rewrites = parse_host_rewrites(host_rewrites_str)?;
+    }
+    Ok(fetch_config)
+}
+
+fn parse_openapi_headers(headers_str: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut headers = vec![];
+
+    for line in headers_str.split(LIST_SEPERATOR) {
+        if line.trim() == "" {
+            continue;
+        }
+
+        let index = 0;
+        let (name_str, index) = parse_untill_mapping_subdelimiter(index, line)?;
+        let (value_str, _) = parse_untill_mapping_subdelimiter(index, line)?;
+
+        let name = replace_escaped_sequences(name_str.trim());
+        let is_valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|x| x.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(x));
+        if !is_valid_name {
+            return Err(Error::InvalidOpenapiHeader(line.to_string()));
+        }
+        headers.push((name, replace_escaped_sequences(value_str.trim())));
+    }
+
+    Ok(headers)
+}
+
+fn parse_host_rewrites(host_rewrites_str: &str) -> Result<Vec<HostRewrite>, Error> {
+    let mut host_rewrites = vec![];
+    if host_rewrites_str.trim().eq_ignore_ascii_case(HOST_REWRITES_NONE) {
+        return Ok(host_rewrites);
+    }
+
+    for line in host_rewrites_str.split(LIST_SEPERATOR) {
+        if line.trim() == "" {
+            continue;
+        }
+
+        let index = 0;
+        let (host_str, index) = parse_untill_mapping_subdelimiter(index, line)?;
+        let (replacement_str, _) = parse_untill_mapping_subdelimiter(index, line)?;
+
+        let host = host_str.trim().to_lowercase();
+        let replacement = replacement_str.trim().to_string();
+        if host.is_empty() || url::Host::parse(&replacement).is_err() {
+            return Err(Error::InvalidHostRewrite(line.to_string()));
+        }
+        host_rewrites.push(HostRewrite { host, replacement });
+    }
+
+    Ok(host_rewrites)
+}
+
+/// Parses a duration in seconds, optionally followed by `s`.
+fn parse_duration(duration_str: &str) -> Result<Duration, Error> {
+    let seconds_str = duration_str.trim();
+    let seconds_str = seconds_str.strip_suffix('s').unwrap_or(seconds_str);
+    match seconds_str.parse() {
+        Ok(seconds) => Ok(Duration::from_secs(seconds)),
+        Err(_) => Err(Error::InvalidDuration(duration_str.to_string())),
+    }
+}
+
+fn parse_deprecated_policy(deprecated_policy_str: &str) -> Result<DeprecatedPolicy, Error> {
+    match deprecated_policy_str.trim().to_lowercase().as_str() {
+        "" | DEPRECATED_INCLUDE => Ok(DeprecatedPolicy::Include),
+        DEPRECATED_EXCLUDE => Ok(DeprecatedPolicy::Exclude),
+        DEPRECATED_REPORT_ONLY => Ok(DeprecatedPolicy::ReportOnly),
+        _ => Err(Error::InvalidDeprecatedPolicy(deprecated_policy_str.to_string())),
+    }
+}
+
+fn parse_proxy(proxy_str: &str) -> Result<Proxy, Error> {
+    match proxy_str.trim().to_lowercase().as_str() {
+        "" | PROXY_NGINX => Ok(Proxy::Nginx),
+        PROXY_BUILTIN => Ok(Proxy::Builtin),
+        PROXY_NONE => Ok(Proxy::None),
+        _ => Err(Error::InvalidProxy(proxy_str.to_string())),
+    }
+}
+
+fn parse_server_variables(server_variables_str: &str) -> Result<BTreeMap<String, String>, Error> {
+    let mut server_variables = BTreeMap::new();
+
+    for line in server_variables_str.split(LIST_SEPERATOR) {
+        if line.trim() == "" {
+            continue;
+        }
+
+        let index = 0;
+        let (name_str, index) = parse_untill_mapping_subdelimiter(index, line)?;
+        let (value_str, _) = parse_untill_mapping_subdelimiter(index, line)?;
+
+        let name = replace_escaped_sequences(name_str.trim());
+        if name.is_empty() {
+            return Err(Error::InvalidServerVariable(line.to_string()));
+        }
+        server_variables.insert(name, replace_escaped_sequences(value_str.trim()));
+    }
+
+    Ok(server_variables)
+}
+
 fn parse_untill_mapping_subdelimiter(index: usize, base: &str) -> Result<(&str, usize), Error> {
     let mut final_index = index;
     let mut is_escaped = false;
@@ -215,6 +533,14 @@ fn parse_untill_mapping_subdelimiter(index: usize, base: &str) -> Result<(&str,
     }
 }
 
+/// Parses the next field of a mapping line, if there is one.
+fn parse_optional_mapping_field(index: usize, base: &str) -> Result<(&str, usize), Error> {
+    match base.get(index..) {
+        Some(rest) if !rest.trim().is_empty() => parse_untill_mapping_subdelimiter(index, base),
+        _ => Ok(("", index)),
+    }
+}
+
 fn check_runtime_compatability(runtimes: &Vec<Arc<Runtime>>) -> bool {
     let mut ports = HashSet::new();
     for runtime in runtimes {
@@ -229,18 +555,74 @@ fn replace_escaped_sequences(base: &str) -> String {
     base.replace("\\;", ";")
 }
 
+fn parse_graphql_path(graphql_path_str: &str) -> Result<String, Error> {
+    let graphql_path = graphql_path_str.trim();
+    if !graphql_path.starts_with('/') || graphql_path.contains(char::is_whitespace) {
+        return Err(Error::InvalidPath(graphql_path.to_string()));
+    }
+    Ok(graphql_path.to_string())
+}
+
+/// Parses the openapi source of a runtime, which is either a url or a comma separated list of
+/// spec files, directories and globs. A list of only GraphQL schema files is a GraphQL source,
+/// route inventories are listed after a `routes:` prefix.
+fn parse_openapi_source(openapi_source_str: &str) -> OpenapiSource {
+    // checked first, since the prefix would be parsed as the scheme of a url
+    if let Some(routes_str) = openapi_source_str.trim().strip_prefix(ROUTES_SOURCE_PREFIX) {
+        return OpenapiSource::Routes(parse_source_paths(routes_str));
+    }
+    if let Ok(openapi_url) = Url::from_str(openapi_source_str.trim()) {
+        return OpenapiSource::Url(openapi_url);
+    }
+
+    let mut openapi_paths = parse_source_paths(openapi_source_str);
+
+    let is_graphql_path = |path: &Path| {
+        path.extension()
+            .and_then(|x| x.to_str())
+            .is_some_and(|x| GRAPHQL_EXTENSIONS.contains(&x))
+    };
+    if !openapi_paths.is_empty() && openapi_paths.iter().all(|x| is_graphql_path(x)) {
+        return OpenapiSource::Graphql(openapi_paths);
+    }
+
+    if openapi_paths.len() == 1 {
+        // unwrap is fine, since there is exactly one path
+        OpenapiSource::Path(openapi_paths.pop().unwrap())
+    } else {
+        OpenapiSource::Paths(openapi_paths)
+    }
+}
+
+fn parse_source_paths(paths_str: &str) -> Vec<Box<Path>> {
+    paths_str
+        .split(OPENAPI_SOURCE_SEPERATOR)
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|x| Box::from(Path::new(x)))
+        .collect()
+}
+
 fn parse_runtime(
     openapi_source_str: &str,
     app_base_url_str: &str,
     port_str: Option<&str>,
-) -> Result<Arc<Runtime>, Error> {
-    let openapi_source = match Url::from_str(openapi_source_str.trim()) {
-        Ok(openapi_url) => OpenapiSource::Url(openapi_url),
-        Err(_) => OpenapiSource::Path(Box::from(Path::new(openapi_source_str.trim()))),
-    };
-    let app_base_url = match Url::from_str(app_base_url_str.trim()) {
-        Ok(app_base_url) => app_base_url,
-        Err(parse_error) => return Err(Error::InvalidApplicationURL(parse_error.to_string())),
+    server_variables: &BTreeMap<String, String>,
+    validate: bool,
+    fetch_config: &FetchConfig,
+    mount_point: &str,
+) -> Result<Runtime, Error> {
+    let openapi_source = parse_openapi_source(openapi_source_str);
+    let app_base_url = if app_base_url_str.trim().is_empty() && validate {
+        // unwrap is fine, since the url is a valid constant
+        Url::from_str(VALIDATION_APP_BASE_URL).unwrap()
+    } else if app_base_url_str.trim().is_empty() {
+        get_openapi_app_base_url(&openapi_source, fetch_config, mount_point)?
+    } else {
+        match Url::from_str(app_base_url_str.trim()) {
+            Ok(app_base_url) => app_base_url,
+            Err(parse_error) => return Err(Error::InvalidApplicationURL(parse_error.to_string())),
+        }
     };
 
     let port = match port_str {
@@ -251,11 +633,42 @@ fn parse_runtime(
         _ => DEFAULT_PORT,
     };
 
-    Ok(Arc::from(Runtime {
+    Ok(Runtime {
         openapi_source,
         app_base_url,
         port,
-    }))
+        server_variables: server_variables.clone(),
+        base_openapi_source: None,
+        openapi_snapshot: None,
+        path_normalization: PathNormalization::default(),
+    })
+}
+
+fn parse_base_openapi_source(base_openapi_source_str: &str) -> Option<OpenapiSource> {
+    if base_openapi_source_str.trim().is_empty() {
+        None
+    } else {
+        Some(parse_openapi_source(base_openapi_source_str))
+    }
+}
+
+/// Snapshots are only taken of specs fetched from urls and have to be readable as spec files.
+fn parse_openapi_snapshot(
+    openapi_snapshot_str: &str,
+    openapi_source: &OpenapiSource,
+) -> Result<Option<Box<Path>>, Error> {
+    if openapi_snapshot_str.trim().is_empty() {
+        return Ok(None);
+    }
+    let openapi_snapshot: Box<Path> = Box::from(Path::new(openapi_snapshot_str.trim()));
+    let has_spec_extension = openapi_snapshot
+        .extension()
+        .and_then(|x| x.to_str())
+        .is_some_and(|x| SNAPSHOT_EXTENSIONS.contains(&x));
+    if !matches!(openapi_source, OpenapiSource::Url(_)) || !has_spec_extension {
+        return Err(Error::InvalidOpenapiSnapshot(openapi_snapshot));
+    }
+    Ok(Some(openapi_snapshot))
 }
 
 fn get_bool_env_var(key: &str, env_vars: &HashMap<String, String>) -> bool {
@@ -305,7 +718,7 @@ fn parse_grouping_strings(
     status_str: &str,
     is_ignore_group_str: &str,
 ) -> Result<Grouping, Error> {
-    let path = OpenapiPath::from_str(path_str.trim())?;
+    let selector = EndpointSelector::from_str(path_str)?;
     let mut methods = vec![];
     for method_str in methods_str.split(',') {
         let method = match Method::from_str(method_str.trim()) {
@@ -324,26 +737,41 @@ fn parse_grouping_strings(
         status.push(single_status);
     }
     let is_ignore_group = parse_bool(is_ignore_group_str.trim());
-    Ok(Grouping::new(methods, status, path, is_ignore_group))
+    Ok(Grouping::new(methods, status, selector, is_ignore_group))
 }
 
 #[cfg(test)]
 mod test {
     use float_eq::assert_float_eq;
-    use std::{collections::HashMap, path::Path};
+    use std::{
+        collections::{BTreeMap, HashMap},
+        path::Path,
+        str::FromStr,
+        time::Duration,
+    };
+
+    use url::Url;
 
     use crate::config::{
         environment::{
-            get_bool_env_var, key_exists_and_is_not_empty, parse_complex_mapping, replace_escaped_sequences,
+            get_bool_env_var, key_exists_and_is_not_empty, parse_complex_mapping, parse_deprecated_policy,
+            parse_grouping, parse_server_variables, parse_thresholds, replace_escaped_sequences,
             translate_test_coverage, DEFAULT_TEST_COVERAGE, ENV_VAR_ACCOUNT_FOR_UNAUTORIZED, ENV_VAR_MAPPING,
-            ENV_VAR_ONLY_ACCOUNT_MERGE, ENV_VAR_PORT,
+            ENV_VAR_ONLY_ACCOUNT_MERGE, ENV_VAR_PARAMETER_VALUE_COVERAGE, ENV_VAR_PORT, ENV_VAR_SERVER_VARIABLES,
+            ENV_VAR_VALIDATE,
         },
-        OpenapiSource,
+        DeprecatedPolicy, FetchConfig, HostRewrite, OpenapiSource, PathNormalization, Proxy,
     };
+    use crate::models::{EndpointSelector, LogFormat};
+    use crate::utils::Error;
 
     use super::{
+        parse_duration, parse_host_rewrites, parse_openapi_headers, parse_openapi_source, parse_proxy,
         parse_untill_mapping_subdelimiter, CoveAPIConfig, ENV_VAR_ACCOUNT_FOR_FORBIDDEN, ENV_VAR_APP_BASE_URL,
-        ENV_VAR_DEBUG, ENV_VAR_IS_MERGE, ENV_VAR_OPENAPI_SOURCE,
+        ENV_VAR_BASE_OPENAPI_SOURCE, ENV_VAR_CASE_INSENSITIVE_PATHS, ENV_VAR_DEBUG, ENV_VAR_GRAPHQL_PATH,
+        ENV_VAR_HAR_FILES, ENV_VAR_HOST_REWRITES, ENV_VAR_IGNORE_TRAILING_SLASH, ENV_VAR_IS_MERGE, ENV_VAR_LOG_FORMAT,
+        ENV_VAR_MOUNT_POINT, ENV_VAR_OPENAPI_CA_BUNDLE, ENV_VAR_OPENAPI_HEADERS, ENV_VAR_OPENAPI_RETRY_DEADLINE,
+        ENV_VAR_OPENAPI_SNAPSHOT, ENV_VAR_OPENAPI_SOURCE, ENV_VAR_OPENAPI_TIMEOUT, ENV_VAR_PROXY,
     };
 
     fn generate_config_map() -> HashMap<String, String> {
@@ -361,6 +789,22 @@ mod test {
         config_map
     }
 
+    #[test]
+    fn parses_list_of_openapi_paths() {
+        let mut config_map = generate_config_map();
+        config_map.insert(
+            String::from(ENV_VAR_OPENAPI_SOURCE),
+            String::from("docs/users.yaml, docs/orders/*.json"),
+        );
+        assert_eq!(
+            CoveAPIConfig::from_raw(&config_map).unwrap().runtimes[0].openapi_source,
+            OpenapiSource::Paths(vec![
+                Box::from(Path::new("docs/users.yaml")),
+                Box::from(Path::new("docs/orders/*.json"))
+            ])
+        );
+    }
+
     #[test]
     fn can_fetch_valid_openapi_path() {
         let config_map = generate_config_map();
@@ -392,7 +836,7 @@ mod test {
     #[test]
     fn missing_keys_lead_to_err() {
         let mut config_map = generate_config_map();
-        config_map.remove(ENV_VAR_APP_BASE_URL);
+        config_map.remove(ENV_VAR_OPENAPI_SOURCE);
         assert!(CoveAPIConfig::from_raw(&config_map).is_err());
     }
 
@@ -568,7 +1012,7 @@ mod test {
 
     #[test]
     fn parses_basic_mapping() {
-        let runtimes = parse_complex_mapping("https://localhost:8090; docs/swagger1.yaml; 13751;COVEAPI_LINE_SEPERATORhttps://example:8091; docs/swagger2.yaml; 13752;").unwrap();
+        let runtimes = parse_complex_mapping("https://localhost:8090; docs/swagger1.yaml; 13751;COVEAPI_LINE_SEPERATORhttps://example:8091; docs/swagger2.yaml; 13752;", &BTreeMap::new(), false, &FetchConfig::default(), "./", PathNormalization::default()).unwrap();
         assert_eq!(runtimes.len(), 2);
 
         assert!(runtimes.iter().any(|x| x.port == 13751));
@@ -591,13 +1035,28 @@ mod test {
 
     #[test]
     fn allows_different_whitespace_ammounts() {
-        let runtimes = parse_complex_mapping("\n   https://localhost:8090; docs/swagger1.yaml     ; 13751   ;\n\n");
+        let runtimes = parse_complex_mapping(
+            "\n   https://localhost:8090; docs/swagger1.yaml     ; 13751   ;\n\n",
+            &BTreeMap::new(),
+            false,
+            &FetchConfig::default(),
+            "./",
+            PathNormalization::default(),
+        );
         assert!(runtimes.is_ok())
     }
 
     #[test]
     fn allows_escaping_of_semicolon() {
-        let runtimes = parse_complex_mapping(r"https://localhost:8090; docs/swagger\;1.yaml; 13751;").unwrap();
+        let runtimes = parse_complex_mapping(
+            r"https://localhost:8090; docs/swagger\;1.yaml; 13751;",
+            &BTreeMap::new(),
+            false,
+            &FetchConfig::default(),
+            "./",
+            PathNormalization::default(),
+        )
+        .unwrap();
         assert_eq!(
             runtimes[0].openapi_source,
             OpenapiSource::Path(Box::from(Path::new("docs/swagger;1.yaml")))
@@ -706,4 +1165,346 @@ mod test {
         let config = CoveAPIConfig::from_raw(&env_vars).unwrap();
         assert!(config.only_account_for_merge);
     }
+
+    #[test]
+    fn parses_server_variables() {
+        let server_variables = parse_server_variables("env; staging;COVEAPI_LINE_SEPERATOR version ; v2 ;").unwrap();
+        assert_eq!(server_variables.get("env").unwrap(), "staging");
+        assert_eq!(server_variables.get("version").unwrap(), "v2");
+    }
+
+    #[test]
+    fn server_variables_without_name_lead_to_error() {
+        assert!(parse_server_variables(" ; staging;").is_err());
+    }
+
+    #[test]
+    fn validation_doesnt_require_app_base_url() {
+        let mut env_vars = generate_config_map();
+        env_vars.remove(ENV_VAR_APP_BASE_URL);
+        env_vars.insert(
+            ENV_VAR_OPENAPI_SOURCE.to_string(),
+            "./dump/split/openapi.yaml".to_string(),
+        );
+        assert!(CoveAPIConfig::from_raw(&env_vars).is_err());
+
+        env_vars.insert(ENV_VAR_VALIDATE.to_string(), "1".to_string());
+        assert!(CoveAPIConfig::from_raw(&env_vars).unwrap().validate);
+    }
+
+    #[test]
+    fn parameter_value_coverage_is_optional() {
+        let mut env_vars = generate_config_map();
+        assert_eq!(
+            CoveAPIConfig::from_raw(&env_vars).unwrap().parameter_value_coverage,
+            None
+        );
+
+        env_vars.insert(ENV_VAR_PARAMETER_VALUE_COVERAGE.to_string(), "80%".to_string());
+        assert_float_eq!(
+            CoveAPIConfig::from_raw(&env_vars)
+                .unwrap()
+                .parameter_value_coverage
+                .unwrap(),
+            0.8,
+            abs <= 0.001
+        );
+    }
+
+    #[test]
+    fn detects_graphql_schemas() {
+        assert_eq!(
+            parse_openapi_source("schema.graphql, types/users.gql"),
+            OpenapiSource::Graphql(vec![
+                Box::from(Path::new("schema.graphql")),
+                Box::from(Path::new("types/users.gql"))
+            ])
+        );
+        assert_eq!(
+            parse_openapi_source("schema.graphql, openapi.yaml"),
+            OpenapiSource::Paths(vec![
+                Box::from(Path::new("schema.graphql")),
+                Box::from(Path::new("openapi.yaml"))
+            ])
+        );
+    }
+
+    #[test]
+    fn detects_route_inventories() {
+        assert_eq!(
+            parse_openapi_source("routes: config/routes.txt, mappings.json"),
+            OpenapiSource::Routes(vec![
+                Box::from(Path::new("config/routes.txt")),
+                Box::from(Path::new("mappings.json"))
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_fetch_config() {
+        let mut env_vars = generate_config_map();
+        assert_eq!(
+            CoveAPIConfig::from_raw(&env_vars).unwrap().fetch_config,
+            FetchConfig::default()
+        );
+
+        env_vars.insert(
+            ENV_VAR_OPENAPI_HEADERS.to_string(),
+            "Authorization; Bearer abc\\;def;COVEAPI_LINE_SEPERATORX-Tenant; test;".to_string(),
+        );
+        env_vars.insert(ENV_VAR_OPENAPI_TIMEOUT.to_string(), "5s".to_string());
+        env_vars.insert(ENV_VAR_OPENAPI_RETRY_DEADLINE.to_string(), " 120 ".to_string());
+        env_vars.insert(ENV_VAR_OPENAPI_CA_BUNDLE.to_string(), "certs/ca.pem".to_string());
+        env_vars.insert(
+            ENV_VAR_HOST_REWRITES.to_string(),
+            "Localhost; host.docker.internal;".to_string(),
+        );
+        let fetch_config = CoveAPIConfig::from_raw(&env_vars).unwrap().fetch_config;
+
+        assert_eq!(
+            fetch_config.headers,
+            vec![
+                ("Authorization".to_string(), "Bearer abc;def".to_string()),
+                ("X-Tenant".to_string(), "test".to_string())
+            ]
+        );
+        assert_eq!(fetch_config.timeout, Duration::from_secs(5));
+        assert_eq!(fetch_config.retry_deadline, Duration::from_secs(120));
+        assert_eq!(fetch_config.ca_bundle, Some(Box::from(Path::new("certs/ca.pem"))));
+        assert_eq!(
+            fetch_config.host_rewrites,
+            vec![HostRewrite {
+                host: "localhost".to_string(),
+                replacement: "host.docker.internal".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_fetch_config() {
+        assert!(parse_openapi_headers("Bearer token; abc;").is_err());
+        assert!(parse_host_rewrites("localhost;;").is_err());
+    }
+
+    #[test]
+    fn parses_disabled_host_rewrites() {
+        assert_eq!(FetchConfig::default().host_rewrites, vec![]);
+        assert_eq!(parse_host_rewrites("").unwrap(), vec![]);
+        assert_eq!(parse_host_rewrites(" None ").unwrap(), vec![]);
+        assert!(parse_duration("1m").is_err());
+    }
+
+    #[test]
+    fn parses_base_openapi_source_and_snapshot() {
+        let mut env_vars = generate_config_map();
+        env_vars.insert(
+            ENV_VAR_OPENAPI_SOURCE.to_string(),
+            "http://localhost:8080/openapi.json".to_string(),
+        );
+        env_vars.insert(
+            ENV_VAR_BASE_OPENAPI_SOURCE.to_string(),
+            " snapshots/openapi.json ".to_string(),
+        );
+        env_vars.insert(ENV_VAR_OPENAPI_SNAPSHOT.to_string(), "snapshots/new.json".to_string());
+        let config = CoveAPIConfig::from_raw(&env_vars).unwrap();
+
+        assert_eq!(
+            config.runtimes[0].base_openapi_source,
+            Some(OpenapiSource::Path(Box::from(Path::new("snapshots/openapi.json"))))
+        );
+        assert_eq!(
+            config.runtimes[0].openapi_snapshot,
+            Some(Box::from(Path::new("snapshots/new.json")))
+        );
+        assert!(config.all_runtimes_have_pre_merge_source());
+
+        // specs read from files don't need to be stored
+        let mut env_vars = generate_config_map();
+        env_vars.insert(ENV_VAR_OPENAPI_SNAPSHOT.to_string(), "snapshots/new.json".to_string());
+        assert!(matches!(
+            CoveAPIConfig::from_raw(&env_vars),
+            Err(Error::InvalidOpenapiSnapshot(_))
+        ));
+    }
+
+    #[test]
+    fn parses_optional_fields_of_mapping() {
+        let runtimes = parse_complex_mapping(
+            "http://localhost:8090; http://localhost:8090/openapi.json; 13751; http://staging:8090/openapi.json; snapshots/users.yaml;COVEAPI_LINE_SEPERATORhttp://localhost:8091; docs/swagger2.yaml; 13752;",
+            &BTreeMap::new(),
+            false,
+            &FetchConfig::default(),
+            "./",
+            PathNormalization::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            runtimes[0].base_openapi_source,
+            Some(OpenapiSource::Url(
+                Url::from_str("http://staging:8090/openapi.json").unwrap()
+            ))
+        );
+        assert_eq!(
+            runtimes[0].openapi_snapshot,
+            Some(Box::from(Path::new("snapshots/users.yaml")))
+        );
+        assert_eq!(runtimes[1].base_openapi_source, None);
+        assert_eq!(runtimes[1].openapi_snapshot, None);
+
+        assert!(matches!(
+            parse_complex_mapping(
+                "http://localhost:8090; http://localhost:8090/openapi.json; 13751; ; snapshots/users.txt;",
+                &BTreeMap::new(),
+                false,
+                &FetchConfig::default(),
+                "./",
+                PathNormalization::default(),
+            ),
+            Err(Error::InvalidOpenapiSnapshot(_))
+        ));
+    }
+
+    #[test]
+    fn parses_graphql_path() {
+        let mut env_vars = generate_config_map();
+        assert_eq!(CoveAPIConfig::from_raw(&env_vars).unwrap().graphql_path, "/graphql");
+
+        env_vars.insert(ENV_VAR_GRAPHQL_PATH.to_string(), " /api/graphql ".to_string());
+        assert_eq!(CoveAPIConfig::from_raw(&env_vars).unwrap().graphql_path, "/api/graphql");
+
+        env_vars.insert(ENV_VAR_GRAPHQL_PATH.to_string(), "graphql".to_string());
+        assert!(CoveAPIConfig::from_raw(&env_vars).is_err());
+    }
+
+    #[test]
+    fn parses_thresholds() {
+        let thresholds = parse_thresholds("tag:users; 90%;COVEAPI_LINE_SEPERATOR operationId:getUser ; 1 ;").unwrap();
+
+        assert_eq!(thresholds.len(), 2);
+        assert_eq!(thresholds[0].selector, EndpointSelector::Tag("users".to_string()));
+        assert_float_eq!(thresholds[0].test_coverage, 0.9, abs <= 0.001);
+        assert_eq!(
+            thresholds[1].selector,
+            EndpointSelector::OperationId("getUser".to_string())
+        );
+        assert!(parse_thresholds("tag:users; ;").is_err());
+        assert!(parse_thresholds("tag:; 50%;").is_err());
+    }
+
+    #[test]
+    fn parses_proxy() {
+        assert_eq!(parse_proxy("").unwrap(), Proxy::Nginx);
+        assert_eq!(parse_proxy(" Builtin ").unwrap(), Proxy::Builtin);
+        assert_eq!(parse_proxy("none").unwrap(), Proxy::None);
+        assert!(parse_proxy("envoy").is_err());
+    }
+
+    #[test]
+    fn parses_mount_point() {
+        let mut env_vars = generate_config_map();
+        assert_eq!(CoveAPIConfig::from_raw(&env_vars).unwrap().mount_point, "/repo");
+
+        env_vars.insert(ENV_VAR_PROXY.to_string(), "builtin".to_string());
+        assert_eq!(CoveAPIConfig::from_raw(&env_vars).unwrap().mount_point, ".");
+
+        env_vars.insert(ENV_VAR_MOUNT_POINT.to_string(), " /work/api ".to_string());
+        assert_eq!(CoveAPIConfig::from_raw(&env_vars).unwrap().mount_point, "/work/api");
+    }
+
+    #[test]
+    fn parses_har_files() {
+        let mut env_vars = generate_config_map();
+        assert!(CoveAPIConfig::from_raw(&env_vars).unwrap().har_files.is_empty());
+
+        env_vars.insert(ENV_VAR_PROXY.to_string(), "none".to_string());
+        assert!(matches!(
+            CoveAPIConfig::from_raw(&env_vars),
+            Err(Error::MissingHarFiles)
+        ));
+
+        env_vars.insert(
+            ENV_VAR_HAR_FILES.to_string(),
+            "traffic/e2e.har, traffic/manual.har".to_string(),
+        );
+        let config = CoveAPIConfig::from_raw(&env_vars).unwrap();
+        assert_eq!(config.proxy, Proxy::None);
+        assert_eq!(
+            config.har_files,
+            vec![
+                Box::from(Path::new("traffic/e2e.har")),
+                Box::from(Path::new("traffic/manual.har"))
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_path_normalization() {
+        let mut env_vars = generate_config_map();
+        assert_eq!(
+            CoveAPIConfig::from_raw(&env_vars).unwrap().runtimes[0].path_normalization,
+            PathNormalization::default()
+        );
+
+        env_vars.insert(ENV_VAR_IGNORE_TRAILING_SLASH.to_string(), "1".to_string());
+        env_vars.insert(ENV_VAR_CASE_INSENSITIVE_PATHS.to_string(), "true".to_string());
+        assert_eq!(
+            CoveAPIConfig::from_raw(&env_vars).unwrap().runtimes[0].path_normalization,
+            PathNormalization {
+                merge_slashes: false,
+                ignore_trailing_slash: true,
+                case_insensitive: true,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_log_format() {
+        let mut env_vars = generate_config_map();
+        assert_eq!(
+            CoveAPIConfig::from_raw(&env_vars).unwrap().log_format,
+            LogFormat::default()
+        );
+
+        env_vars.insert(
+            ENV_VAR_LOG_FORMAT.to_string(),
+            "'$request $status $server_port $request_time'".to_string(),
+        );
+        assert!(CoveAPIConfig::from_raw(&env_vars)
+            .unwrap()
+            .log_format
+            .has_variable("request_time"));
+
+        env_vars.insert(ENV_VAR_LOG_FORMAT.to_string(), "'$request $status'".to_string());
+        assert!(matches!(
+            CoveAPIConfig::from_raw(&env_vars),
+            Err(Error::InvalidLogFormat(_))
+        ));
+    }
+
+    #[test]
+    fn parses_deprecated_policy() {
+        assert_eq!(parse_deprecated_policy("").unwrap(), DeprecatedPolicy::Include);
+        assert_eq!(parse_deprecated_policy(" Exclude ").unwrap(), DeprecatedPolicy::Exclude);
+        assert_eq!(
+            parse_deprecated_policy("report-only").unwrap(),
+            DeprecatedPolicy::ReportOnly
+        );
+        assert!(parse_deprecated_policy("ignore").is_err());
+    }
+
+    #[test]
+    fn parses_groupings_of_operations() {
+        let groupings = parse_grouping("tag:admin; GET, POST; 2XX; 1;").unwrap();
+        assert_eq!(groupings.len(), 1);
+        assert!(groupings.iter().all(|x| x.is_ignore_group));
+    }
+
+    #[test]
+    fn server_variables_are_added_to_runtimes() {
+        let mut env_vars = generate_config_map();
+        env_vars.insert(ENV_VAR_SERVER_VARIABLES.to_string(), "version; v2;".to_string());
+        let config = CoveAPIConfig::from_raw(&env_vars).unwrap();
+        assert_eq!(config.runtimes[0].server_variables.get("version").unwrap(), "v2");
+    }
 }
@@ -1,13 +1,18 @@
-use std::{collections::HashSet, path::Path, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
 
 use url::Url;
 
 mod environment;
 mod nginx;
 
-pub use nginx::configure_nginx;
+pub use nginx::{configure_nginx, get_access_log_format, get_graphql_log_format};
 
-use crate::models::Grouping;
+use crate::models::{EndpointConfiguration, Grouping, LogFormat, Threshold};
 
 #[derive(Debug)]
 pub struct CoveAPIConfig {
@@ -19,6 +24,50 @@ pub struct CoveAPIConfig {
     pub is_merge: bool,
     pub only_account_for_merge: bool,
     pub groupings: HashSet<Grouping>,
+    pub thresholds: Vec<Threshold>,
+    pub deprecated_policy: DeprecatedPolicy,
+    /// Requires every request media type of an operation to be tested on its own.
+    pub account_for_request_media_types: bool,
+    /// Coverage of enum and boolean parameter values to enforce, `None` if it isn't enforced.
+    pub parameter_value_coverage: Option<f32>,
+    /// Only validates the specs instead of measuring coverage.
+    pub validate: bool,
+    /// Path GraphQL schemas are served at by their applications.
+    pub graphql_path: String,
+    /// Requires every field of the types returned by GraphQL queries and mutations to be selected.
+    pub graphql_fields: bool,
+    pub fetch_config: FetchConfig,
+    pub proxy: Proxy,
+    /// Format of the nginx access log, which the log is parsed with as well.
+    pub log_format: LogFormat,
+    /// Fails on lines of the nginx logs and entries of HAR files that can't be parsed, instead of
+    /// skipping them.
+    pub strict_logs: bool,
+    /// HAR files whose requests are imported along with the recorded ones, relative to the
+    /// repository.
+    pub har_files: Vec<Box<Path>>,
+    /// Directory of the repository, which the paths of specs and other files are relative to.
+    pub mount_point: String,
+}
+
+/// How specs are fetched from urls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchConfig {
+    /// Headers sent along with every request, like an authorization header.
+    pub headers: Vec<(String, String)>,
+    pub timeout: Duration,
+    /// How long failed requests are retried, for applications that are still starting.
+    pub retry_deadline: Duration,
+    /// PEM file of additional certificate authorities to trust, relative to the repository.
+    pub ca_bundle: Option<Box<Path>>,
+    /// Rewrites hosts of spec urls and of application urls derived from specs.
+    pub host_rewrites: Vec<HostRewrite>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostRewrite {
+    pub host: String,
+    pub replacement: String,
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -26,12 +75,59 @@ pub struct Runtime {
     pub openapi_source: OpenapiSource,
     pub app_base_url: Url,
     pub port: u16,
+    pub server_variables: BTreeMap<String, String>,
+    /// Spec the changes are compared against instead of the `.coveapi.old` files, like the url of
+    /// a deployed version or a stored snapshot.
+    pub base_openapi_source: Option<OpenapiSource>,
+    /// File the spec is stored in after being fetched, relative to the repository.
+    pub openapi_snapshot: Option<Box<Path>>,
+    pub path_normalization: PathNormalization,
+}
+
+/// How the paths of requests are normalized before they're matched against the paths of a spec.
+/// Queries are always split off and percent-encoded characters are always decoded.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PathNormalization {
+    /// Treats consecutive slashes like a single one, like `/users//1` as `/users/1`.
+    pub merge_slashes: bool,
+    /// Treats paths with a trailing slash like the ones without, like `/users/` as `/users`.
+    pub ignore_trailing_slash: bool,
+    /// Compares paths regardless of the case of their letters.
+    pub case_insensitive: bool,
 }
 
 #[derive(Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum OpenapiSource {
     Path(Box<Path>),
+    /// Several spec files, directories or globs, whose operations are merged into one spec.
+    Paths(Vec<Box<Path>>),
     Url(Url),
+    /// GraphQL schema files, whose queries and mutations are evaluated instead of operations.
+    Graphql(Vec<Box<Path>>),
+    /// Route inventories of frameworks, for services without a spec.
+    Routes(Vec<Box<Path>>),
+}
+
+/// How requests are forwarded to the applications and recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Proxy {
+    /// An nginx subprocess, whose access logs are parsed once it stopped.
+    Nginx,
+    /// A reverse proxy within CoveAPI, which records the requests directly.
+    Builtin,
+    /// No requests are recorded, for runs that only import HAR files.
+    None,
+}
+
+/// How operations marked as deprecated in the spec are taken into account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeprecatedPolicy {
+    /// Deprecated operations count towards the test coverage like any other operation.
+    Include,
+    /// Deprecated operations are left out of the evaluation.
+    Exclude,
+    /// Deprecated operations don't count towards the test coverage, but are still reported.
+    ReportOnly,
 }
 
 impl CoveAPIConfig {
@@ -44,35 +140,126 @@ impl CoveAPIConfig {
         for runtime_index in 0..self.runtimes.len() {
             match &self.runtimes[runtime_index].openapi_source {
                 OpenapiSource::Path(path) => println!(" - openapi path: {:?}", path),
+                OpenapiSource::Paths(paths) => println!(" - openapi paths: {:?}", paths),
                 OpenapiSource::Url(url) => print!(" - openapi url: {}", url),
+                OpenapiSource::Graphql(paths) => println!(" - graphql schema paths: {:?}", paths),
+                OpenapiSource::Routes(paths) => println!(" - route inventory paths: {:?}", paths),
             };
             println!(" - app_base_url: {}", self.runtimes[runtime_index].app_base_url);
             println!(" - port: {}", self.runtimes[runtime_index].port);
+            for (name, value) in &self.runtimes[runtime_index].server_variables {
+                println!(" - server variable: {} = {}", name, value);
+            }
+            match &self.runtimes[runtime_index].base_openapi_source {
+                Some(OpenapiSource::Url(url)) => println!(" - base openapi url: {}", url),
+                Some(base_openapi_source) => println!(" - base openapi source: {:?}", base_openapi_source),
+                None => (),
+            }
+            if let Some(openapi_snapshot) = &self.runtimes[runtime_index].openapi_snapshot {
+                println!(" - openapi snapshot: {:?}", openapi_snapshot);
+            }
+            println!(
+                " - path normalization: {:?}",
+                self.runtimes[runtime_index].path_normalization
+            );
         }
-        println!(" - account_for_security: {}", self.security_accounts_for_forbidden);
+        println!(
+            " - account_for_security_forbidden: {}",
+            self.security_accounts_for_forbidden
+        );
+        println!(
+            " - account_for_security_unautorized: {}",
+            self.security_accounts_for_unautorized
+        );
         println!(" - test_coverage: {}", self.test_coverage);
+        for threshold in &self.thresholds {
+            println!(" - threshold: {} = {}", threshold.selector, threshold.test_coverage);
+        }
         println!(" - is_merge: {}", self.is_merge);
         println!(" - only_account_for_merge: {}", self.only_account_for_merge);
+        println!(
+            " - account_for_request_media_types: {}",
+            self.account_for_request_media_types
+        );
+        println!(" - deprecated_policy: {:?}", self.deprecated_policy);
+        if let Some(parameter_value_coverage) = self.parameter_value_coverage {
+            println!(" - parameter_value_coverage: {}", parameter_value_coverage);
+        }
+        for (name, _) in &self.fetch_config.headers {
+            // values are left out, since they usually contain credentials
+            println!(" - openapi header: {}", name);
+        }
+        println!(" - openapi_timeout: {:?}", self.fetch_config.timeout);
+        println!(" - openapi_retry_deadline: {:?}", self.fetch_config.retry_deadline);
+        if let Some(ca_bundle) = &self.fetch_config.ca_bundle {
+            println!(" - openapi_ca_bundle: {:?}", ca_bundle);
+        }
+        for host_rewrite in &self.fetch_config.host_rewrites {
+            println!(" - host rewrite: {} = {}", host_rewrite.host, host_rewrite.replacement);
+        }
+        if self.has_graphql_runtime() {
+            println!(" - graphql_path: {}", self.graphql_path);
+            println!(" - graphql_fields: {}", self.graphql_fields);
+        }
+        println!(" - proxy: {:?}", self.proxy);
+        println!(" - log_format: {}", self.log_format);
+        println!(" - strict_logs: {}", self.strict_logs);
+        for har_file in &self.har_files {
+            println!(" - har file: {:?}", har_file);
+        }
+        println!(" - validate: {}", self.validate);
+        println!(" - mount_point: {}", self.mount_point);
     }
 
-    pub fn all_openapi_sources_are_paths(&self) -> bool {
-        for runtime in &self.runtimes {
-            match runtime.openapi_source {
-                OpenapiSource::Url(_) => return false,
-                OpenapiSource::Path(_) => (),
-            }
+    /// Endpoints generated from security requirements are only expected, if the configuration
+    /// accounts for their status code.
+    pub fn expects_endpoint(&self, endpoint: &EndpointConfiguration) -> bool {
+        !endpoint.is_generated
+            || (endpoint.status_code == 401 && self.security_accounts_for_unautorized)
+            || (endpoint.status_code == 403 && self.security_accounts_for_forbidden)
+    }
+
+    /// Splits the endpoints by the request media types of their operations, if the coverage
+    /// accounts for them.
+    pub fn apply_request_media_types(&self, endpoints: Vec<EndpointConfiguration>) -> Vec<EndpointConfiguration> {
+        if !self.account_for_request_media_types {
+            return endpoints;
         }
-        true
+        endpoints
+            .into_iter()
+            .flat_map(|x| x.split_by_request_media_type())
+            .collect()
+    }
+
+    /// Checks that the specs of all runtimes can be compared with a previous version, which is
+    /// either configured as a base source or read from the `.coveapi.old` files.
+    pub fn all_runtimes_have_pre_merge_source(&self) -> bool {
+        self.runtimes.iter().all(|x| x.has_pre_merge_source())
+    }
+
+    pub fn has_graphql_runtime(&self) -> bool {
+        self.runtimes.iter().any(|x| x.is_graphql())
+    }
+}
+
+impl Runtime {
+    pub fn is_graphql(&self) -> bool {
+        matches!(self.openapi_source, OpenapiSource::Graphql(_))
+    }
+
+    pub fn has_pre_merge_source(&self) -> bool {
+        self.base_openapi_source.is_some() || !matches!(self.openapi_source, OpenapiSource::Url(_))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{path::Path, str::FromStr, sync::Arc};
-
-    use reqwest::Url;
+    use std::{path::Path, sync::Arc};
 
-    use crate::utils::test::create_mock_config;
+    use crate::{
+        models::{EndpointConfiguration, Method, StatusCode},
+        utils::test::{create_mock_config, create_mock_runtime},
+    };
 
     use super::{OpenapiSource, Runtime};
 
@@ -80,13 +267,9 @@ mod tests {
     fn should_only_print_merge_if_openapi_source_is_file() {
         let mut config = create_mock_config();
         config.is_merge = true;
-        config.runtimes = vec![Arc::new(Runtime {
-            openapi_source: OpenapiSource::Url(Url::from_str("https://example.com").unwrap()),
-            app_base_url: Url::from_str("https://example.com").unwrap(),
-            port: 8080,
-        })];
+        config.runtimes = vec![Arc::new(create_mock_runtime())];
 
-        assert!(!config.all_openapi_sources_are_paths())
+        assert!(!config.all_runtimes_have_pre_merge_source())
     }
 
     #[test]
@@ -95,10 +278,42 @@ mod tests {
         config.is_merge = true;
         config.runtimes = vec![Arc::new(Runtime {
             openapi_source: OpenapiSource::Path(Box::from(Path::new("./dump"))),
-            app_base_url: Url::from_str("https://example.com").unwrap(),
-            port: 8080,
+            ..create_mock_runtime()
         })];
 
-        assert!(config.all_openapi_sources_are_paths())
+        assert!(config.all_runtimes_have_pre_merge_source())
+    }
+
+    #[test]
+    fn should_print_merge_if_openapi_url_has_base_source() {
+        let mut config = create_mock_config();
+        config.is_merge = true;
+        let mut runtime = create_mock_runtime();
+        runtime.base_openapi_source = Some(OpenapiSource::Path(Box::from(Path::new("./dump/swagger.json"))));
+        config.runtimes = vec![Arc::new(runtime)];
+
+        assert!(config.all_runtimes_have_pre_merge_source())
+    }
+
+    #[test]
+    fn only_expects_generated_endpoints_that_are_accounted_for() {
+        let mut config = create_mock_config();
+        config.security_accounts_for_unautorized = true;
+        config.security_accounts_for_forbidden = false;
+
+        let create_endpoint = |status_code, is_generated| {
+            EndpointConfiguration::new(
+                Method::GET,
+                "/",
+                StatusCode::Exact(status_code),
+                Arc::new(create_mock_runtime()),
+                is_generated,
+            )
+            .unwrap()
+        };
+
+        assert!(config.expects_endpoint(&create_endpoint(401, true)));
+        assert!(!config.expects_endpoint(&create_endpoint(403, true)));
+        assert!(config.expects_endpoint(&create_endpoint(403, false)));
     }
 }
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeSet,
     fs::{File, OpenOptions},
     io::{Read, Write},
     path::Path,
@@ -6,10 +7,55 @@ use std::{
 };
 
 use super::{CoveAPIConfig, Runtime};
-use crate::utils::Error;
+use crate::{
+    models::{EndpointConfiguration, LogFormat, ParameterLocation},
+    utils::Error,
+};
+
+pub fn configure_nginx(config: &CoveAPIConfig, openapi_endpoints: &[EndpointConfiguration]) -> Result<(), Error> {
+    configure_nginx_file(
+        config,
+        &get_access_log_format(config, openapi_endpoints),
+        Path::new("/etc/nginx/nginx.conf"),
+    )
+}
+
+/// Format of the access log, which logs the header parameters of the specs in addition to the
+/// configured variables.
+pub fn get_access_log_format(config: &CoveAPIConfig, openapi_endpoints: &[EndpointConfiguration]) -> LogFormat {
+    add_header_variables(
+        config.log_format.clone(),
+        &get_header_parameter_names(openapi_endpoints),
+    )
+}
+
+/// Format of the log of requests to GraphQL schemas, whose bodies contain the selected fields.
+pub fn get_graphql_log_format(config: &CoveAPIConfig) -> LogFormat {
+    config.log_format.clone().with_variable("request_body")
+}
 
-pub fn configure_nginx(config: &CoveAPIConfig) -> Result<(), Error> {
-    configure_nginx_file(config, Path::new("/etc/nginx/nginx.conf"))
+/// Names of all header parameters declared in the specs, nginx can only log headers it knows
+/// the name of.
+fn get_header_parameter_names(openapi_endpoints: &[EndpointConfiguration]) -> BTreeSet<String> {
+    openapi_endpoints
+        .iter()
+        .filter_map(|x| x.operation.as_ref())
+        .flat_map(|x| x.parameters.iter())
+        .filter(|x| x.location == ParameterLocation::Header)
+        // nginx variables can only be built from header names consisting of these characters
+        .filter(|x| {
+            x.name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+        .map(|x| x.name.to_lowercase())
+        .collect()
+}
+
+fn add_header_variables(log_format: LogFormat, header_names: &BTreeSet<String>) -> LogFormat {
+    header_names.iter().fold(log_format, |log_format, name| {
+        log_format.with_variable(&format!("http_{}", name.replace('-', "_")))
+    })
 }
 
 fn replace_url(base: &str, url: &str) -> String {
@@ -24,28 +70,61 @@ fn replace_port_number(base: &str, port: u16) -> String {
     base.replace("INSERT_PORT_HERE", &port.to_string())
 }
 
-fn replace_runtime_configurations(base: &str, runtimes: &Vec<Arc<Runtime>>) -> String {
+fn replace_log_formats(base: &str, access_log_format: &LogFormat, graphql_log_format: &LogFormat) -> String {
+    base.replace("INSERT_LOG_FORMAT_HERE", &access_log_format.to_string())
+        .replace("INSERT_GRAPHQL_LOG_FORMAT_HERE", &graphql_log_format.to_string())
+}
+
+fn replace_graphql_path(base: &str, graphql_path: &str) -> String {
+    base.replace("INSERT_GRAPHQL_PATH_HERE", graphql_path)
+}
+
+fn replace_runtime_configurations(base: &str, runtimes: &Vec<Arc<Runtime>>, graphql_path: &str) -> String {
     let mut config_string = String::new();
     for runtime in runtimes {
-        config_string.push_str(&build_runtime_config(runtime));
+        config_string.push_str(&build_runtime_config(runtime, graphql_path));
     }
     base.replace("INSERT_CONFIGURATIONS_HERE", &config_string)
 }
 
-fn build_runtime_config(runtime: &Runtime) -> String {
+fn build_runtime_config(runtime: &Runtime, graphql_path: &str) -> String {
     const BASE_CONFIGURATION_STRUCTURE: &str = "
     server {
         listen INSERT_PORT_HERE;
         location /502 {
             return 502 'CoveAPI could not connect to your service, please double check that you specified the correct uri.';
         }
+        INSERT_GRAPHQL_LOCATION_HERE
         location / {
             proxy_pass INSERT_URL_HERE;
         }
     }
     ";
+    // the body has to fit into memory to be logged, it identifies the queries of the request
+    const GRAPHQL_LOCATION_STRUCTURE: &str = "location = INSERT_GRAPHQL_PATH_HERE {
+            client_body_buffer_size 1m;
+            access_log /var/log/nginx/access.log main;
+            access_log /var/log/nginx/graphql.log graphql;
+            proxy_pass INSERT_URL_HERE;
+        }";
+
     let config = &String::from(BASE_CONFIGURATION_STRUCTURE);
-    let config = replace_port_number(config, runtime.port);
+    let config = if runtime.is_graphql() {
+        let graphql_location = replace_graphql_path(GRAPHQL_LOCATION_STRUCTURE, graphql_path);
+        // the path of the location would be replaced by the one of the url, so it's added again
+        let graphql_location = replace_url(
+            &graphql_location,
+            &format!(
+                "{}{}",
+                runtime.app_base_url.as_str().trim_end_matches('/'),
+                graphql_path
+            ),
+        );
+        config.replace("INSERT_GRAPHQL_LOCATION_HERE", &graphql_location)
+    } else {
+        config.replace("\n        INSERT_GRAPHQL_LOCATION_HERE", "")
+    };
+    let config = replace_port_number(&config, runtime.port);
     let config = replace_url(&config, runtime.app_base_url.as_str());
     config
 }
@@ -65,7 +144,7 @@ fn open_config_file(path: &Path, for_writing: bool) -> Result<File, Error> {
     }
 }
 
-fn configure_nginx_file(config: &CoveAPIConfig, path: &Path) -> Result<(), Error> {
+fn configure_nginx_file(config: &CoveAPIConfig, access_log_format: &LogFormat, path: &Path) -> Result<(), Error> {
     let mut file = open_config_file(path, false)?;
 
     let mut config_string = String::new();
@@ -82,7 +161,8 @@ fn configure_nginx_file(config: &CoveAPIConfig, path: &Path) -> Result<(), Error
     if config.debug {
         config_string = replace_error_log(&config_string);
     }
-    config_string = replace_runtime_configurations(&config_string, &config.runtimes);
+    config_string = replace_log_formats(&config_string, access_log_format, &get_graphql_log_format(config));
+    config_string = replace_runtime_configurations(&config_string, &config.runtimes, &config.graphql_path);
 
     let mut file = open_config_file(path, true)?;
     match file.write_all(config_string.as_bytes()) {
@@ -101,6 +181,7 @@ fn configure_nginx_file(config: &CoveAPIConfig, path: &Path) -> Result<(), Error
 #[cfg(test)]
 mod tests {
     use std::{
+        collections::BTreeSet,
         fs::File,
         io::{Read, Write},
         path::Path,
@@ -113,12 +194,14 @@ mod tests {
     use crate::{
         config::{
             nginx::{
-                configure_nginx_file, replace_error_log, replace_port_number, replace_runtime_configurations,
+                add_header_variables, build_runtime_config, configure_nginx_file, get_graphql_log_format,
+                replace_error_log, replace_log_formats, replace_port_number, replace_runtime_configurations,
                 replace_url,
             },
             OpenapiSource, Runtime,
         },
-        utils::test::create_mock_config,
+        models::LogFormat,
+        utils::test::{create_mock_config, create_mock_runtime},
     };
 
     use super::open_config_file;
@@ -138,7 +221,7 @@ mod tests {
 
         let nginx_path = Path::new("./dump/nginx.conf");
         let config = create_mock_config();
-        configure_nginx_file(&config, nginx_path).unwrap();
+        configure_nginx_file(&config, &config.log_format, nginx_path).unwrap();
         let mut conf_string = String::from("");
         File::open(nginx_path)
             .unwrap()
@@ -155,18 +238,32 @@ mod tests {
     fn generates_multiple_configurations() {
         let mut config = create_mock_config();
         config.runtimes.push(Arc::from(Runtime {
-            openapi_source: OpenapiSource::Url(Url::from_str("http://example.com").unwrap()),
-            app_base_url: Url::from_str("http://example.com").unwrap(),
             port: 123,
+            ..create_mock_runtime()
         }));
         config.runtimes.push(Arc::from(Runtime {
-            openapi_source: OpenapiSource::Url(Url::from_str("http://example.com").unwrap()),
-            app_base_url: Url::from_str("http://example.com").unwrap(),
             port: 456,
+            ..create_mock_runtime()
         }));
-        let config_string = replace_runtime_configurations("INSERT_CONFIGURATIONS_HERE", &config.runtimes);
+        let config_string = replace_runtime_configurations("INSERT_CONFIGURATIONS_HERE", &config.runtimes, "/graphql");
         assert!(config_string.contains("123"));
         assert!(config_string.contains("456"));
+        assert!(!config_string.contains("INSERT_GRAPHQL_LOCATION_HERE"));
+    }
+
+    #[test]
+    fn logs_requests_to_graphql_path() {
+        let runtime = Runtime {
+            openapi_source: OpenapiSource::Graphql(vec![Box::from(Path::new("schema.graphql"))]),
+            app_base_url: Url::from_str("http://example.com/api/").unwrap(),
+            port: 123,
+            ..create_mock_runtime()
+        };
+        let config_string = build_runtime_config(&runtime, "/graphql");
+        assert!(config_string.contains("location = /graphql {"));
+        assert!(config_string.contains("access_log /var/log/nginx/graphql.log graphql;"));
+        assert!(config_string.contains("proxy_pass http://example.com/api/graphql;"));
+        assert!(config_string.contains("proxy_pass http://example.com/api/;"));
     }
 
     fn write_default_config() {
@@ -185,6 +282,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn logs_header_parameters() {
+        let mut config = create_mock_config();
+        config.log_format = LogFormat::from_str("'$request $status $server_port'").unwrap();
+        let header_names = BTreeSet::from(["x-request-id".to_string(), "accept-language".to_string()]);
+        assert_eq!(
+            replace_log_formats(
+                "log_format  main  INSERT_LOG_FORMAT_HERE;\nlog_format  graphql  INSERT_GRAPHQL_LOG_FORMAT_HERE;",
+                &add_header_variables(config.log_format.clone(), &header_names),
+                &get_graphql_log_format(&config)
+            ),
+            "log_format  main  '$request $status $server_port \"$http_accept_language\" \"$http_x_request_id\"';\nlog_format  graphql  '$request $status $server_port \"$request_body\"';"
+        );
+    }
+
     #[test]
     fn repaces_port_number() {
         let test_string = String::from("... stuff ... INSERT_PORT_HERE ... stuff ...");
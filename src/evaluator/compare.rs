@@ -1,9 +1,11 @@
-use std::{
-    cell::RefCell,
-    collections::{HashMap, HashSet},
-};
+use std::collections::HashSet;
+
+use crate::models::{EndpointConfiguration, EndpointSelector, Grouping, StatusCode};
 
-use crate::models::{EndpointConfiguration, Grouping};
+use super::{
+    media::{evaluate_request_media_types, evaluate_response_media_types, MediaTypeEvaluation},
+    parameters::{evaluate_parameters, ParameterEvaluation},
+};
 
 pub fn evaluate<'a>(
     openapi_endpoints: &'a Vec<EndpointConfiguration>,
@@ -11,58 +13,112 @@ pub fn evaluate<'a>(
     nginx_endpoints: &Vec<EndpointConfiguration>,
     groupings: &HashSet<Grouping>,
 ) -> Evaluation<'a> {
-    let mut grouping_endpoints: HashMap<&Grouping, Vec<RefCell<(&EndpointConfiguration, bool)>>> = HashMap::new();
-    for grouping in groupings {
-        grouping_endpoints.insert(grouping, vec![]);
-    }
-
-    let mut unmatched_endpoints: Vec<RefCell<(&EndpointConfiguration, bool)>> = vec![];
     let relevant_endpoints = get_endpoints_for_diff(pre_merge_endpoints, openapi_endpoints);
 
-    for openapi_endpoint in &relevant_endpoints {
-        let mut has_group = false;
-        for grouping in grouping_endpoints.iter_mut() {
-            if grouping.0.incompases_endpoint_config(openapi_endpoint) {
-                has_group = true;
-                if (!grouping.1.is_empty() && grouping.1[0].borrow().1) || grouping.0.is_ignore_group {
-                    grouping.1.push(RefCell::new((openapi_endpoint, true)));
-                } else if endpoint_incompases_any(openapi_endpoint, nginx_endpoints) {
-                    for endpoint in grouping.1.iter_mut() {
-                        let mut endpoint = endpoint.borrow_mut();
-                        endpoint.1 = true;
-                    }
-                    grouping.1.push(RefCell::new((openapi_endpoint, true)));
-                } else {
-                    add_endpoint_as_missed(openapi_endpoint, grouping.1, &mut unmatched_endpoints);
-                }
-            }
+    // a group counts as covered as soon as a single one of its endpoints is tested
+    let mut covered_groupings = HashSet::new();
+    for grouping in groupings {
+        if grouping.is_ignore_group
+            || relevant_endpoints.iter().any(|x| {
+                grouping.incompases_endpoint_config(x) && is_endpoint_tested(x, openapi_endpoints, nginx_endpoints)
+            })
+        {
+            covered_groupings.insert(grouping);
         }
+    }
 
-        if !has_group && !endpoint_incompases_any(openapi_endpoint, nginx_endpoints) {
-            unmatched_endpoints.push(RefCell::new((openapi_endpoint, false)));
+    let mut endpoints_not_covered = vec![];
+    for openapi_endpoint in &relevant_endpoints {
+        let mut endpoint_groupings = groupings
+            .iter()
+            .filter(|x| x.incompases_endpoint_config(openapi_endpoint))
+            .peekable();
+
+        let is_covered = if endpoint_groupings.peek().is_some() {
+            endpoint_groupings.all(|x| covered_groupings.contains(x))
+        } else {
+            is_endpoint_tested(openapi_endpoint, openapi_endpoints, nginx_endpoints)
+        };
+
+        if !is_covered {
+            endpoints_not_covered.push(*openapi_endpoint);
         }
     }
 
-    // filter for met endpoints
-    unmatched_endpoints.retain(|x| !x.borrow().1);
-
-    let test_coverage = if relevant_endpoints.is_empty() {
-        1.0
-    } else {
-        (relevant_endpoints.len() as f32 - unmatched_endpoints.len() as f32) / relevant_endpoints.len() as f32
-    };
+    let test_coverage = get_test_coverage(relevant_endpoints.len(), endpoints_not_covered.len());
 
     let has_gateway_issues = has_gateway_issues(nginx_endpoints);
 
-    let endpoints_not_covered = unmatched_endpoints.iter().map(|x| x.borrow().0).collect();
+    let relevant_endpoints: Vec<&EndpointConfiguration> = relevant_endpoints.into_iter().collect();
+    let parameter_evaluation = evaluate_parameters(&relevant_endpoints, nginx_endpoints, groupings);
+    let request_media_type_evaluation = evaluate_request_media_types(&relevant_endpoints, nginx_endpoints, groupings);
+    let response_media_type_evaluation =
+        evaluate_response_media_types(&relevant_endpoints, openapi_endpoints, nginx_endpoints, groupings);
 
     Evaluation {
         has_gateway_issues,
         test_coverage,
+        relevant_endpoints,
         endpoints_not_covered,
+        parameter_evaluation,
+        request_media_type_evaluation,
+        response_media_type_evaluation,
     }
 }
 
+fn get_test_coverage(endpoint_count: usize, not_covered_count: usize) -> f32 {
+    if endpoint_count == 0 {
+        1.0
+    } else {
+        (endpoint_count as f32 - not_covered_count as f32) / endpoint_count as f32
+    }
+}
+
+/// Checks whether a request matched the endpoint.
+fn is_endpoint_tested(
+    endpoint: &EndpointConfiguration,
+    openapi_endpoints: &[EndpointConfiguration],
+    nginx_endpoints: &Vec<EndpointConfiguration>,
+) -> bool {
+    if endpoint.status_code != StatusCode::Default {
+        return endpoint_incompases_any(endpoint, nginx_endpoints);
+    }
+    !get_endpoint_requests(endpoint, openapi_endpoints, nginx_endpoints).is_empty()
+}
+
+/// Collects the requests that matched the endpoint. The `default` response of an operation only
+/// counts requests with status codes that aren't declared by another response of the operation.
+pub(super) fn get_endpoint_requests<'a>(
+    endpoint: &EndpointConfiguration,
+    openapi_endpoints: &[EndpointConfiguration],
+    nginx_endpoints: &'a [EndpointConfiguration],
+) -> Vec<&'a EndpointConfiguration> {
+    let declared_status_codes: Vec<&StatusCode> = if endpoint.status_code == StatusCode::Default {
+        openapi_endpoints
+            .iter()
+            .filter(|x| {
+                x.status_code != StatusCode::Default
+                    && x.method == endpoint.method
+                    && x.path == endpoint.path
+                    && x.runtime == endpoint.runtime
+            })
+            .map(|x| &x.status_code)
+            .collect()
+    } else {
+        vec![]
+    };
+
+    nginx_endpoints
+        .iter()
+        .filter(|x| {
+            endpoint.incompases_endpoint(x)
+                && !declared_status_codes
+                    .iter()
+                    .any(|status_code| status_code.incompases_status_code(&x.status_code))
+        })
+        .collect()
+}
+
 fn endpoint_incompases_any(
     endpoint: &EndpointConfiguration,
     possibly_incompased_endpoints: &Vec<EndpointConfiguration>,
@@ -92,17 +148,7 @@ fn get_endpoints_for_diff<'a>(
     relevant_endpoints
 }
 
-fn add_endpoint_as_missed<'a>(
-    endpoint: &'a EndpointConfiguration,
-    grouping_endpoints: &mut Vec<RefCell<(&'a EndpointConfiguration, bool)>>,
-    unmatched_endpoints: &mut Vec<RefCell<(&'a EndpointConfiguration, bool)>>,
-) {
-    let endpoint = RefCell::new((endpoint, false));
-    grouping_endpoints.push(endpoint.clone());
-    unmatched_endpoints.push(endpoint.clone());
-}
-
-fn has_gateway_issues(nginx_endpoints: &Vec<EndpointConfiguration>) -> bool {
+fn has_gateway_issues(nginx_endpoints: &[EndpointConfiguration]) -> bool {
     let gateway_issues = nginx_endpoints.iter().filter(|x| x.status_code == 502).count();
     gateway_issues > 40 || gateway_issues > nginx_endpoints.len() / 4
 }
@@ -110,32 +156,82 @@ fn has_gateway_issues(nginx_endpoints: &Vec<EndpointConfiguration>) -> bool {
 pub struct Evaluation<'a> {
     pub has_gateway_issues: bool,
     pub test_coverage: f32,
+    pub relevant_endpoints: Vec<&'a EndpointConfiguration>,
     pub endpoints_not_covered: Vec<&'a EndpointConfiguration>,
+    pub parameter_evaluation: ParameterEvaluation<'a>,
+    pub request_media_type_evaluation: MediaTypeEvaluation<'a>,
+    pub response_media_type_evaluation: MediaTypeEvaluation<'a>,
+}
+
+impl Evaluation<'_> {
+    /// Test coverage of the endpoints a selector matches, `None` if it doesn't match any.
+    pub fn get_selector_test_coverage(&self, selector: &EndpointSelector) -> Option<f32> {
+        let endpoint_count = self
+            .relevant_endpoints
+            .iter()
+            .filter(|x| selector.incompases_endpoint_config(x))
+            .count();
+        if endpoint_count == 0 {
+            return None;
+        }
+
+        let not_covered_count = self
+            .endpoints_not_covered
+            .iter()
+            .filter(|x| selector.incompases_endpoint_config(x))
+            .count();
+        Some(get_test_coverage(endpoint_count, not_covered_count))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{collections::HashSet, str::FromStr, sync::Arc};
+    use std::{
+        collections::{BTreeMap, HashSet},
+        str::FromStr,
+        sync::Arc,
+    };
 
     use float_eq::assert_float_eq;
 
     use crate::{
-        models::{EndpointConfiguration, Grouping, Method, OpenapiPath},
-        utils::test::create_mock_runtime,
+        models::{EndpointConfiguration, EndpointSelector, Grouping, Method, OpenapiPath, Operation, StatusCode},
+        utils::test::{create_mock_operation, create_mock_runtime},
     };
 
     use super::{endpoint_incompases_any, evaluate, has_gateway_issues};
 
     fn create_endpoint_a() -> EndpointConfiguration {
-        EndpointConfiguration::new(Method::GET, "/a", 200, Arc::new(create_mock_runtime()), false).unwrap()
+        EndpointConfiguration::new(
+            Method::GET,
+            "/a",
+            StatusCode::Exact(200),
+            Arc::new(create_mock_runtime()),
+            false,
+        )
+        .unwrap()
     }
 
     fn create_endpoint_b() -> EndpointConfiguration {
-        EndpointConfiguration::new(Method::GET, "/b", 200, Arc::new(create_mock_runtime()), false).unwrap()
+        EndpointConfiguration::new(
+            Method::GET,
+            "/b",
+            StatusCode::Exact(200),
+            Arc::new(create_mock_runtime()),
+            false,
+        )
+        .unwrap()
     }
 
     fn create_endpoint_c() -> EndpointConfiguration {
-        EndpointConfiguration::new(Method::POST, "/c", 200, Arc::new(create_mock_runtime()), false).unwrap()
+        EndpointConfiguration::new(
+            Method::POST,
+            "/c",
+            StatusCode::Exact(200),
+            Arc::new(create_mock_runtime()),
+            false,
+        )
+        .unwrap()
     }
 
     #[test]
@@ -172,8 +268,8 @@ mod tests {
         let nginx_endpoints = vec![create_endpoint_a(), create_endpoint_b()];
         let grouping = Grouping::new(
             vec![Method::GET, Method::POST],
-            vec![200],
-            OpenapiPath::from_str("/{foo}").unwrap(),
+            vec![StatusCode::Exact(200)],
+            EndpointSelector::Path(OpenapiPath::from_str("/{foo}").unwrap()),
             false,
         );
         let mut groupings = HashSet::new();
@@ -207,9 +303,184 @@ mod tests {
 
     #[test]
     fn correctly_asserts_gateway_issues() {
-        let nginx_endpoints =
-            vec![EndpointConfiguration::new(Method::GET, "/", 502, Arc::new(create_mock_runtime()), false).unwrap()];
+        let nginx_endpoints = vec![EndpointConfiguration::new(
+            Method::GET,
+            "/",
+            StatusCode::Exact(502),
+            Arc::new(create_mock_runtime()),
+            false,
+        )
+        .unwrap()];
 
         assert!(has_gateway_issues(&nginx_endpoints));
     }
+
+    #[test]
+    fn evaluate_groups_regardless_of_order() {
+        let openapi_endpoints = vec![create_endpoint_a(), create_endpoint_b()];
+        let nginx_endpoints = vec![create_endpoint_b()];
+        let grouping = Grouping::new(
+            vec![Method::GET],
+            vec![StatusCode::Exact(200)],
+            EndpointSelector::Path(OpenapiPath::from_str("/{foo}").unwrap()),
+            false,
+        );
+        let mut groupings = HashSet::new();
+        groupings.insert(grouping);
+
+        // the endpoints are visited in a different order with every evaluation
+        for _ in 0..32 {
+            let evaluation = evaluate(&openapi_endpoints, &None, &nginx_endpoints, &groupings);
+            assert_float_eq!(evaluation.test_coverage, 1.0, abs <= 0.001);
+            assert!(evaluation.endpoints_not_covered.is_empty());
+        }
+    }
+
+    #[test]
+    fn evaluate_counts_untested_group_as_missed() {
+        let openapi_endpoints = vec![create_endpoint_a(), create_endpoint_b(), create_endpoint_c()];
+        let nginx_endpoints = vec![create_endpoint_c()];
+        let grouping = Grouping::new(
+            vec![Method::GET],
+            vec![StatusCode::Exact(200)],
+            EndpointSelector::Path(OpenapiPath::from_str("/{foo}").unwrap()),
+            false,
+        );
+        let mut groupings = HashSet::new();
+        groupings.insert(grouping);
+
+        let evaluation = evaluate(&openapi_endpoints, &None, &nginx_endpoints, &groupings);
+
+        assert_float_eq!(evaluation.test_coverage, 1.0 / 3.0, abs <= 0.001);
+        assert_eq!(evaluation.endpoints_not_covered.len(), 2);
+    }
+
+    #[test]
+    fn evaluate_requires_every_group_of_endpoint_to_be_covered() {
+        let openapi_endpoints = vec![create_endpoint_a(), create_endpoint_b()];
+        let nginx_endpoints = vec![create_endpoint_b()];
+        let mut groupings = HashSet::new();
+        groupings.insert(Grouping::new(
+            vec![Method::GET],
+            vec![StatusCode::Exact(200)],
+            EndpointSelector::Path(OpenapiPath::from_str("/{foo}").unwrap()),
+            false,
+        ));
+        groupings.insert(Grouping::new(
+            vec![Method::GET],
+            vec![StatusCode::Exact(200)],
+            EndpointSelector::Path(OpenapiPath::from_str("/a").unwrap()),
+            false,
+        ));
+
+        let evaluation = evaluate(&openapi_endpoints, &None, &nginx_endpoints, &groupings);
+
+        assert_float_eq!(evaluation.test_coverage, 0.5, abs <= 0.001);
+        assert_eq!(
+            evaluation.endpoints_not_covered[0].path,
+            OpenapiPath::from_str("/a").unwrap()
+        );
+    }
+
+    fn create_endpoint_with_status(status_code: StatusCode) -> EndpointConfiguration {
+        EndpointConfiguration::new(Method::GET, "/a", status_code, Arc::new(create_mock_runtime()), false).unwrap()
+    }
+
+    #[test]
+    fn evaluate_covers_range_with_code_of_its_class() {
+        let openapi_endpoints = vec![
+            create_endpoint_with_status(StatusCode::Range(2)),
+            create_endpoint_with_status(StatusCode::Range(4)),
+        ];
+        let nginx_endpoints = vec![create_endpoint_with_status(StatusCode::Exact(204))];
+
+        let evaluation = evaluate(&openapi_endpoints, &None, &nginx_endpoints, &HashSet::new());
+
+        assert_float_eq!(evaluation.test_coverage, 0.5, abs <= 0.001);
+        assert_eq!(evaluation.endpoints_not_covered[0].status_code, StatusCode::Range(4));
+    }
+
+    #[test]
+    fn evaluate_covers_default_only_with_undeclared_codes() {
+        let openapi_endpoints = vec![
+            create_endpoint_with_status(StatusCode::Exact(200)),
+            create_endpoint_with_status(StatusCode::Range(4)),
+            create_endpoint_with_status(StatusCode::Default),
+        ];
+
+        let nginx_endpoints = vec![
+            create_endpoint_with_status(StatusCode::Exact(200)),
+            create_endpoint_with_status(StatusCode::Exact(404)),
+        ];
+        let evaluation = evaluate(&openapi_endpoints, &None, &nginx_endpoints, &HashSet::new());
+        assert_eq!(evaluation.endpoints_not_covered.len(), 1);
+        assert_eq!(evaluation.endpoints_not_covered[0].status_code, StatusCode::Default);
+
+        let nginx_endpoints = vec![
+            create_endpoint_with_status(StatusCode::Exact(200)),
+            create_endpoint_with_status(StatusCode::Exact(404)),
+            create_endpoint_with_status(StatusCode::Exact(500)),
+        ];
+        let evaluation = evaluate(&openapi_endpoints, &None, &nginx_endpoints, &HashSet::new());
+        assert_float_eq!(evaluation.test_coverage, 1.0, abs <= 0.001);
+    }
+
+    #[test]
+    fn evaluates_test_coverage_of_selectors() {
+        let openapi_endpoints = vec![create_endpoint_a(), create_endpoint_b(), create_endpoint_c()];
+        let nginx_endpoints = vec![create_endpoint_a()];
+        let evaluation = evaluate(&openapi_endpoints, &None, &nginx_endpoints, &HashSet::new());
+
+        let selector = EndpointSelector::Path(OpenapiPath::from_str("/{foo}").unwrap());
+        assert_float_eq!(
+            evaluation.get_selector_test_coverage(&selector).unwrap(),
+            1.0 / 3.0,
+            abs <= 0.001
+        );
+        assert!(evaluation
+            .get_selector_test_coverage(&EndpointSelector::Tag("users".to_string()))
+            .is_none());
+    }
+
+    #[test]
+    fn diff_ignores_endpoints_whose_operation_changed() {
+        let pre_merge_operation = Arc::new(create_mock_operation(Method::GET, "/a"));
+        let operation = Arc::new(Operation {
+            extensions: BTreeMap::from([("x-owner".to_string(), "users".to_string())]),
+            ..create_mock_operation(Method::GET, "/a")
+        });
+        let openapi_endpoints = vec![create_endpoint_a().with_operation(&operation), create_endpoint_b()];
+        let pre_merge_endpoints = Some(vec![create_endpoint_a().with_operation(&pre_merge_operation)]);
+        let nginx_endpoints = vec![];
+        let evaluation = evaluate(
+            &openapi_endpoints,
+            &pre_merge_endpoints,
+            &nginx_endpoints,
+            &HashSet::new(),
+        );
+
+        assert_eq!(evaluation.relevant_endpoints, vec![&create_endpoint_b()]);
+    }
+
+    #[test]
+    fn diff_ignores_endpoints_whose_description_changed() {
+        let pre_merge_operation = Arc::new(create_mock_operation(Method::GET, "/a"));
+        let operation = Arc::new(Operation {
+            operation_id: Some("getA".to_string()),
+            summary: Some("Gets a".to_string()),
+            tags: vec!["users".to_string()],
+            ..create_mock_operation(Method::GET, "/a")
+        });
+        let openapi_endpoints = vec![create_endpoint_a().with_operation(&operation)];
+        let pre_merge_endpoints = Some(vec![create_endpoint_a().with_operation(&pre_merge_operation)]);
+        let nginx_endpoints = vec![];
+        let evaluation = evaluate(
+            &openapi_endpoints,
+            &pre_merge_endpoints,
+            &nginx_endpoints,
+            &HashSet::new(),
+        );
+
+        assert!(evaluation.relevant_endpoints.is_empty());
+    }
 }
@@ -0,0 +1,270 @@
+use std::{collections::HashSet, fmt::Display};
+
+use crate::models::{EndpointConfiguration, Grouping, MediaType};
+
+use super::{
+    compare::get_endpoint_requests,
+    parameters::{get_coverage, get_operation_endpoints},
+};
+
+/// A media type declared for the request body of an operation or the response body of an
+/// endpoint. Request media types are listed along with one of the endpoints of the operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvaluatedMediaType<'a> {
+    pub endpoint: &'a EndpointConfiguration,
+    pub media_type: &'a MediaType,
+    pub is_response: bool,
+}
+
+impl Display for EvaluatedMediaType<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_response {
+            write!(
+                f,
+                "{} {} {} response {}",
+                self.endpoint.path, self.endpoint.method, self.endpoint.status_code, self.media_type
+            )
+        } else {
+            write!(
+                f,
+                "{} {} request {}",
+                self.endpoint.path, self.endpoint.method, self.media_type
+            )
+        }
+    }
+}
+
+pub struct MediaTypeEvaluation<'a> {
+    pub test_coverage: f32,
+    pub relevant_media_types: Vec<EvaluatedMediaType<'a>>,
+    pub media_types_not_covered: Vec<EvaluatedMediaType<'a>>,
+}
+
+/// Checks which of the request media types declared by the operations of the endpoints were sent
+/// by at least one request, regardless of the status code it returned.
+pub fn evaluate_request_media_types<'a>(
+    relevant_endpoints: &[&'a EndpointConfiguration],
+    nginx_endpoints: &[EndpointConfiguration],
+    groupings: &HashSet<Grouping>,
+) -> MediaTypeEvaluation<'a> {
+    let mut relevant_media_types = vec![];
+    let mut media_types_not_covered = vec![];
+
+    for endpoint in get_operation_endpoints(relevant_endpoints, groupings) {
+        let operation = match &endpoint.operation {
+            Some(operation) => operation,
+            None => continue,
+        };
+
+        for media_type in &operation.request_media_types {
+            let is_covered = nginx_endpoints.iter().any(|x| {
+                endpoint.incompases_operation_of(x)
+                    && match &x.request_media_type {
+                        Some(request_media_type) => media_type.incompases_media_type(request_media_type),
+                        None => false,
+                    }
+            });
+
+            let evaluated_media_type = EvaluatedMediaType {
+                endpoint,
+                media_type,
+                is_response: false,
+            };
+            if !is_covered {
+                media_types_not_covered.push(evaluated_media_type);
+            }
+            relevant_media_types.push(evaluated_media_type);
+        }
+    }
+
+    MediaTypeEvaluation {
+        test_coverage: get_coverage(relevant_media_types.len(), media_types_not_covered.len()),
+        relevant_media_types,
+        media_types_not_covered,
+    }
+}
+
+/// Checks which of the media types declared for the responses of the endpoints were returned by
+/// at least one request that tested the endpoint.
+pub fn evaluate_response_media_types<'a>(
+    relevant_endpoints: &[&'a EndpointConfiguration],
+    openapi_endpoints: &[EndpointConfiguration],
+    nginx_endpoints: &[EndpointConfiguration],
+    groupings: &HashSet<Grouping>,
+) -> MediaTypeEvaluation<'a> {
+    let mut relevant_media_types = vec![];
+    let mut media_types_not_covered = vec![];
+
+    let mut sorted_endpoints = relevant_endpoints.to_vec();
+    sorted_endpoints.sort_by_key(|x| x.to_string());
+
+    let mut evaluated_endpoints: Vec<&EndpointConfiguration> = vec![];
+    for endpoint in sorted_endpoints {
+        let response = match &endpoint.operation {
+            Some(operation) => match operation
+                .responses
+                .iter()
+                .find(|x| x.status_code == endpoint.status_code)
+            {
+                Some(response) => response,
+                // responses generated from security requirements don't declare media types
+                None => continue,
+            },
+            None => continue,
+        };
+        let is_same_response = |x: &&EndpointConfiguration| {
+            x.method == endpoint.method
+                && x.path == endpoint.path
+                && x.runtime == endpoint.runtime
+                && x.status_code == endpoint.status_code
+        };
+        let is_ignored = groupings
+            .iter()
+            .any(|x| x.is_ignore_group && x.incompases_endpoint_config(endpoint));
+        if is_ignored || evaluated_endpoints.iter().any(is_same_response) {
+            continue;
+        }
+        evaluated_endpoints.push(endpoint);
+
+        // endpoints split by request media type share their response
+        let requests: Vec<&EndpointConfiguration> = relevant_endpoints
+            .iter()
+            .filter(|x| is_same_response(x))
+            .flat_map(|x| get_endpoint_requests(x, openapi_endpoints, nginx_endpoints))
+            .collect();
+
+        for media_type in &response.media_types {
+            let is_covered = requests.iter().any(|x| match &x.response_media_type {
+                Some(response_media_type) => media_type.incompases_media_type(response_media_type),
+                None => false,
+            });
+
+            let evaluated_media_type = EvaluatedMediaType {
+                endpoint,
+                media_type,
+                is_response: true,
+            };
+            if !is_covered {
+                media_types_not_covered.push(evaluated_media_type);
+            }
+            relevant_media_types.push(evaluated_media_type);
+        }
+    }
+
+    MediaTypeEvaluation {
+        test_coverage: get_coverage(relevant_media_types.len(), media_types_not_covered.len()),
+        relevant_media_types,
+        media_types_not_covered,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use float_eq::assert_float_eq;
+
+    use crate::{
+        models::{EndpointConfiguration, MediaType, Method, Operation, Response, StatusCode},
+        utils::test::{create_mock_operation, create_mock_runtime},
+    };
+
+    use super::{evaluate_request_media_types, evaluate_response_media_types};
+
+    fn create_endpoint(status_code: u16, request_media_type: Option<&str>) -> EndpointConfiguration {
+        EndpointConfiguration::new(
+            Method::POST,
+            "/uploads",
+            StatusCode::Exact(status_code),
+            Arc::new(create_mock_runtime()),
+            false,
+        )
+        .unwrap()
+        .with_request_media_type(request_media_type.map(MediaType::new))
+    }
+
+    #[test]
+    fn evaluates_request_media_types_once_per_operation() {
+        let operation = Arc::new(Operation {
+            request_media_types: vec![
+                MediaType::new("application/json"),
+                MediaType::new("multipart/form-data"),
+            ],
+            ..create_mock_operation(Method::POST, "/uploads")
+        });
+        let endpoint_created = create_endpoint(201, None).with_operation(&operation);
+        let endpoint_bad_request = create_endpoint(400, None).with_operation(&operation);
+        let nginx_endpoints = vec![
+            create_endpoint(400, Some("application/json; charset=utf-8")),
+            create_endpoint(201, None),
+        ];
+
+        let evaluation = evaluate_request_media_types(
+            &[&endpoint_created, &endpoint_bad_request],
+            &nginx_endpoints,
+            &HashSet::new(),
+        );
+
+        assert_eq!(evaluation.relevant_media_types.len(), 2);
+        assert_eq!(
+            evaluation.media_types_not_covered[0].to_string(),
+            "/uploads POST request multipart/form-data"
+        );
+        assert_float_eq!(evaluation.test_coverage, 0.5, abs <= 0.001);
+    }
+
+    #[test]
+    fn evaluates_response_media_types_per_status_code() {
+        let operation = Arc::new(Operation {
+            responses: vec![
+                Response {
+                    status_code: StatusCode::Exact(201),
+                    media_types: vec![MediaType::new("application/json"), MediaType::new("text/csv")],
+                },
+                Response {
+                    status_code: StatusCode::Default,
+                    media_types: vec![MediaType::new("application/problem+json")],
+                },
+            ],
+            ..create_mock_operation(Method::POST, "/uploads")
+        });
+        let openapi_endpoints = vec![
+            create_endpoint(201, None).with_operation(&operation),
+            EndpointConfiguration::new(
+                Method::POST,
+                "/uploads",
+                StatusCode::Default,
+                Arc::new(create_mock_runtime()),
+                false,
+            )
+            .unwrap()
+            .with_operation(&operation),
+        ];
+        let nginx_endpoints = vec![
+            create_endpoint(201, None).with_response_media_type(Some(MediaType::new("application/json"))),
+            // declared by another response, so it doesn't test the default response
+            create_endpoint(201, None).with_response_media_type(Some(MediaType::new("application/problem+json"))),
+        ];
+
+        let evaluation = evaluate_response_media_types(
+            &openapi_endpoints.iter().collect::<Vec<_>>(),
+            &openapi_endpoints,
+            &nginx_endpoints,
+            &HashSet::new(),
+        );
+
+        let media_types_not_covered: Vec<String> = evaluation
+            .media_types_not_covered
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        assert_eq!(
+            media_types_not_covered,
+            vec![
+                "/uploads POST 201 response text/csv",
+                "/uploads POST default response application/problem+json"
+            ]
+        );
+        assert_float_eq!(evaluation.test_coverage, 1.0 / 3.0, abs <= 0.001);
+    }
+}
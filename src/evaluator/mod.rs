@@ -1,3 +1,9 @@
 mod compare;
+mod media;
+mod parameters;
 pub use compare::evaluate;
 pub use compare::Evaluation;
+pub use media::EvaluatedMediaType;
+pub use media::MediaTypeEvaluation;
+pub use parameters::EvaluatedParameter;
+pub use parameters::ParameterEvaluation;
@@ -0,0 +1,289 @@
+use std::{collections::HashSet, fmt::Display};
+
+use crate::models::{EndpointConfiguration, Grouping, Parameter, ParameterLocation};
+
+/// Parameters that can be observed in the captured requests, cookies and request bodies aren't
+/// logged.
+const EVALUATED_PARAMETER_LOCATIONS: [ParameterLocation; 3] = [
+    ParameterLocation::Query,
+    ParameterLocation::Header,
+    ParameterLocation::Path,
+];
+
+/// A parameter declared by an operation, along with one of the endpoints of the operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvaluatedParameter<'a> {
+    pub endpoint: &'a EndpointConfiguration,
+    pub parameter: &'a Parameter,
+}
+
+impl Display for EvaluatedParameter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} parameter {}",
+            self.endpoint.path, self.endpoint.method, self.parameter.location, self.parameter.name
+        )
+    }
+}
+
+/// A value an enum or boolean parameter can take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvaluatedValue<'a> {
+    pub parameter: EvaluatedParameter<'a>,
+    pub value: &'a str,
+}
+
+pub struct ParameterEvaluation<'a> {
+    pub test_coverage: f32,
+    pub relevant_parameters: Vec<EvaluatedParameter<'a>>,
+    pub parameters_not_covered: Vec<EvaluatedParameter<'a>>,
+    pub value_coverage: f32,
+    pub relevant_values: Vec<EvaluatedValue<'a>>,
+    pub values_not_covered: Vec<EvaluatedValue<'a>>,
+}
+
+/// Checks which of the query, header and path parameters declared by the operations of the
+/// endpoints were sent by at least one request. Path parameters are always part of the request,
+/// so they are covered as soon as their operation is called. The values of enum and boolean
+/// parameters are evaluated the same way.
+pub fn evaluate_parameters<'a>(
+    relevant_endpoints: &[&'a EndpointConfiguration],
+    nginx_endpoints: &[EndpointConfiguration],
+    groupings: &HashSet<Grouping>,
+) -> ParameterEvaluation<'a> {
+    let mut relevant_parameters = vec![];
+    let mut parameters_not_covered = vec![];
+    let mut relevant_values = vec![];
+    let mut values_not_covered = vec![];
+
+    for endpoint in get_operation_endpoints(relevant_endpoints, groupings) {
+        let operation = match &endpoint.operation {
+            Some(operation) => operation,
+            None => continue,
+        };
+        let requests: Vec<&EndpointConfiguration> = nginx_endpoints
+            .iter()
+            .filter(|x| endpoint.incompases_operation_of(x))
+            .collect();
+
+        for parameter in &operation.parameters {
+            if !EVALUATED_PARAMETER_LOCATIONS.contains(&parameter.location) {
+                continue;
+            }
+
+            let is_covered = match parameter.location {
+                ParameterLocation::Path => !requests.is_empty(),
+                _ => requests
+                    .iter()
+                    .any(|x| x.parameters.iter().any(|y| parameter.is_sent_as(y))),
+            };
+
+            let evaluated_parameter = EvaluatedParameter { endpoint, parameter };
+            if !is_covered {
+                parameters_not_covered.push(evaluated_parameter);
+            }
+            relevant_parameters.push(evaluated_parameter);
+
+            let sent_values = get_sent_values(endpoint, parameter, &requests);
+            for value in &parameter.values {
+                let evaluated_value = EvaluatedValue {
+                    parameter: evaluated_parameter,
+                    value,
+                };
+                if !sent_values.contains(value) {
+                    values_not_covered.push(evaluated_value);
+                }
+                relevant_values.push(evaluated_value);
+            }
+        }
+    }
+
+    ParameterEvaluation {
+        test_coverage: get_coverage(relevant_parameters.len(), parameters_not_covered.len()),
+        relevant_parameters,
+        parameters_not_covered,
+        value_coverage: get_coverage(relevant_values.len(), values_not_covered.len()),
+        relevant_values,
+        values_not_covered,
+    }
+}
+
+pub(super) fn get_coverage(count: usize, not_covered_count: usize) -> f32 {
+    if count == 0 {
+        1.0
+    } else {
+        (count - not_covered_count) as f32 / count as f32
+    }
+}
+
+/// Collects the values a parameter took in the requests to its operation.
+fn get_sent_values(
+    endpoint: &EndpointConfiguration,
+    parameter: &Parameter,
+    requests: &[&EndpointConfiguration],
+) -> Vec<String> {
+    let mut sent_values = vec![];
+    for request in requests {
+        match parameter.location {
+            ParameterLocation::Path => {
+                if let Some(variable_values) = endpoint
+                    .path
+                    .get_request_variable_values(&request.path, &endpoint.runtime.path_normalization)
+                {
+                    sent_values.extend(
+                        variable_values
+                            .into_iter()
+                            .filter(|(name, _)| name == &parameter.name)
+                            .map(|(_, value)| value),
+                    );
+                }
+            }
+            _ => sent_values.extend(
+                request
+                    .parameters
+                    .iter()
+                    .filter(|x| parameter.is_sent_as(x))
+                    .map(|x| x.value.clone()),
+            ),
+        }
+    }
+    sent_values
+}
+
+/// Picks a single endpoint per operation, since all status codes of an operation share its
+/// parameters. Endpoints of ignored groups are left out.
+pub(super) fn get_operation_endpoints<'a>(
+    relevant_endpoints: &[&'a EndpointConfiguration],
+    groupings: &HashSet<Grouping>,
+) -> Vec<&'a EndpointConfiguration> {
+    let mut operation_endpoints: Vec<&EndpointConfiguration> = vec![];
+    for endpoint in relevant_endpoints {
+        let is_ignored = groupings
+            .iter()
+            .any(|x| x.is_ignore_group && x.incompases_endpoint_config(endpoint));
+        let is_known_operation = operation_endpoints
+            .iter()
+            .any(|x| x.method == endpoint.method && x.path == endpoint.path && x.runtime == endpoint.runtime);
+
+        if !is_ignored && !is_known_operation {
+            operation_endpoints.push(endpoint);
+        }
+    }
+    operation_endpoints.sort_by_key(|x| x.to_string());
+    operation_endpoints
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use float_eq::assert_float_eq;
+
+    use crate::{
+        models::{
+            EndpointConfiguration, Method, Operation, Parameter, ParameterLocation, RequestParameter, StatusCode,
+        },
+        utils::test::{create_mock_operation, create_mock_runtime},
+    };
+
+    use super::evaluate_parameters;
+
+    fn create_endpoint(path: &str, status_code: u16) -> EndpointConfiguration {
+        EndpointConfiguration::new(
+            Method::GET,
+            path,
+            StatusCode::Exact(status_code),
+            Arc::new(create_mock_runtime()),
+            false,
+        )
+        .unwrap()
+    }
+
+    fn create_operation() -> Arc<Operation> {
+        Arc::new(Operation {
+            parameters: vec![
+                Parameter::new("id", "path", true).unwrap(),
+                Parameter::new("status", "query", false).unwrap(),
+                Parameter::new("X-Tenant", "header", false).unwrap(),
+                Parameter::new("session", "cookie", false).unwrap(),
+            ],
+            ..create_mock_operation(Method::GET, "/users/{id}/orders")
+        })
+    }
+
+    #[test]
+    fn evaluates_parameters_once_per_operation() {
+        let operation = create_operation();
+        let endpoint_ok = create_endpoint("/users/{id}/orders", 200).with_operation(&operation);
+        let endpoint_not_found = create_endpoint("/users/{id}/orders", 404).with_operation(&operation);
+        let openapi_endpoints = vec![&endpoint_ok, &endpoint_not_found];
+
+        let nginx_endpoints = vec![create_endpoint("/users/1/orders", 404).with_parameters(vec![
+            RequestParameter::new(ParameterLocation::Header, "x-tenant", "acme"),
+            RequestParameter::new(ParameterLocation::Query, "limit", "10"),
+        ])];
+
+        let evaluation = evaluate_parameters(&openapi_endpoints, &nginx_endpoints, &HashSet::new());
+
+        assert_eq!(evaluation.relevant_parameters.len(), 3);
+        assert_eq!(evaluation.parameters_not_covered.len(), 1);
+        assert_eq!(evaluation.parameters_not_covered[0].parameter.name, "status");
+        assert_float_eq!(evaluation.test_coverage, 2.0 / 3.0, abs <= 0.001);
+    }
+
+    #[test]
+    fn doesnt_cover_parameters_of_operations_without_requests() {
+        let operation = create_operation();
+        let endpoint = create_endpoint("/users/{id}/orders", 200).with_operation(&operation);
+        let nginx_endpoints = vec![
+            create_endpoint("/users/1", 200).with_parameters(vec![RequestParameter::new(
+                ParameterLocation::Query,
+                "status",
+                "open",
+            )]),
+        ];
+
+        let evaluation = evaluate_parameters(&[&endpoint], &nginx_endpoints, &HashSet::new());
+
+        assert_float_eq!(evaluation.test_coverage, 0.0, abs <= 0.001);
+        assert_eq!(
+            evaluation.parameters_not_covered[0].to_string(),
+            "/users/{id}/orders GET path parameter id"
+        );
+    }
+
+    #[test]
+    fn evaluates_values_of_enum_and_boolean_parameters() {
+        let operation = Arc::new(Operation {
+            parameters: vec![
+                Parameter::new("format", "path", true)
+                    .unwrap()
+                    .with_values(vec!["json".to_string(), "csv".to_string()]),
+                Parameter::new("archived", "query", false)
+                    .unwrap()
+                    .with_values(vec!["true".to_string(), "false".to_string()]),
+            ],
+            ..(*create_operation()).clone()
+        });
+        let endpoint = create_endpoint("/users/{id}/orders.{format}", 200).with_operation(&operation);
+        let nginx_endpoints = vec![
+            create_endpoint("/users/1/orders.csv", 200).with_parameters(vec![RequestParameter::new(
+                ParameterLocation::Query,
+                "archived",
+                "true",
+            )]),
+            create_endpoint("/users/2/orders.xml", 400).with_parameters(vec![RequestParameter::new(
+                ParameterLocation::Query,
+                "archived",
+                "0",
+            )]),
+        ];
+
+        let evaluation = evaluate_parameters(&[&endpoint], &nginx_endpoints, &HashSet::new());
+
+        let values_not_covered: Vec<&str> = evaluation.values_not_covered.iter().map(|x| x.value).collect();
+        assert_eq!(values_not_covered, vec!["json", "false"]);
+        assert_float_eq!(evaluation.value_coverage, 0.5, abs <= 0.001);
+    }
+}
@@ -1,22 +1,32 @@
-use std::process::{Command, Stdio};
+use std::{
+    process::{self, Command, Stdio},
+    sync::mpsc,
+};
 
-use config::{configure_nginx, CoveAPIConfig};
+use config::{configure_nginx, get_access_log_format, get_graphql_log_format, CoveAPIConfig, DeprecatedPolicy};
 use evaluator::evaluate;
 use models::EndpointConfiguration;
-use parser::{get_openapi_endpoint_configs, get_pre_merge_openapi_endpoints};
+use parser::{
+    get_har_endpoint_configs, get_openapi_endpoint_configs, get_openapi_lint_problems, get_pre_merge_openapi_endpoints,
+};
+use proxy::ReverseProxy;
 use utils::print_debug_message;
 
-use crate::{parser::parse_nginx_access_log, utils::print_error_and_exit};
+use crate::{
+    parser::{parse_nginx_access_log, parse_nginx_graphql_log},
+    utils::print_error_and_exit,
+};
 
 pub mod config;
 pub mod evaluator;
 pub mod models;
 pub mod parser;
+pub mod proxy;
 pub mod utils;
 
-pub fn run_nginx(config: &CoveAPIConfig) {
-    // insert application URL to nginx file
-    match configure_nginx(config) {
+pub fn run_nginx(config: &CoveAPIConfig, openapi_endpoints: &[EndpointConfiguration]) {
+    // insert application URL and logged headers to nginx file
+    match configure_nginx(config, openapi_endpoints) {
         Ok(_) => (),
         Err(error) => error.display_error_and_exit(),
     }
@@ -42,17 +52,78 @@ pub fn run_nginx(config: &CoveAPIConfig) {
     }
 }
 
-pub fn initialize_coveapi() -> (
-    CoveAPIConfig,
-    Vec<EndpointConfiguration>,
-    Option<Vec<EndpointConfiguration>>,
-) {
-    let config = match CoveAPIConfig::from_env() {
-        Ok(config) => config,
+/// Reads the requests nginx logged while it was running.
+pub fn parse_nginx_logs(
+    config: &CoveAPIConfig,
+    openapi_endpoints: &[EndpointConfiguration],
+) -> Vec<EndpointConfiguration> {
+    // the logs are written in the formats nginx was configured with
+    let access_log_format = get_access_log_format(config, openapi_endpoints);
+    let (mut nginx_endpoints, diagnostics) =
+        match parse_nginx_access_log(&config.runtimes, &access_log_format, config.strict_logs) {
+            Ok(parsed_log) => parsed_log,
+            Err(error) => error.display_error_and_exit(),
+        };
+    diagnostics.print("lines of the nginx access log");
+    if config.has_graphql_runtime() {
+        match parse_nginx_graphql_log(&config.runtimes, &get_graphql_log_format(config), config.strict_logs) {
+            Ok((mut graphql_endpoints, diagnostics)) => {
+                nginx_endpoints.append(&mut graphql_endpoints);
+                diagnostics.print("lines of the nginx GraphQL log");
+            }
+            Err(error) => error.display_error_and_exit(),
+        }
+    }
+    nginx_endpoints
+}
+
+/// Reads the requests of the configured HAR files.
+pub fn parse_har_files(config: &CoveAPIConfig) -> Vec<EndpointConfiguration> {
+    let mut har_endpoints = vec![];
+    for har_file in &config.har_files {
+        match get_har_endpoint_configs(config, har_file) {
+            Ok((mut endpoints, diagnostics)) => {
+                har_endpoints.append(&mut endpoints);
+                diagnostics.print(&format!("entries of the HAR file {:?}", har_file));
+            }
+            Err(error) => error.display_error_and_exit(),
+        }
+    }
+    har_endpoints
+}
+
+/// Records the requests through the built-in proxy, until CoveAPI is interrupted or terminated.
+pub fn run_proxy(config: &CoveAPIConfig) -> Vec<EndpointConfiguration> {
+    let (stop_sender, stop_receiver) = mpsc::channel();
+    if let Err(why) = ctrlc::set_handler(move || {
+        // the receiver only waits for the first signal
+        let _ = stop_sender.send(());
+    }) {
+        print_error_and_exit(format!("Error: Registering the stop signal failed with: {}", why));
+    }
+
+    print_debug_message("Starting the built-in proxy");
+    let proxy = match ReverseProxy::start(config) {
+        Ok(proxy) => proxy,
         Err(error) => error.display_error_and_exit(),
     };
 
-    let openapi_endpoints = match get_openapi_endpoint_configs(&config) {
+    if stop_receiver.recv().is_err() {
+        print_error_and_exit("Error: Waiting for the stop signal failed");
+    }
+    print_debug_message("Stopping the built-in proxy");
+    proxy.stop()
+}
+
+pub fn initialize_config() -> CoveAPIConfig {
+    match CoveAPIConfig::from_env() {
+        Ok(config) => config,
+        Err(error) => error.display_error_and_exit(),
+    }
+}
+
+pub fn initialize_coveapi(config: &CoveAPIConfig) -> (Vec<EndpointConfiguration>, Option<Vec<EndpointConfiguration>>) {
+    let openapi_endpoints = match get_openapi_endpoint_configs(config) {
         Ok(openapi_endpoints) => openapi_endpoints,
         Err(error) => error.display_error_and_exit(),
     };
@@ -61,9 +132,9 @@ pub fn initialize_coveapi() -> (
 
     // filter out impossible szenarios, where they require only_account_for_merge but nothing can
     // be compared
-    if config.only_account_for_merge && !config.all_openapi_sources_are_paths() {
+    if config.only_account_for_merge && !config.all_runtimes_have_pre_merge_source() {
         if config.is_merge {
-            print_error_and_exit("Your configuration contains a dynamically loaded openapi spec. CoveAPI needs it to be a local file when only accounting for the difference between commits.");
+            print_error_and_exit("Your configuration contains a dynamically loaded openapi spec without a base source. CoveAPI needs it to be a local file or a base-openapi-source to compare against when only accounting for the difference between commits.");
         } else {
             print_error_and_exit("You need to have two commits to compare (ex. pull/merge request) when only accounting for the difference between commits.");
         }
@@ -74,31 +145,53 @@ pub fn initialize_coveapi() -> (
         let mut endpoints = vec![];
 
         for runtime in &config.runtimes {
-            let mut pre_merge_endpoints_of_runtime = match get_pre_merge_openapi_endpoints(runtime.clone()) {
+            let mut pre_merge_endpoints_of_runtime = match get_pre_merge_openapi_endpoints(config, runtime.clone()) {
                 Ok(endpoints) => endpoints,
                 Err(err) => err.display_error_and_exit(),
             };
-            endpoints.append(&mut pre_merge_endpoints_of_runtime);
+            pre_merge_endpoints_of_runtime.retain(|x| config.expects_endpoint(x));
+            endpoints.append(&mut config.apply_request_media_types(pre_merge_endpoints_of_runtime));
         }
         pre_merge_endpoints = Some(endpoints);
     }
-    (config, openapi_endpoints, pre_merge_endpoints)
+    (openapi_endpoints, pre_merge_endpoints)
+}
+
+pub fn run_validation(config: &CoveAPIConfig) -> ! {
+    print_debug_message("Validating openapi specs");
+
+    let problems = get_openapi_lint_problems(config);
+    for problem in &problems {
+        println!("{}", problem);
+    }
+
+    if !problems.is_empty() {
+        print_error_and_exit(format!(
+            "Error: Found {} problem(s) in the openapi specs.",
+            problems.len()
+        ));
+    }
+    println!("No problems found in the openapi specs.");
+    process::exit(0);
 }
 
 pub fn run_eval(
     config: &CoveAPIConfig,
     openapi_endpoints: Vec<EndpointConfiguration>,
     pre_merge_endpoints: Option<Vec<EndpointConfiguration>>,
+    nginx_endpoints: Vec<EndpointConfiguration>,
 ) {
     print_debug_message("Evaluating endpoint coverage");
 
-    let nginx_endpoints = match parse_nginx_access_log(&config.runtimes) {
-        Ok(nginx_endpoints) => nginx_endpoints,
-        Err(_) => print_error_and_exit("An unexpected error occured while parsing the nginx logs"),
+    let (deprecated_endpoints, current_endpoints): (Vec<_>, Vec<_>) =
+        openapi_endpoints.iter().cloned().partition(|x| x.is_deprecated());
+    let evaluated_endpoints = match config.deprecated_policy {
+        DeprecatedPolicy::Include => openapi_endpoints,
+        DeprecatedPolicy::Exclude | DeprecatedPolicy::ReportOnly => current_endpoints,
     };
 
     let evaluation = evaluate(
-        &openapi_endpoints,
+        &evaluated_endpoints,
         &pre_merge_endpoints,
         &nginx_endpoints,
         &config.groupings,
@@ -110,10 +203,161 @@ pub fn run_eval(
 
     println!("Test Coverage: {}%", evaluation.test_coverage * 100.0);
 
-    if !evaluation.endpoints_not_covered.is_empty() {
+    let parameter_evaluation = &evaluation.parameter_evaluation;
+    if !parameter_evaluation.relevant_parameters.is_empty() {
+        println!("Parameter Coverage: {}%", parameter_evaluation.test_coverage * 100.0);
+    }
+    if !parameter_evaluation.relevant_values.is_empty() {
+        println!(
+            "Parameter Value Coverage: {}%",
+            parameter_evaluation.value_coverage * 100.0
+        );
+    }
+    let request_media_type_evaluation = &evaluation.request_media_type_evaluation;
+    if !request_media_type_evaluation.relevant_media_types.is_empty() {
+        println!(
+            "Request Media Type Coverage: {}%",
+            request_media_type_evaluation.test_coverage * 100.0
+        );
+    }
+    let response_media_type_evaluation = &evaluation.response_media_type_evaluation;
+    if !response_media_type_evaluation.relevant_media_types.is_empty() {
+        println!(
+            "Response Media Type Coverage: {}%",
+            response_media_type_evaluation.test_coverage * 100.0
+        );
+    }
+
+    let mut missed_thresholds = vec![];
+    for threshold in &config.thresholds {
+        match evaluation.get_selector_test_coverage(&threshold.selector) {
+            Some(test_coverage) => {
+                println!("Test Coverage of {}: {}%", threshold.selector, test_coverage * 100.0);
+                if test_coverage < threshold.test_coverage {
+                    missed_thresholds.push(threshold);
+                }
+            }
+            None => println!(
+                "WARNING: the threshold for {} doesn't match any endpoint.",
+                threshold.selector
+            ),
+        }
+    }
+
+    let (generated_endpoints_not_covered, endpoints_not_covered): (Vec<_>, Vec<_>) = evaluation
+        .endpoints_not_covered
+        .into_iter()
+        .partition(|x| x.is_generated);
+
+    if !endpoints_not_covered.is_empty() {
         println!("The following endpoints were missed:");
-        for endpoint in evaluation.endpoints_not_covered {
-            println!("- {} {} {}", endpoint.path, endpoint.method, endpoint.status_code);
+        for endpoint in endpoints_not_covered {
+            println!("- {}", endpoint);
+        }
+    }
+
+    if !generated_endpoints_not_covered.is_empty() {
+        println!("The following responses expected by security requirements were missed:");
+        for endpoint in generated_endpoints_not_covered {
+            println!("- {}", endpoint);
+        }
+    }
+
+    if !parameter_evaluation.parameters_not_covered.is_empty() {
+        println!("The following parameters were never sent:");
+        for parameter in &parameter_evaluation.parameters_not_covered {
+            println!("- {}", parameter);
+        }
+    }
+
+    if !parameter_evaluation.values_not_covered.is_empty() {
+        println!("The following parameter values were never sent:");
+        // missed values are listed in the order of their parameters, so they can be grouped
+        let mut values_not_covered = parameter_evaluation.values_not_covered.iter().peekable();
+        while let Some(value) = values_not_covered.next() {
+            let mut values = vec![value.value];
+            while let Some(next_value) = values_not_covered.next_if(|x| x.parameter == value.parameter) {
+                values.push(next_value.value);
+            }
+            println!("- {}: {}", value.parameter, values.join(", "));
+        }
+    }
+
+    if !request_media_type_evaluation.media_types_not_covered.is_empty() {
+        println!("The following request media types were never sent:");
+        for media_type in &request_media_type_evaluation.media_types_not_covered {
+            println!("- {}", media_type);
+        }
+    }
+
+    if !response_media_type_evaluation.media_types_not_covered.is_empty() {
+        println!("The following response media types were never returned:");
+        for media_type in &response_media_type_evaluation.media_types_not_covered {
+            println!("- {}", media_type);
+        }
+    }
+
+    print_deprecated_endpoints(config, &deprecated_endpoints, &pre_merge_endpoints, &nginx_endpoints);
+
+    if let Some(parameter_value_coverage) = config.parameter_value_coverage {
+        if parameter_evaluation.value_coverage < parameter_value_coverage {
+            print_error_and_exit(format!(
+                "Error: The parameter value coverage of {}% is below the required {}%.",
+                parameter_evaluation.value_coverage * 100.0,
+                parameter_value_coverage * 100.0
+            ));
+        }
+    }
+    if let Some(threshold) = missed_thresholds.first() {
+        print_error_and_exit(format!(
+            "Error: The test coverage of {} is below the required {}%.",
+            threshold.selector,
+            threshold.test_coverage * 100.0
+        ));
+    }
+}
+
+/// Reports deprecated endpoints in their own section, so endpoints tests still rely on can be
+/// spotted before they are removed.
+fn print_deprecated_endpoints(
+    config: &CoveAPIConfig,
+    deprecated_endpoints: &Vec<EndpointConfiguration>,
+    pre_merge_endpoints: &Option<Vec<EndpointConfiguration>>,
+    nginx_endpoints: &Vec<EndpointConfiguration>,
+) {
+    if deprecated_endpoints.is_empty() {
+        return;
+    }
+
+    let deprecated_evaluation = evaluate(
+        deprecated_endpoints,
+        pre_merge_endpoints,
+        nginx_endpoints,
+        &config.groupings,
+    );
+    let tested_count =
+        deprecated_evaluation.relevant_endpoints.len() - deprecated_evaluation.endpoints_not_covered.len();
+
+    match config.deprecated_policy {
+        DeprecatedPolicy::Exclude => {
+            println!(
+                "{} deprecated endpoint(s) were excluded from the evaluation, {} of them are still called by tests.",
+                deprecated_evaluation.relevant_endpoints.len(),
+                tested_count
+            );
+            return;
+        }
+        DeprecatedPolicy::Include => println!("Deprecated endpoints (included in the test coverage):"),
+        DeprecatedPolicy::ReportOnly => println!("Deprecated endpoints (not included in the test coverage):"),
+    }
+
+    let mut relevant_endpoints = deprecated_evaluation.relevant_endpoints.clone();
+    relevant_endpoints.sort_by_key(|x| x.to_string());
+    for endpoint in relevant_endpoints {
+        if deprecated_evaluation.endpoints_not_covered.contains(&endpoint) {
+            println!("- {} no longer called by tests", endpoint);
+        } else {
+            println!("- {} still called by tests", endpoint);
         }
     }
 }
@@ -1,12 +1,28 @@
-use coveapi::{initialize_coveapi, run_eval, run_nginx};
+use coveapi::{
+    config::Proxy, initialize_config, initialize_coveapi, parse_har_files, parse_nginx_logs, run_eval, run_nginx,
+    run_proxy, run_validation,
+};
 
 fn main() {
-    let (config, openapi_endpoints, pre_merge_endpoints) = initialize_coveapi();
+    let config = initialize_config();
 
     if config.debug {
         config.print();
     }
-    run_nginx(&config);
+    if config.validate {
+        run_validation(&config);
+    }
+
+    let (openapi_endpoints, pre_merge_endpoints) = initialize_coveapi(&config);
+    let mut nginx_endpoints = match config.proxy {
+        Proxy::Nginx => {
+            run_nginx(&config, &openapi_endpoints);
+            parse_nginx_logs(&config, &openapi_endpoints)
+        }
+        Proxy::Builtin => run_proxy(&config),
+        Proxy::None => vec![],
+    };
+    nginx_endpoints.append(&mut parse_har_files(&config));
 
-    run_eval(&config, openapi_endpoints, pre_merge_endpoints);
+    run_eval(&config, openapi_endpoints, pre_merge_endpoints, nginx_endpoints);
 }
@@ -0,0 +1,118 @@
+use std::{collections::BTreeMap, fmt::Display, str::FromStr, sync::Arc};
+
+use url::Url;
+
+use super::operation::{Operation, SecurityRequirement};
+
+const DEFAULT_SCHEME: &str = "http";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpenapiVersion {
+    Swagger2,
+    Openapi3,
+}
+
+/// Format independent representation of an openapi spec. Both json and yaml specs are loaded into
+/// it, after all references have been resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenapiDocument {
+    pub version: OpenapiVersion,
+    pub servers: Vec<Server>,
+    /// `host` of a Swagger 2.0 spec, including the port if one is specified.
+    pub host: Option<String>,
+    pub schemes: Vec<String>,
+    pub security: Option<Vec<SecurityRequirement>>,
+    pub operations: Vec<Arc<Operation>>,
+}
+
+/// A server the api is reachable on. Swagger 2.0 specs are represented by a single server with
+/// the `basePath` as url.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Server {
+    pub url: String,
+    pub variables: BTreeMap<String, String>,
+}
+
+/// Position of an element in the spec, both line and column start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl OpenapiDocument {
+    /// Url of the application described by the `host` and `schemes` of a Swagger 2.0 spec, the
+    /// first scheme listed is used.
+    pub fn get_app_base_url(&self) -> Option<Url> {
+        let host = self.host.as_ref()?;
+        let scheme = match self.schemes.first() {
+            Some(scheme) => scheme.as_str(),
+            None => DEFAULT_SCHEME,
+        };
+        Url::from_str(&format!("{}://{}", scheme, host)).ok()
+    }
+
+    /// Security requirements of an operation, operations that don't declare their own inherit
+    /// the ones of the document.
+    pub fn get_security_requirements<'a>(&'a self, operation: &'a Operation) -> &'a [SecurityRequirement] {
+        match (&operation.security, &self.security) {
+            (Some(requirements), _) | (None, Some(requirements)) => requirements,
+            (None, None) => &[],
+        }
+    }
+
+    /// Checks if every request to the operation has to be authenticated. An empty requirement
+    /// (`{}`) makes authentication optional.
+    pub fn requires_authentication(&self, operation: &Operation) -> bool {
+        let requirements = self.get_security_requirements(operation);
+        !requirements.is_empty() && requirements.iter().all(|x| !x.is_empty())
+    }
+}
+
+impl Server {
+    pub fn new(url: &str) -> Server {
+        Server {
+            url: url.to_string(),
+            variables: BTreeMap::new(),
+        }
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OpenapiDocument, OpenapiVersion};
+
+    fn create_document(host: Option<&str>, schemes: Vec<&str>) -> OpenapiDocument {
+        OpenapiDocument {
+            version: OpenapiVersion::Swagger2,
+            servers: vec![],
+            host: host.map(|x| x.to_string()),
+            schemes: schemes.into_iter().map(|x| x.to_string()).collect(),
+            security: None,
+            operations: vec![],
+        }
+    }
+
+    #[test]
+    fn builds_app_base_url_from_host_and_scheme() {
+        let document = create_document(Some("localhost:8080"), vec!["https", "http"]);
+        assert_eq!(document.get_app_base_url().unwrap().as_str(), "https://localhost:8080/");
+    }
+
+    #[test]
+    fn defaults_to_http_without_schemes() {
+        let document = create_document(Some("example.com"), vec![]);
+        assert_eq!(document.get_app_base_url().unwrap().as_str(), "http://example.com/");
+    }
+
+    #[test]
+    fn has_no_app_base_url_without_host() {
+        assert!(create_document(None, vec!["https"]).get_app_base_url().is_none());
+    }
+}
@@ -1,23 +1,69 @@
-use std::{fmt::Display, str::FromStr, sync::Arc};
-
-use crate::{config::Runtime, utils::Error};
-
-use super::misc::Method;
-
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+    str::FromStr,
+    sync::Arc,
+};
+
+use crate::{
+    config::{PathNormalization, Runtime},
+    utils::Error,
+};
+
+use super::{
+    media::MediaType,
+    misc::Method,
+    operation::{Operation, ParameterLocation},
+    status::StatusCode,
+};
+
+#[derive(Debug, Clone)]
 pub struct EndpointConfiguration {
     pub method: Method,
     pub path: OpenapiPath,
-    pub status_code: u16,
+    pub status_code: StatusCode,
     pub runtime: Arc<Runtime>,
     pub is_generated: bool,
+    pub operation: Option<Arc<Operation>>,
+    /// Parameters that were sent along with a captured request.
+    pub parameters: Vec<RequestParameter>,
+    /// Media type of the request body. Endpoints of the spec only require one, if the coverage
+    /// accounts for request media types.
+    pub request_media_type: Option<MediaType>,
+    /// Media type of the response body of a captured request.
+    pub response_media_type: Option<MediaType>,
+    /// Query, mutation or field of a GraphQL schema, like `query.user`, since all of them share
+    /// the same path.
+    pub graphql_field: Option<String>,
+}
+
+/// A query parameter or header of a captured request. Header names are always lowercase.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct RequestParameter {
+    pub location: ParameterLocation,
+    pub name: String,
+    pub value: String,
+}
+
+impl RequestParameter {
+    pub fn new(location: ParameterLocation, name: &str, value: &str) -> RequestParameter {
+        let name = match location {
+            ParameterLocation::Header => name.to_lowercase(),
+            _ => name.to_string(),
+        };
+        RequestParameter {
+            location,
+            name,
+            value: value.to_string(),
+        }
+    }
 }
 
 impl EndpointConfiguration {
     pub fn new(
         method: Method,
         openapi_path: &str,
-        status_code: u16,
+        status_code: StatusCode,
         runtime: Arc<Runtime>,
         is_generated: bool,
     ) -> Result<EndpointConfiguration, Error> {
@@ -27,14 +73,145 @@ impl EndpointConfiguration {
             status_code,
             runtime,
             is_generated,
+            operation: None,
+            parameters: vec![],
+            request_media_type: None,
+            response_media_type: None,
+            graphql_field: None,
         })
     }
 
+    /// Links the endpoint to the operation of the openapi spec it was generated from.
+    pub fn with_operation(mut self, operation: &Arc<Operation>) -> EndpointConfiguration {
+        self.operation = Some(operation.clone());
+        self
+    }
+
+    pub fn with_parameters(mut self, parameters: Vec<RequestParameter>) -> EndpointConfiguration {
+        self.parameters = parameters;
+        self
+    }
+
+    pub fn with_request_media_type(mut self, request_media_type: Option<MediaType>) -> EndpointConfiguration {
+        self.request_media_type = request_media_type;
+        self
+    }
+
+    pub fn with_response_media_type(mut self, response_media_type: Option<MediaType>) -> EndpointConfiguration {
+        self.response_media_type = response_media_type;
+        self
+    }
+
+    pub fn with_graphql_field(mut self, graphql_field: Option<String>) -> EndpointConfiguration {
+        self.graphql_field = graphql_field;
+        self
+    }
+
+    /// Splits the endpoint into one endpoint per request media type of its operation, so each of
+    /// them has to be tested on its own.
+    pub fn split_by_request_media_type(self) -> Vec<EndpointConfiguration> {
+        let request_media_types = match &self.operation {
+            Some(operation) if !operation.request_media_types.is_empty() => operation.request_media_types.clone(),
+            _ => return vec![self],
+        };
+        request_media_types
+            .into_iter()
+            .map(|x| self.clone().with_request_media_type(Some(x)))
+            .collect()
+    }
+
+    /// Checks if the operation the endpoint belongs to is marked as deprecated.
+    pub fn is_deprecated(&self) -> bool {
+        match &self.operation {
+            Some(operation) => operation.deprecated,
+            None => false,
+        }
+    }
+
     pub fn incompases_endpoint(&self, other: &EndpointConfiguration) -> bool {
         self.method == other.method
+            && self.status_code.incompases_status_code(&other.status_code)
+            && self.runtime == other.runtime
+            && self
+                .path
+                .incompases_request_path(&other.path, &self.runtime.path_normalization)
+            && match (&self.request_media_type, &other.request_media_type) {
+                (Some(media_type), Some(other_media_type)) => media_type.incompases_media_type(other_media_type),
+                (Some(_), None) => false,
+                (None, _) => true,
+            }
+            && (self.graphql_field.is_none() || self.graphql_field == other.graphql_field)
+    }
+
+    /// Checks if a request was sent to the operation of the endpoint, regardless of its status.
+    pub fn incompases_operation_of(&self, other: &EndpointConfiguration) -> bool {
+        self.method == other.method
+            && self.runtime == other.runtime
+            && self
+                .path
+                .incompases_request_path(&other.path, &self.runtime.path_normalization)
+            && (self.graphql_field.is_none() || self.graphql_field == other.graphql_field)
+    }
+}
+
+/// The operation only describes the endpoint, so it is left out of the identity of endpoints.
+/// Otherwise any change to the spec would make the endpoint count as new in diff mode.
+impl PartialEq for EndpointConfiguration {
+    fn eq(&self, other: &Self) -> bool {
+        self.method == other.method
+            && self.path == other.path
             && self.status_code == other.status_code
             && self.runtime == other.runtime
-            && self.path.incompases_openapi_path(&other.path)
+            && self.is_generated == other.is_generated
+            && self.parameters == other.parameters
+            && self.request_media_type == other.request_media_type
+            && self.response_media_type == other.response_media_type
+            && self.graphql_field == other.graphql_field
+    }
+}
+
+impl Eq for EndpointConfiguration {}
+
+impl Hash for EndpointConfiguration {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.method.hash(state);
+        self.path.hash(state);
+        self.status_code.hash(state);
+        self.runtime.hash(state);
+        self.is_generated.hash(state);
+        self.parameters.hash(state);
+        self.request_media_type.hash(state);
+        self.response_media_type.hash(state);
+        self.graphql_field.hash(state);
+    }
+}
+
+/// Prints the endpoint along with the operationId, summary and tags of its operation, so it can
+/// be recognized without knowing the spec.
+impl Display for EndpointConfiguration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.path, self.method, self.status_code)?;
+        if let Some(request_media_type) = &self.request_media_type {
+            write!(f, " {}", request_media_type)?;
+        }
+
+        let operation = match &self.operation {
+            Some(operation) => operation,
+            None => return Ok(()),
+        };
+        if let Some(operation_id) = &operation.operation_id {
+            write!(f, " {}", operation_id)?;
+        }
+        if let Some(summary) = &operation.summary {
+            write!(f, " \"{}\"", summary)?;
+        }
+        if !operation.tags.is_empty() {
+            write!(f, " [{}]", operation.tags.join(", "))?;
+        }
+        if operation.deprecated {
+            write!(f, " (deprecated)")?;
+        }
+        Ok(())
     }
 }
 
@@ -46,46 +223,107 @@ pub struct OpenapiPath {
 
 impl OpenapiPath {
     pub fn incompases_openapi_path(&self, other: &OpenapiPath) -> bool {
-        let mut parse_index = 0;
-        let other_as_str = &other.original_source;
+        self.get_variable_values(other).is_some()
+    }
+
+    /// Values the variables of the path take in another path it incompases, along with their
+    /// names. `None` if the path doesn't incompase the other one.
+    pub fn get_variable_values(&self, other: &OpenapiPath) -> Option<Vec<(String, String)>> {
+        self.match_segments(other, true)
+    }
+
+    /// Checks if a normalized request path was sent to this path, the case of the path only
+    /// matters if the normalization doesn't ignore it.
+    pub fn incompases_request_path(&self, other: &OpenapiPath, path_normalization: &PathNormalization) -> bool {
+        self.get_request_variable_values(other, path_normalization).is_some()
+    }
 
-        for component_index in 0..self.components.len() {
-            let component = &self.components[component_index];
+    pub fn get_request_variable_values(
+        &self,
+        other: &OpenapiPath,
+        path_normalization: &PathNormalization,
+    ) -> Option<Vec<(String, String)>> {
+        self.match_segments(other, !path_normalization.case_insensitive)
+    }
+
+    /// Matches the path segment by segment, since variables never span several segments.
+    fn match_segments(&self, other: &OpenapiPath, case_sensitive: bool) -> Option<Vec<(String, String)>> {
+        let segments = self.get_segments();
+        let other_segments: Vec<&str> = other.original_source.split('/').collect();
+        if segments.len() != other_segments.len() {
+            return None;
+        }
+
+        let mut variable_values = vec![];
+        for (components, other_segment) in segments.iter().zip(other_segments) {
+            if !match_segment(components, other_segment, case_sensitive, &mut variable_values) {
+                return None;
+            }
+        }
+        Some(variable_values)
+    }
+
+    fn get_segments(&self) -> Vec<Vec<OpenapiPathComponent>> {
+        let mut segments = vec![vec![]];
+        for component in &self.components {
             match component {
                 OpenapiPathComponent::Fixed(fixed) => {
-                    if fixed.len() + parse_index > other_as_str.len()
-                        || fixed != &other_as_str[parse_index..parse_index + fixed.len()]
-                    {
-                        return false;
-                    }
-                    parse_index += fixed.len();
-                }
-                OpenapiPathComponent::Variable => {
-                    const EMPTY_NEXT_STRING: &str = "";
-                    let next_string = match self.components.get(component_index + 1) {
-                        Some(next_component) => match next_component {
-                            OpenapiPathComponent::Fixed(original_source) => original_source,
-                            OpenapiPathComponent::Variable => EMPTY_NEXT_STRING,
-                        },
-                        None => EMPTY_NEXT_STRING,
-                    };
-
-                    while parse_index < other_as_str.len() {
-                        if &other_as_str[parse_index..parse_index + 1] == "/"
-                            || (next_string != EMPTY_NEXT_STRING
-                                && other_as_str.len() > next_string.len() + parse_index
-                                && &other_as_str[parse_index..parse_index + next_string.len()] == next_string)
-                        {
-                            break;
+                    for (index, fixed_part) in fixed.split('/').enumerate() {
+                        if index > 0 {
+                            segments.push(vec![]);
+                        }
+                        if !fixed_part.is_empty() {
+                            // unwrap is fine, since there always is a segment
+                            segments
+                                .last_mut()
+                                .unwrap()
+                                .push(OpenapiPathComponent::Fixed(fixed_part.to_string()));
                         }
-                        parse_index += 1;
                     }
                 }
+                OpenapiPathComponent::Variable(_) => segments.last_mut().unwrap().push(component.clone()),
             }
         }
+        segments
+    }
+}
 
-        parse_index == other_as_str.len()
+/// Matches the components of a segment against the segment of another path, variables take the
+/// value up to the next fixed part of the segment.
+fn match_segment(
+    components: &[OpenapiPathComponent],
+    other_segment: &str,
+    case_sensitive: bool,
+    variable_values: &mut Vec<(String, String)>,
+) -> bool {
+    let mut rest = other_segment;
+    for (index, component) in components.iter().enumerate() {
+        match component {
+            OpenapiPathComponent::Fixed(fixed) => match rest.get(..fixed.len()) {
+                Some(prefix) if prefix == fixed || (!case_sensitive && prefix.eq_ignore_ascii_case(fixed)) => {
+                    rest = &rest[fixed.len()..];
+                }
+                _ => return false,
+            },
+            OpenapiPathComponent::Variable(name) => {
+                let value_end = match components.get(index + 1) {
+                    Some(OpenapiPathComponent::Fixed(next_fixed)) if case_sensitive => rest.find(next_fixed.as_str()),
+                    // lowercasing ascii characters keeps the indices intact
+                    Some(OpenapiPathComponent::Fixed(next_fixed)) => {
+                        rest.to_ascii_lowercase().find(next_fixed.to_ascii_lowercase().as_str())
+                    }
+                    _ => Some(rest.len()),
+                };
+                let value_end = match value_end {
+                    Some(value_end) => value_end,
+                    None => return false,
+                };
+                variable_values.push((name.to_string(), rest[..value_end].to_string()));
+                rest = &rest[value_end..];
+            }
+        }
     }
+    rest.is_empty()
 }
 
 impl Display for OpenapiPath {
@@ -110,7 +348,7 @@ impl FromStr for OpenapiPath {
                     path.push(OpenapiPathComponent::Fixed(cached_component.to_string()));
                     cached_component = String::new();
                 }
-                path.push(OpenapiPathComponent::Variable);
+                path.push(OpenapiPathComponent::Variable(current_component));
                 current_component = String::new();
             } else if !is_in_variable && character.to_string() == "{" {
                 is_in_variable = true;
@@ -143,17 +381,96 @@ impl FromStr for OpenapiPath {
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
 pub enum OpenapiPathComponent {
     Fixed(String),
-    Variable,
+    Variable(String),
 }
 
 #[cfg(test)]
 mod tests {
     use std::{str::FromStr, sync::Arc};
 
-    use crate::{models::Method, utils::test::create_mock_runtime};
+    use crate::{
+        config::PathNormalization,
+        models::{MediaType, Method, Operation, StatusCode},
+        utils::test::{create_mock_operation, create_mock_runtime},
+    };
 
     use super::{EndpointConfiguration, OpenapiPath, OpenapiPathComponent};
 
+    #[test]
+    fn displays_operation_of_endpoint() {
+        let endpoint = EndpointConfiguration::new(
+            Method::GET,
+            "/users/{id}",
+            StatusCode::Exact(404),
+            Arc::new(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
+        assert_eq!(endpoint.to_string(), "/users/{id} GET 404");
+
+        let operation = Operation {
+            operation_id: Some("getUser".to_string()),
+            summary: Some("Get a user".to_string()),
+            deprecated: true,
+            tags: vec!["users".to_string(), "admin".to_string()],
+            ..create_mock_operation(Method::GET, "/users/{id}")
+        };
+        assert_eq!(
+            endpoint.with_operation(&Arc::new(operation)).to_string(),
+            "/users/{id} GET 404 getUser \"Get a user\" [users, admin] (deprecated)"
+        );
+    }
+
+    #[test]
+    fn splits_endpoint_by_request_media_type() {
+        let operation = Operation {
+            request_media_types: vec![MediaType::new("application/json"), MediaType::new("image/*")],
+            ..create_mock_operation(Method::POST, "/uploads")
+        };
+        let endpoints = EndpointConfiguration::new(
+            Method::POST,
+            "/uploads",
+            StatusCode::Exact(201),
+            Arc::new(create_mock_runtime()),
+            false,
+        )
+        .unwrap()
+        .with_operation(&Arc::new(operation))
+        .split_by_request_media_type();
+        assert_eq!(endpoints.len(), 2);
+
+        let request = EndpointConfiguration::new(
+            Method::POST,
+            "/uploads",
+            StatusCode::Exact(201),
+            Arc::new(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
+        assert!(!endpoints[1].incompases_endpoint(&request));
+        assert!(endpoints[1].incompases_endpoint(&request.with_request_media_type(Some(MediaType::new("image/png")))));
+    }
+
+    #[test]
+    fn matches_graphql_field_of_request() {
+        let create_endpoint = |status_code, graphql_field: Option<&str>| {
+            EndpointConfiguration::new(
+                Method::POST,
+                "/graphql",
+                status_code,
+                Arc::new(create_mock_runtime()),
+                false,
+            )
+            .unwrap()
+            .with_graphql_field(graphql_field.map(|x| x.to_string()))
+        };
+        let endpoint = create_endpoint(StatusCode::Range(2), Some("query.user"));
+
+        assert!(endpoint.incompases_endpoint(&create_endpoint(StatusCode::Exact(200), Some("query.user"))));
+        assert!(!endpoint.incompases_endpoint(&create_endpoint(StatusCode::Exact(200), Some("query.users"))));
+        assert!(!endpoint.incompases_endpoint(&create_endpoint(StatusCode::Exact(200), None)));
+    }
+
     #[test]
     fn parses_fixed_path() {
         let expected = OpenapiPath {
@@ -170,7 +487,7 @@ mod tests {
         let expected = OpenapiPath {
             components: vec![
                 OpenapiPathComponent::Fixed("/foo/".to_string()),
-                OpenapiPathComponent::Variable,
+                OpenapiPathComponent::Variable("bar".to_string()),
                 OpenapiPathComponent::Fixed("/moo".to_string()),
             ],
             original_source: "/foo/{bar}/moo".to_string(),
@@ -206,7 +523,7 @@ mod tests {
         let expected = OpenapiPath {
             components: vec![
                 OpenapiPathComponent::Fixed("/foo/".to_string()),
-                OpenapiPathComponent::Variable,
+                OpenapiPathComponent::Variable("bar".to_string()),
             ],
             original_source: "/foo/{bar}".to_string(),
         };
@@ -232,30 +549,66 @@ mod tests {
 
     #[test]
     fn expoints_with_different_methods_dont_encompas_eachother() {
-        let endpoint_cfg_a =
-            EndpointConfiguration::new(Method::PUT, "/foo/bar", 200, Arc::from(create_mock_runtime()), false).unwrap();
-        let endpoint_cfg_b =
-            EndpointConfiguration::new(Method::GET, "/foo/bar", 200, Arc::from(create_mock_runtime()), false).unwrap();
+        let endpoint_cfg_a = EndpointConfiguration::new(
+            Method::PUT,
+            "/foo/bar",
+            StatusCode::Exact(200),
+            Arc::from(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
+        let endpoint_cfg_b = EndpointConfiguration::new(
+            Method::GET,
+            "/foo/bar",
+            StatusCode::Exact(200),
+            Arc::from(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
 
         assert!(!endpoint_cfg_a.incompases_endpoint(&endpoint_cfg_b));
     }
 
     #[test]
     fn expoints_with_different_status_codes_dont_encompas_eachother() {
-        let endpoint_cfg_a =
-            EndpointConfiguration::new(Method::GET, "/foo/bar", 400, Arc::from(create_mock_runtime()), false).unwrap();
-        let endpoint_cfg_b =
-            EndpointConfiguration::new(Method::GET, "/foo/bar", 200, Arc::from(create_mock_runtime()), false).unwrap();
+        let endpoint_cfg_a = EndpointConfiguration::new(
+            Method::GET,
+            "/foo/bar",
+            StatusCode::Exact(400),
+            Arc::from(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
+        let endpoint_cfg_b = EndpointConfiguration::new(
+            Method::GET,
+            "/foo/bar",
+            StatusCode::Exact(200),
+            Arc::from(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
 
         assert!(!endpoint_cfg_a.incompases_endpoint(&endpoint_cfg_b));
     }
 
     #[test]
     fn expoints_with_different_runtimes_dont_encompas_eachother() {
-        let endpoint_cfg_a =
-            EndpointConfiguration::new(Method::GET, "/foo/bar", 400, Arc::from(create_mock_runtime()), false).unwrap();
-        let endpoint_cfg_b =
-            EndpointConfiguration::new(Method::GET, "/foo/bar", 200, Arc::from(create_mock_runtime()), false).unwrap();
+        let endpoint_cfg_a = EndpointConfiguration::new(
+            Method::GET,
+            "/foo/bar",
+            StatusCode::Exact(400),
+            Arc::from(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
+        let endpoint_cfg_b = EndpointConfiguration::new(
+            Method::GET,
+            "/foo/bar",
+            StatusCode::Exact(200),
+            Arc::from(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
 
         assert!(!endpoint_cfg_a.incompases_endpoint(&endpoint_cfg_b));
     }
@@ -276,8 +629,60 @@ mod tests {
         test_incompas_path_with_string("/foo/{bar}/moo", "/foo/{moo}/moo", true);
     }
 
+    #[test]
+    fn extracts_variable_values() {
+        let path = OpenapiPath::from_str("/reports/{year}/{format}.{extension}").unwrap();
+        let other = OpenapiPath::from_str("/reports/2023/summary.csv").unwrap();
+
+        assert_eq!(
+            path.get_variable_values(&other).unwrap(),
+            vec![
+                ("year".to_string(), "2023".to_string()),
+                ("format".to_string(), "summary".to_string()),
+                ("extension".to_string(), "csv".to_string()),
+            ]
+        );
+        assert!(path
+            .get_variable_values(&OpenapiPath::from_str("/reports/2023").unwrap())
+            .is_none());
+    }
+
     #[test]
     fn matches_numerics_as_vairable_in_path() {
         test_incompas_path_with_string("/foo/{bar}", "/foo/69", true);
     }
+
+    #[test]
+    fn matches_multi_byte_paths() {
+        test_incompas_path_with_string("/caf\u{e9}s/{name}", "/caf\u{e9}s/gr\u{fc}n", true);
+        test_incompas_path_with_string("/caf\u{e9}", "/\u{e9}\u{e9}\u{e9}\u{e9}", false);
+        test_incompas_path_with_string("/{a}.json", "/\u{e9}.json", true);
+    }
+
+    #[test]
+    fn matches_fixed_suffix_of_variable() {
+        let path = OpenapiPath::from_str("/files/{name}.json").unwrap();
+        assert_eq!(
+            path.get_variable_values(&OpenapiPath::from_str("/files/report.json").unwrap())
+                .unwrap(),
+            vec![("name".to_string(), "report".to_string())]
+        );
+        assert!(!path.incompases_openapi_path(&OpenapiPath::from_str("/files/report.csv").unwrap()));
+    }
+
+    #[test]
+    fn ignores_case_if_configured() {
+        let path = OpenapiPath::from_str("/Users/{id}/Orders").unwrap();
+        let other = OpenapiPath::from_str("/users/AB/orders").unwrap();
+        assert!(!path.incompases_request_path(&other, &PathNormalization::default()));
+
+        let path_normalization = PathNormalization {
+            case_insensitive: true,
+            ..PathNormalization::default()
+        };
+        assert_eq!(
+            path.get_request_variable_values(&other, &path_normalization).unwrap(),
+            vec![("id".to_string(), "AB".to_string())]
+        );
+    }
 }
@@ -1,25 +1,33 @@
-use super::{EndpointConfiguration, Method, OpenapiPath};
+use super::{EndpointConfiguration, EndpointSelector, Method, StatusCode};
 
 #[derive(Debug, Hash, PartialEq, Eq)]
 pub struct Grouping {
     methods: Vec<Method>,
-    status: Vec<u16>,
-    path: OpenapiPath,
+    status: Vec<StatusCode>,
+    selector: EndpointSelector,
     pub is_ignore_group: bool,
 }
 
 impl Grouping {
     pub fn incompases_endpoint_config(&self, endpoint: &EndpointConfiguration) -> bool {
         self.methods.contains(&endpoint.method)
-            && self.status.contains(&endpoint.status_code)
-            && self.path.incompases_openapi_path(&endpoint.path)
+            && self
+                .status
+                .iter()
+                .any(|x| x.incompases_status_code(&endpoint.status_code))
+            && self.selector.incompases_endpoint_config(endpoint)
     }
 
-    pub fn new(methods: Vec<Method>, status: Vec<u16>, path: OpenapiPath, is_ignore_group: bool) -> Grouping {
+    pub fn new(
+        methods: Vec<Method>,
+        status: Vec<StatusCode>,
+        selector: EndpointSelector,
+        is_ignore_group: bool,
+    ) -> Grouping {
         Grouping {
             methods,
             status,
-            path,
+            selector,
             is_ignore_group,
         }
     }
@@ -30,7 +38,7 @@ mod tests {
     use std::{str::FromStr, sync::Arc};
 
     use crate::{
-        models::{EndpointConfiguration, Method, OpenapiPath},
+        models::{EndpointConfiguration, EndpointSelector, Method, OpenapiPath, StatusCode},
         utils::test::create_mock_runtime,
     };
 
@@ -40,12 +48,18 @@ mod tests {
     fn grouping_detects_incompased_endpoint() {
         let grouping = Grouping {
             methods: vec![Method::GET],
-            status: vec![200],
-            path: OpenapiPath::from_str("/foo/{bar}").unwrap(),
+            status: vec![StatusCode::Exact(200)],
+            selector: EndpointSelector::Path(OpenapiPath::from_str("/foo/{bar}").unwrap()),
             is_ignore_group: false,
         };
-        let endpoint =
-            EndpointConfiguration::new(Method::GET, "/foo/69", 200, Arc::from(create_mock_runtime()), false).unwrap();
+        let endpoint = EndpointConfiguration::new(
+            Method::GET,
+            "/foo/69",
+            StatusCode::Exact(200),
+            Arc::from(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
 
         assert!(grouping.incompases_endpoint_config(&endpoint));
     }
@@ -54,12 +68,18 @@ mod tests {
     fn different_status_leads_to_not_incompased() {
         let grouping = Grouping {
             methods: vec![Method::POST],
-            status: vec![418],
-            path: OpenapiPath::from_str("/foo/{bar}").unwrap(),
+            status: vec![StatusCode::Exact(418)],
+            selector: EndpointSelector::Path(OpenapiPath::from_str("/foo/{bar}").unwrap()),
             is_ignore_group: false,
         };
-        let endpoint =
-            EndpointConfiguration::new(Method::GET, "/foo/69", 200, Arc::from(create_mock_runtime()), false).unwrap();
+        let endpoint = EndpointConfiguration::new(
+            Method::GET,
+            "/foo/69",
+            StatusCode::Exact(200),
+            Arc::from(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
 
         assert!(!grouping.incompases_endpoint_config(&endpoint));
     }
@@ -68,12 +88,18 @@ mod tests {
     fn different_method_leads_to_not_incompased() {
         let grouping = Grouping {
             methods: vec![Method::POST],
-            status: vec![200],
-            path: OpenapiPath::from_str("/foo/{bar}").unwrap(),
+            status: vec![StatusCode::Exact(200)],
+            selector: EndpointSelector::Path(OpenapiPath::from_str("/foo/{bar}").unwrap()),
             is_ignore_group: false,
         };
-        let endpoint =
-            EndpointConfiguration::new(Method::GET, "/foo/69", 200, Arc::from(create_mock_runtime()), false).unwrap();
+        let endpoint = EndpointConfiguration::new(
+            Method::GET,
+            "/foo/69",
+            StatusCode::Exact(200),
+            Arc::from(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
 
         assert!(!grouping.incompases_endpoint_config(&endpoint));
     }
@@ -0,0 +1,259 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::utils::Error;
+
+const ESCAPE_PREFIX: &str = "escape=";
+const ESCAPE_DEFAULT: &str = "default";
+const ESCAPE_JSON: &str = "json";
+const ESCAPE_NONE: &str = "none";
+
+const DEFAULT_LOG_FORMAT: &str =
+    "[$time_local] \"$request\" $status $server_port \"$content_type\" \"$sent_http_content_type\"";
+
+/// How nginx escapes the values of variables it logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogEscape {
+    /// Quotes, backslashes and non printable characters are replaced by `\xHH`.
+    Default,
+    /// Values are escaped to be used within JSON strings.
+    Json,
+    None,
+}
+
+/// Part of a log format, either text written as is or a variable whose value is logged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogSegment {
+    Literal(String),
+    /// Name of the variable without the leading `$`.
+    Variable(String),
+}
+
+/// An nginx `log_format`, which is written into the nginx configuration and used to parse the
+/// lines of the resulting log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFormat {
+    pub escape: LogEscape,
+    format: String,
+}
+
+impl LogFormat {
+    pub fn segments(&self) -> Vec<LogSegment> {
+        let mut segments = vec![];
+        let mut literal = String::new();
+        let mut rest = self.format.as_str();
+        while let Some(index) = rest.find('$') {
+            literal.push_str(&rest[..index]);
+            rest = &rest[index + 1..];
+
+            let (variable, length) = match rest.strip_prefix('{') {
+                Some(braced) => match braced.find('}') {
+                    Some(end) => (&braced[..end], end + 2),
+                    None => ("", 0),
+                },
+                None => {
+                    let end = rest
+                        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                        .unwrap_or(rest.len());
+                    (&rest[..end], end)
+                }
+            };
+            if variable.is_empty() {
+                // nginx only treats `$` followed by a name as a variable
+                literal.push('$');
+                continue;
+            }
+
+            if !literal.is_empty() {
+                segments.push(LogSegment::Literal(literal));
+                literal = String::new();
+            }
+            segments.push(LogSegment::Variable(variable.to_string()));
+            rest = &rest[length..];
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(LogSegment::Literal(literal));
+        }
+        segments
+    }
+
+    pub fn has_variable(&self, variable: &str) -> bool {
+        self.segments()
+            .iter()
+            .any(|x| matches!(x, LogSegment::Variable(name) if name == variable))
+    }
+
+    /// Adds a variable to the end of the format, as another quoted value or as another member of
+    /// a JSON object.
+    pub fn with_variable(mut self, variable: &str) -> LogFormat {
+        if self.has_variable(variable) {
+            return self;
+        }
+        match (self.escape, self.format.rfind('}')) {
+            (LogEscape::Json, Some(object_end)) => {
+                self.format
+                    .insert_str(object_end, &format!(",\"{}\":\"${}\"", variable, variable));
+            }
+            _ => self.format.push_str(&format!(" \"${}\"", variable)),
+        }
+        self
+    }
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat {
+            escape: LogEscape::Default,
+            format: DEFAULT_LOG_FORMAT.to_string(),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = Error;
+
+    /// Parses the parameters of an nginx `log_format` after its name, like
+    /// `escape=json '{"request":"$request"}'`. The quotes can be left out for a single string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rest = s.trim().trim_end_matches(';').trim_end();
+        let mut escape = LogEscape::Default;
+        if let Some(escape_str) = rest.strip_prefix(ESCAPE_PREFIX) {
+            let (escape_str, format) = escape_str.split_once(char::is_whitespace).unwrap_or((escape_str, ""));
+            escape = match escape_str {
+                ESCAPE_DEFAULT => LogEscape::Default,
+                ESCAPE_JSON => LogEscape::Json,
+                ESCAPE_NONE => LogEscape::None,
+                _ => return Err(Error::InvalidLogFormat(s.to_string())),
+            };
+            rest = format.trim_start();
+        }
+
+        let format = if rest.starts_with('\'') || rest.starts_with('"') {
+            parse_quoted_strings(rest).ok_or_else(|| Error::InvalidLogFormat(s.to_string()))?
+        } else {
+            rest.to_string()
+        };
+        let log_format = LogFormat { escape, format };
+
+        // the endpoint of a request can only be determined with these variables
+        let has_request = log_format.has_variable("request")
+            || (log_format.has_variable("request_method") && log_format.has_variable("request_uri"));
+        if !has_request || !log_format.has_variable("status") || !log_format.has_variable("server_port") {
+            return Err(Error::InvalidLogFormat(s.to_string()));
+        }
+        Ok(log_format)
+    }
+}
+
+/// Concatenates quoted strings, like nginx does with the strings of a `log_format`.
+fn parse_quoted_strings(s: &str) -> Option<String> {
+    let mut format = String::new();
+    let mut chars = s.chars();
+    while let Some(quote) = chars.next() {
+        if quote.is_whitespace() {
+            continue;
+        }
+        if quote != '\'' && quote != '"' {
+            return None;
+        }
+        loop {
+            match chars.next()? {
+                '\\' => match chars.next()? {
+                    'n' => format.push('\n'),
+                    'r' => format.push('\r'),
+                    't' => format.push('\t'),
+                    c @ ('\'' | '"' | '\\') => format.push(c),
+                    c => {
+                        format.push('\\');
+                        format.push(c);
+                    }
+                },
+                c if c == quote => break,
+                c => format.push(c),
+            }
+        }
+    }
+    Some(format)
+}
+
+impl Display for LogFormat {
+    /// Writes the format the way it's declared within the nginx configuration.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.escape {
+            LogEscape::Default => (),
+            LogEscape::Json => write!(f, "{}{} ", ESCAPE_PREFIX, ESCAPE_JSON)?,
+            LogEscape::None => write!(f, "{}{} ", ESCAPE_PREFIX, ESCAPE_NONE)?,
+        }
+        write!(f, "'{}'", self.format.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{LogEscape, LogFormat, LogSegment};
+
+    #[test]
+    fn splits_format_into_segments() {
+        let log_format = LogFormat::from_str("$request ${status}ms $server_port $ \"$http_x_id\"").unwrap();
+        assert_eq!(
+            log_format.segments(),
+            vec![
+                LogSegment::Variable("request".to_string()),
+                LogSegment::Literal(" ".to_string()),
+                LogSegment::Variable("status".to_string()),
+                LogSegment::Literal("ms ".to_string()),
+                LogSegment::Variable("server_port".to_string()),
+                LogSegment::Literal(" $ \"".to_string()),
+                LogSegment::Variable("http_x_id".to_string()),
+                LogSegment::Literal("\"".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_quoted_json_format() {
+        let log_format = LogFormat::from_str(
+            "escape=json '{\"request\":\"$request\",' '\"status\":$status,\"port\":$server_port}';",
+        )
+        .unwrap();
+        assert_eq!(log_format.escape, LogEscape::Json);
+        assert_eq!(
+            log_format.to_string(),
+            "escape=json '{\"request\":\"$request\",\"status\":$status,\"port\":$server_port}'"
+        );
+    }
+
+    #[test]
+    fn requires_variables_of_endpoint() {
+        assert!(LogFormat::from_str("$request_method $request_uri $status $server_port").is_ok());
+        assert!(LogFormat::from_str("$request $status").is_err());
+        assert!(LogFormat::from_str("$request_method $status $server_port").is_err());
+        assert!(LogFormat::from_str("escape=xml '$request $status $server_port'").is_err());
+        assert!(LogFormat::from_str("'$request $status $server_port").is_err());
+    }
+
+    #[test]
+    fn adds_variables() {
+        let log_format = LogFormat::from_str("$request $status $server_port")
+            .unwrap()
+            .with_variable("http_x_id")
+            .with_variable("status");
+        assert_eq!(log_format.to_string(), "'$request $status $server_port \"$http_x_id\"'");
+
+        let log_format = LogFormat::from_str("escape=json '{\"r\":\"$request\",\"s\":$status,\"p\":$server_port}'")
+            .unwrap()
+            .with_variable("request_body");
+        assert_eq!(
+            log_format.to_string(),
+            "escape=json '{\"r\":\"$request\",\"s\":$status,\"p\":$server_port,\"request_body\":\"$request_body\"}'"
+        );
+    }
+
+    #[test]
+    fn escapes_quotes_of_declaration() {
+        let log_format = LogFormat::from_str("'it\\'s $request $status $server_port'").unwrap();
+        assert_eq!(log_format.to_string(), "'it\\'s $request $status $server_port'");
+    }
+}
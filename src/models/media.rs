@@ -0,0 +1,61 @@
+use std::fmt::Display;
+
+const MEDIA_TYPE_WILDCARD: &str = "*";
+
+/// Media type of a request or response body, without parameters like `charset`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MediaType {
+    media_type: String,
+}
+
+impl MediaType {
+    pub fn new(media_type_str: &str) -> MediaType {
+        let media_type = match media_type_str.split_once(';') {
+            Some((media_type, _)) => media_type,
+            None => media_type_str,
+        };
+        MediaType {
+            media_type: media_type.trim().to_lowercase(),
+        }
+    }
+
+    /// Checks whether a media type is part of this one, which might be a wildcard like `*/*` or
+    /// `image/*`.
+    pub fn incompases_media_type(&self, other: &MediaType) -> bool {
+        match (self.media_type.split_once('/'), other.media_type.split_once('/')) {
+            (Some((main_type, sub_type)), Some((other_main_type, other_sub_type))) => {
+                (main_type == MEDIA_TYPE_WILDCARD || main_type == other_main_type)
+                    && (sub_type == MEDIA_TYPE_WILDCARD || sub_type == other_sub_type)
+            }
+            _ => self == other,
+        }
+    }
+}
+
+impl Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.media_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MediaType;
+
+    #[test]
+    fn ignores_parameters_and_case() {
+        assert_eq!(
+            MediaType::new("Application/JSON; charset=utf-8"),
+            MediaType::new("application/json")
+        );
+    }
+
+    #[test]
+    fn wildcards_incompase_media_types() {
+        let json = MediaType::new("application/json");
+        assert!(MediaType::new("*/*").incompases_media_type(&json));
+        assert!(MediaType::new("application/*").incompases_media_type(&json));
+        assert!(!MediaType::new("text/*").incompases_media_type(&json));
+        assert!(!json.incompases_media_type(&MediaType::new("application/*")));
+    }
+}
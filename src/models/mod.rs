@@ -1,8 +1,34 @@
+mod document;
 mod endpoint;
+mod exchange;
 mod grouping;
+mod log_format;
+mod media;
 mod misc;
+mod operation;
+mod selector;
+mod status;
+mod threshold;
 
+pub use document::OpenapiDocument;
+pub use document::OpenapiVersion;
+pub use document::Server;
+pub use document::SourceLocation;
 pub use endpoint::EndpointConfiguration;
 pub use endpoint::OpenapiPath;
+pub use endpoint::RequestParameter;
+pub use exchange::Exchange;
 pub use grouping::Grouping;
+pub use log_format::LogEscape;
+pub use log_format::LogFormat;
+pub use log_format::LogSegment;
+pub use media::MediaType;
 pub use misc::Method;
+pub use operation::Operation;
+pub use operation::Parameter;
+pub use operation::ParameterLocation;
+pub use operation::Response;
+pub use operation::SecurityRequirement;
+pub use selector::EndpointSelector;
+pub use status::StatusCode;
+pub use threshold::Threshold;
@@ -0,0 +1,189 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::utils::Error;
+
+use super::{
+    document::{Server, SourceLocation},
+    endpoint::RequestParameter,
+    media::MediaType,
+    misc::Method,
+    status::StatusCode,
+};
+
+const LOCATION_QUERY_STR: &str = "query";
+const LOCATION_HEADER_STR: &str = "header";
+const LOCATION_PATH_STR: &str = "path";
+const LOCATION_COOKIE_STR: &str = "cookie";
+const LOCATION_BODY_STR: &str = "body";
+const LOCATION_FORM_DATA_STR: &str = "formData";
+
+/// Security schemes of a requirement, mapped to the scopes they require.
+pub type SecurityRequirement = BTreeMap<String, Vec<String>>;
+
+/// A single operation of the openapi spec, with everything inherited from its path item and the
+/// document already applied.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Operation {
+    pub method: Method,
+    pub path: String,
+    pub operation_id: Option<String>,
+    pub summary: Option<String>,
+    pub deprecated: bool,
+    pub servers: Vec<Server>,
+    pub parameters: Vec<Parameter>,
+    /// Media types the request body can be sent as.
+    pub request_media_types: Vec<MediaType>,
+    pub responses: Vec<Response>,
+    /// Security requirements declared on the operation itself, `None` if it doesn't declare any.
+    pub security: Option<Vec<SecurityRequirement>>,
+    pub tags: Vec<String>,
+    pub extensions: BTreeMap<String, String>,
+    pub location: Option<SourceLocation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Response {
+    pub status_code: StatusCode,
+    /// Media types the response body can be returned as.
+    pub media_types: Vec<MediaType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Parameter {
+    pub name: String,
+    pub location: ParameterLocation,
+    pub required: bool,
+    /// Values the parameter is limited to by an enum or a boolean type, empty if it can take any
+    /// value.
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParameterLocation {
+    Query,
+    Header,
+    Path,
+    Cookie,
+    Body,
+    FormData,
+}
+
+impl Display for ParameterLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let location_str = match self {
+            ParameterLocation::Query => LOCATION_QUERY_STR,
+            ParameterLocation::Header => LOCATION_HEADER_STR,
+            ParameterLocation::Path => LOCATION_PATH_STR,
+            ParameterLocation::Cookie => LOCATION_COOKIE_STR,
+            ParameterLocation::Body => LOCATION_BODY_STR,
+            ParameterLocation::FormData => LOCATION_FORM_DATA_STR,
+        };
+        write!(f, "{}", location_str)
+    }
+}
+
+impl Parameter {
+    pub fn new(name: &str, location_str: &str, required: bool) -> Result<Parameter, Error> {
+        let location = match location_str {
+            LOCATION_QUERY_STR => ParameterLocation::Query,
+            LOCATION_HEADER_STR => ParameterLocation::Header,
+            LOCATION_PATH_STR => ParameterLocation::Path,
+            LOCATION_COOKIE_STR => ParameterLocation::Cookie,
+            LOCATION_BODY_STR => ParameterLocation::Body,
+            LOCATION_FORM_DATA_STR => ParameterLocation::FormData,
+            _ => return Err(Error::InvalidParseParameter(format!("{} in {}", name, location_str))),
+        };
+
+        Ok(Parameter {
+            name: name.to_string(),
+            // path parameters are always required
+            required: required || location == ParameterLocation::Path,
+            location,
+            values: vec![],
+        })
+    }
+
+    pub fn with_values(mut self, values: Vec<String>) -> Parameter {
+        self.values = values;
+        self
+    }
+
+    /// Checks if both parameters describe the same parameter. Header names are case insensitive.
+    pub fn is_same_parameter(&self, other: &Parameter) -> bool {
+        self.location == other.location
+            && match self.location {
+                ParameterLocation::Header => self.name.eq_ignore_ascii_case(&other.name),
+                _ => self.name == other.name,
+            }
+    }
+
+    /// Checks if a parameter sent along with a request is an instance of this parameter. Header
+    /// names are case insensitive.
+    pub fn is_sent_as(&self, request_parameter: &RequestParameter) -> bool {
+        self.location == request_parameter.location
+            && match self.location {
+                ParameterLocation::Header => self.name.eq_ignore_ascii_case(&request_parameter.name),
+                _ => self.name == request_parameter.name,
+            }
+    }
+
+    /// Combines the parameters of a path item with the ones of an operation, the ones of the
+    /// operation override path level ones.
+    pub fn merge(path_parameters: &[Parameter], operation_parameters: Vec<Parameter>) -> Vec<Parameter> {
+        let mut parameters: Vec<Parameter> = path_parameters
+            .iter()
+            .filter(|x| !operation_parameters.iter().any(|y| y.is_same_parameter(x)))
+            .cloned()
+            .collect();
+        parameters.extend(operation_parameters);
+        parameters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Parameter, ParameterLocation};
+
+    #[test]
+    fn parses_parameter_locations() {
+        assert_eq!(
+            Parameter::new("limit", "query", false).unwrap().location,
+            ParameterLocation::Query
+        );
+        assert_eq!(
+            Parameter::new("file", "formData", false).unwrap().location,
+            ParameterLocation::FormData
+        );
+        assert!(Parameter::new("limit", "body-ish", false).is_err());
+    }
+
+    #[test]
+    fn path_parameters_are_always_required() {
+        assert!(Parameter::new("id", "path", false).unwrap().required);
+    }
+
+    #[test]
+    fn operation_parameters_override_path_parameters() {
+        let path_parameters = vec![
+            Parameter::new("limit", "query", false).unwrap(),
+            Parameter::new("X-Request-Id", "header", false).unwrap(),
+        ];
+        let operation_parameters = vec![Parameter::new("limit", "query", true).unwrap()];
+
+        let parameters = Parameter::merge(&path_parameters, operation_parameters);
+
+        assert_eq!(parameters.len(), 2);
+        assert!(parameters.iter().any(|x| x.name == "limit" && x.required));
+    }
+
+    #[test]
+    fn operation_headers_override_path_headers_of_any_case() {
+        let path_parameters = vec![Parameter::new("X-Tenant", "header", false).unwrap()];
+        let operation_parameters = vec![Parameter::new("x-tenant", "header", true).unwrap()];
+
+        let parameters = Parameter::merge(&path_parameters, operation_parameters);
+
+        assert_eq!(parameters.len(), 1);
+        assert!(parameters[0].name == "x-tenant" && parameters[0].required);
+    }
+}
@@ -0,0 +1,137 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::utils::Error;
+
+use super::{EndpointConfiguration, OpenapiPath};
+
+const OPERATION_ID_PREFIX: &str = "operationId:";
+const TAG_PREFIX: &str = "tag:";
+
+/// Selects endpoints either by their path or by the operation of the spec they belong to.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum EndpointSelector {
+    Path(OpenapiPath),
+    OperationId(String),
+    Tag(String),
+}
+
+impl EndpointSelector {
+    pub fn incompases_endpoint_config(&self, endpoint: &EndpointConfiguration) -> bool {
+        match self {
+            EndpointSelector::Path(path) => path.incompases_openapi_path(&endpoint.path),
+            EndpointSelector::OperationId(operation_id) => match &endpoint.operation {
+                Some(operation) => operation.operation_id.as_ref() == Some(operation_id),
+                None => false,
+            },
+            EndpointSelector::Tag(tag) => match &endpoint.operation {
+                Some(operation) => operation.tags.contains(tag),
+                None => false,
+            },
+        }
+    }
+}
+
+impl FromStr for EndpointSelector {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let selector = if let Some(operation_id) = s.strip_prefix(OPERATION_ID_PREFIX) {
+            EndpointSelector::OperationId(operation_id.trim().to_string())
+        } else if let Some(tag) = s.strip_prefix(TAG_PREFIX) {
+            EndpointSelector::Tag(tag.trim().to_string())
+        } else {
+            EndpointSelector::Path(OpenapiPath::from_str(s)?)
+        };
+
+        match &selector {
+            EndpointSelector::OperationId(name) | EndpointSelector::Tag(name) if name.is_empty() => {
+                Err(Error::InvalidEndpointSelector(s.to_string()))
+            }
+            _ => Ok(selector),
+        }
+    }
+}
+
+impl Display for EndpointSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndpointSelector::Path(path) => write!(f, "{}", path),
+            EndpointSelector::OperationId(operation_id) => write!(f, "{}{}", OPERATION_ID_PREFIX, operation_id),
+            EndpointSelector::Tag(tag) => write!(f, "{}{}", TAG_PREFIX, tag),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use crate::{
+        models::{EndpointConfiguration, Method, OpenapiPath, Operation, StatusCode},
+        utils::test::{create_mock_operation, create_mock_runtime},
+    };
+
+    use super::EndpointSelector;
+
+    fn create_endpoint(operation_id: Option<&str>, tags: Vec<&str>) -> EndpointConfiguration {
+        let operation = Operation {
+            operation_id: operation_id.map(|x| x.to_string()),
+            tags: tags.into_iter().map(|x| x.to_string()).collect(),
+            ..create_mock_operation(Method::GET, "/users/{id}")
+        };
+        EndpointConfiguration::new(
+            Method::GET,
+            "/users/{id}",
+            StatusCode::Exact(200),
+            Arc::new(create_mock_runtime()),
+            false,
+        )
+        .unwrap()
+        .with_operation(&Arc::new(operation))
+    }
+
+    #[test]
+    fn parses_selectors() {
+        assert_eq!(
+            EndpointSelector::from_str("operationId: getUser").unwrap(),
+            EndpointSelector::OperationId("getUser".to_string())
+        );
+        assert_eq!(
+            EndpointSelector::from_str("tag:users").unwrap(),
+            EndpointSelector::Tag("users".to_string())
+        );
+        assert_eq!(
+            EndpointSelector::from_str(" /users/{id} ").unwrap(),
+            EndpointSelector::Path(OpenapiPath::from_str("/users/{id}").unwrap())
+        );
+        assert!(EndpointSelector::from_str("tag:").is_err());
+    }
+
+    #[test]
+    fn selects_endpoints_by_operation() {
+        let endpoint = create_endpoint(Some("getUser"), vec!["users"]);
+
+        assert!(EndpointSelector::OperationId("getUser".to_string()).incompases_endpoint_config(&endpoint));
+        assert!(!EndpointSelector::OperationId("listUsers".to_string()).incompases_endpoint_config(&endpoint));
+        assert!(EndpointSelector::Tag("users".to_string()).incompases_endpoint_config(&endpoint));
+        assert!(!EndpointSelector::Tag("orders".to_string()).incompases_endpoint_config(&endpoint));
+        assert!(EndpointSelector::from_str("/users/{user_id}")
+            .unwrap()
+            .incompases_endpoint_config(&endpoint));
+    }
+
+    #[test]
+    fn operation_selectors_ignore_endpoints_without_operation() {
+        let endpoint = EndpointConfiguration::new(
+            Method::GET,
+            "/users/1",
+            StatusCode::Exact(200),
+            Arc::new(create_mock_runtime()),
+            false,
+        )
+        .unwrap();
+
+        assert!(!EndpointSelector::Tag("users".to_string()).incompases_endpoint_config(&endpoint));
+    }
+}
@@ -0,0 +1,118 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::utils::Error;
+
+const STATUS_DEFAULT_STR: &str = "default";
+const STATUS_RANGE_SUFFIX: &str = "XX";
+
+/// Status code of a response, either an exact code, a class of codes like `4XX` or the `default`
+/// response of an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StatusCode {
+    Exact(u16),
+    Range(u8),
+    Default,
+}
+
+impl StatusCode {
+    /// Checks whether a status code is part of this one, `default` incompases every status code,
+    /// a range every exact code of its class.
+    pub fn incompases_status_code(&self, other: &StatusCode) -> bool {
+        match (self, other) {
+            (StatusCode::Default, _) => true,
+            (StatusCode::Range(class), StatusCode::Exact(code)) => code / 100 == *class as u16,
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl FromStr for StatusCode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == STATUS_DEFAULT_STR {
+            return Ok(StatusCode::Default);
+        }
+
+        // keys of responses can start with any character, so they're only split at char boundaries
+        if let (Some(class_str), Some(suffix)) = (s.get(..1), s.get(1..)) {
+            if suffix.eq_ignore_ascii_case(STATUS_RANGE_SUFFIX) {
+                return match class_str.parse() {
+                    Ok(class @ 1..=5) => Ok(StatusCode::Range(class)),
+                    _ => Err(Error::InvalidParseStatusCode(s.to_string())),
+                };
+            }
+        }
+
+        match s.parse() {
+            Ok(code @ 100..=599) => Ok(StatusCode::Exact(code)),
+            _ => Err(Error::InvalidParseStatusCode(s.to_string())),
+        }
+    }
+}
+
+impl Display for StatusCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusCode::Exact(code) => write!(f, "{}", code),
+            StatusCode::Range(class) => write!(f, "{}{}", class, STATUS_RANGE_SUFFIX),
+            StatusCode::Default => write!(f, "{}", STATUS_DEFAULT_STR),
+        }
+    }
+}
+
+impl From<u16> for StatusCode {
+    fn from(code: u16) -> Self {
+        StatusCode::Exact(code)
+    }
+}
+
+impl PartialEq<u16> for StatusCode {
+    fn eq(&self, other: &u16) -> bool {
+        self == &StatusCode::Exact(*other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::StatusCode;
+
+    #[test]
+    fn parses_status_codes() {
+        assert_eq!(StatusCode::from_str("200").unwrap(), StatusCode::Exact(200));
+        assert_eq!(StatusCode::from_str("4XX").unwrap(), StatusCode::Range(4));
+        assert_eq!(StatusCode::from_str("5xx").unwrap(), StatusCode::Range(5));
+        assert_eq!(StatusCode::from_str("default").unwrap(), StatusCode::Default);
+    }
+
+    #[test]
+    fn rejects_invalid_status_codes() {
+        assert!(StatusCode::from_str("6XX").is_err());
+        assert!(StatusCode::from_str("20").is_err());
+        assert!(StatusCode::from_str("é1").is_err());
+        assert!(StatusCode::from_str("éXX").is_err());
+        assert!(StatusCode::from_str("OK").is_err());
+    }
+
+    #[test]
+    fn ranges_incompas_codes_of_their_class() {
+        assert!(StatusCode::Range(2).incompases_status_code(&StatusCode::Exact(204)));
+        assert!(!StatusCode::Range(2).incompases_status_code(&StatusCode::Exact(404)));
+        assert!(!StatusCode::Exact(204).incompases_status_code(&StatusCode::Range(2)));
+    }
+
+    #[test]
+    fn default_incompases_everything() {
+        assert!(StatusCode::Default.incompases_status_code(&StatusCode::Exact(418)));
+        assert!(StatusCode::Default.incompases_status_code(&StatusCode::Range(5)));
+    }
+
+    #[test]
+    fn displays_status_codes() {
+        assert_eq!(StatusCode::Range(4).to_string(), "4XX");
+        assert_eq!(StatusCode::Default.to_string(), "default");
+        assert_eq!(StatusCode::Exact(201).to_string(), "201");
+    }
+}
@@ -0,0 +1,17 @@
+use super::EndpointSelector;
+
+/// Test coverage required for the endpoints of a selector, in addition to the overall coverage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Threshold {
+    pub selector: EndpointSelector,
+    pub test_coverage: f32,
+}
+
+impl Threshold {
+    pub fn new(selector: EndpointSelector, test_coverage: f32) -> Threshold {
+        Threshold {
+            selector,
+            test_coverage,
+        }
+    }
+}
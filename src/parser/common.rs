@@ -1,3 +1,42 @@
+use std::collections::BTreeMap;
+
+use json::JsonValue;
+use percent_encoding::percent_decode_str;
+use url::{form_urlencoded, Url};
+use yaml_rust::Yaml;
+
+use crate::{
+    config::{HostRewrite, PathNormalization},
+    models::{OpenapiVersion, ParameterLocation, RequestParameter},
+    utils::{print_debug_message, Error},
+};
+
+const OPENAPI_3_VERSION_PREFIX: &str = "3.";
+const SWAGGER_2_VERSION: &str = "2.0";
+const EXTENSION_PREFIX: &str = "x-";
+const PATH_ITEM_FIELDS: [&str; 5] = ["$ref", "summary", "description", "servers", "parameters"];
+
+pub fn parse_openapi_version(
+    swagger_version: Option<&str>,
+    openapi_version: Option<&str>,
+) -> Result<OpenapiVersion, Error> {
+    match (swagger_version, openapi_version) {
+        (_, Some(version)) if version.starts_with(OPENAPI_3_VERSION_PREFIX) => Ok(OpenapiVersion::Openapi3),
+        (_, Some(version)) => Err(Error::UnsupportedOpenapiVersion(version.to_string())),
+        (Some(SWAGGER_2_VERSION), None) | (None, None) => Ok(OpenapiVersion::Swagger2),
+        (Some(version), None) => Err(Error::UnsupportedOpenapiVersion(version.to_string())),
+    }
+}
+
+/// Fields of a path item that describe the path as a whole instead of one of its operations.
+pub fn is_path_item_field(key: &str) -> bool {
+    PATH_ITEM_FIELDS.contains(&key)
+}
+
+pub fn is_extension(key: &str) -> bool {
+    key.starts_with(EXTENSION_PREFIX)
+}
+
 pub fn format_basepath(basepath: &str) -> &str {
     if basepath.ends_with('/') {
         &basepath[0..basepath.len() - 1]
@@ -6,9 +45,172 @@ pub fn format_basepath(basepath: &str) -> &str {
     }
 }
 
+/// Extracts the path part of an OpenAPI 3 server url, which can either be absolute
+/// (`https://example.com/api`) or relative to the document (`/api`).
+pub fn server_url_to_basepath(server_url: &str) -> &str {
+    let without_scheme = match server_url.find("://") {
+        Some(scheme_end) => &server_url[scheme_end + 3..],
+        None => return format_basepath(strip_query_and_fragment(server_url)),
+    };
+
+    match without_scheme.find('/') {
+        Some(path_start) => format_basepath(strip_query_and_fragment(&without_scheme[path_start..])),
+        None => "",
+    }
+}
+
+fn strip_query_and_fragment(url: &str) -> &str {
+    match url.find(['?', '#']) {
+        Some(index) => &url[..index],
+        None => url,
+    }
+}
+
+/// Substitutes all `{variable}` templates of a server url, preferring values configured for
+/// CoveAPI over the defaults provided by the spec, and returns the resulting basepath.
+/// Unknown variables are left in place and therefore match any path segment.
+pub fn expand_server_basepath(
+    server_url: &str,
+    default_variables: &BTreeMap<String, String>,
+    configured_variables: &BTreeMap<String, String>,
+) -> String {
+    let mut expanded_url = String::new();
+    let mut remaining = server_url;
+
+    while let Some(start) = remaining.find('{') {
+        let end = match remaining[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let variable_name = &remaining[start + 1..end];
+        expanded_url.push_str(&remaining[..start]);
+        match configured_variables
+            .get(variable_name)
+            .or_else(|| default_variables.get(variable_name))
+        {
+            Some(value) => expanded_url.push_str(value),
+            None => expanded_url.push_str(&remaining[start..=end]),
+        }
+        remaining = &remaining[end + 1..];
+    }
+    expanded_url.push_str(remaining);
+
+    server_url_to_basepath(&expanded_url).to_string()
+}
+
+pub fn join_basepath(basepath: &str, path: &str) -> String {
+    let mut full_path = String::from(basepath);
+    if path != "/" {
+        full_path.push_str(path);
+    }
+    if full_path.is_empty() {
+        full_path.push('/');
+    }
+    full_path
+}
+
+pub fn push_unique_basepath(basepaths: &mut Vec<String>, basepath: &str) {
+    if !basepaths.iter().any(|x| x == basepath) {
+        basepaths.push(basepath.to_string());
+    }
+}
+
+/// Replaces the host of the url by the first matching rewrite. Hosts without a configured rewrite
+/// are kept as they are.
+pub fn rewrite_host(url: &Url, host_rewrites: &[HostRewrite]) -> Url {
+    let mut url = url.clone();
+    let host = url.host_str().map(|x| x.to_lowercase());
+    if let Some(host_rewrite) = host_rewrites.iter().find(|x| Some(&x.host) == host.as_ref()) {
+        // replacements are validated while parsing the configuration
+        if url.set_host(Some(&host_rewrite.replacement)).is_err() {
+            print_debug_message(format!("Unable to rewrite the host of {}", url));
+        }
+    }
+    url
+}
+
+/// Splits the url of a request into its normalized path and its query.
+pub fn normalize_request_url<'a>(url: &'a str, path_normalization: &PathNormalization) -> (String, &'a str) {
+    let (path, query) = match url.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (url, ""),
+    };
+
+    let mut path = path.to_string();
+    if path_normalization.merge_slashes {
+        while path.contains("//") {
+            path = path.replace("//", "/");
+        }
+    }
+    if path_normalization.ignore_trailing_slash && path.len() > 1 {
+        path = match path.trim_end_matches('/') {
+            "" => "/".to_string(),
+            trimmed_path => trimmed_path.to_string(),
+        };
+    }
+
+    // encoded slashes are part of their segment, so they stay encoded
+    let segments: Vec<String> = path
+        .split('/')
+        .map(|x| percent_decode_str(x).decode_utf8_lossy().replace('/', "%2F"))
+        .collect();
+    (segments.join("/"), query)
+}
+
+pub fn parse_query(query: &str) -> Vec<RequestParameter> {
+    form_urlencoded::parse(query.as_bytes())
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, value)| RequestParameter::new(ParameterLocation::Query, &name, &value))
+        .collect()
+}
+
+/// Converts a yaml tree into json, keys that aren't strings (ex. unquoted status codes) are
+/// converted to their string representation.
+pub fn yaml_to_json(yaml: &Yaml) -> JsonValue {
+    match yaml {
+        Yaml::Hash(hash) => {
+            let mut object = JsonValue::new_object();
+            for (key, value) in hash {
+                if let Some(key) = yaml_key_to_string(key) {
+                    object[key.as_str()] = yaml_to_json(value);
+                }
+            }
+            object
+        }
+        Yaml::Array(array) => JsonValue::Array(array.iter().map(yaml_to_json).collect()),
+        Yaml::String(string) => JsonValue::from(string.as_str()),
+        Yaml::Integer(integer) => JsonValue::from(*integer),
+        // parsing the number as json keeps its representation, so versions like 2.0 stay intact
+        Yaml::Real(real) => match json::parse(real) {
+            Ok(number) if number.is_number() => number,
+            _ => JsonValue::from(real.as_str()),
+        },
+        Yaml::Boolean(boolean) => JsonValue::from(*boolean),
+        Yaml::Null | Yaml::Alias(_) | Yaml::BadValue => JsonValue::Null,
+    }
+}
+
+fn yaml_key_to_string(key: &Yaml) -> Option<String> {
+    match key {
+        Yaml::String(key) | Yaml::Real(key) => Some(key.clone()),
+        Yaml::Integer(key) => Some(key.to_string()),
+        Yaml::Boolean(key) => Some(key.to_string()),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::format_basepath;
+    use std::collections::BTreeMap;
+
+    use yaml_rust::YamlLoader;
+
+    use crate::config::PathNormalization;
+
+    use super::{
+        expand_server_basepath, format_basepath, is_extension, is_path_item_field, join_basepath,
+        normalize_request_url, parse_openapi_version, server_url_to_basepath, yaml_to_json, OpenapiVersion,
+    };
 
     #[test]
     fn coverts_slash_to_empty_string() {
@@ -24,4 +226,122 @@ mod tests {
     fn ignores_emty_string() {
         assert_eq!(format_basepath(""), "");
     }
+
+    #[test]
+    fn detects_openapi_versions() {
+        assert_eq!(
+            parse_openapi_version(Some("2.0"), None).unwrap(),
+            OpenapiVersion::Swagger2
+        );
+        assert_eq!(parse_openapi_version(None, None).unwrap(), OpenapiVersion::Swagger2);
+        assert_eq!(
+            parse_openapi_version(None, Some("3.0.3")).unwrap(),
+            OpenapiVersion::Openapi3
+        );
+        assert!(parse_openapi_version(None, Some("4.0.0")).is_err());
+        assert!(parse_openapi_version(Some("1.2"), None).is_err());
+    }
+
+    #[test]
+    fn extracts_basepath_from_absolute_server_url() {
+        assert_eq!(server_url_to_basepath("https://example.com/api/v1"), "/api/v1");
+        assert_eq!(server_url_to_basepath("https://example.com:8080/api/"), "/api");
+        assert_eq!(server_url_to_basepath("https://example.com"), "");
+    }
+
+    #[test]
+    fn extracts_basepath_from_relative_server_url() {
+        assert_eq!(server_url_to_basepath("/api/v1"), "/api/v1");
+        assert_eq!(server_url_to_basepath("/"), "");
+        assert_eq!(server_url_to_basepath("/api?foo=bar"), "/api");
+    }
+
+    #[test]
+    fn expands_server_variables_with_defaults() {
+        let mut defaults = BTreeMap::new();
+        defaults.insert("env".to_string(), "staging".to_string());
+        defaults.insert("version".to_string(), "v1".to_string());
+        assert_eq!(
+            expand_server_basepath("https://{env}.example.com/api/{version}", &defaults, &BTreeMap::new()),
+            "/api/v1"
+        );
+    }
+
+    #[test]
+    fn configured_server_variables_take_precedence() {
+        let mut defaults = BTreeMap::new();
+        defaults.insert("version".to_string(), "v1".to_string());
+        let mut configured = BTreeMap::new();
+        configured.insert("version".to_string(), "v2".to_string());
+        assert_eq!(
+            expand_server_basepath("https://example.com/api/{version}", &defaults, &configured),
+            "/api/v2"
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_server_variables() {
+        assert_eq!(
+            expand_server_basepath("/api/{version}", &BTreeMap::new(), &BTreeMap::new()),
+            "/api/{version}"
+        );
+    }
+
+    #[test]
+    fn joins_basepath_and_path() {
+        assert_eq!(join_basepath("", "/"), "/");
+        assert_eq!(join_basepath("/api", "/"), "/api");
+        assert_eq!(join_basepath("/api", "/users"), "/api/users");
+    }
+
+    #[test]
+    fn converts_yaml_to_json() {
+        let yaml = &YamlLoader::load_from_str("paths:\n  /foo:\n    get:\n      responses:\n        200:\n          description: OK\n      deprecated: true\n").unwrap()[0];
+        let json = yaml_to_json(yaml);
+        assert_eq!(json["paths"]["/foo"]["get"]["responses"]["200"]["description"], "OK");
+        assert_eq!(json["paths"]["/foo"]["get"]["deprecated"], true);
+
+        let yaml = &YamlLoader::load_from_str("swagger: 2.0").unwrap()[0];
+        assert_eq!(yaml_to_json(yaml)["swagger"].dump(), "2.0");
+    }
+
+    #[test]
+    fn tells_path_item_fields_apart_from_operations() {
+        assert!(is_path_item_field("parameters"));
+        assert!(is_path_item_field("summary"));
+        assert!(!is_path_item_field("get"));
+        assert!(is_extension("x-codegen"));
+        assert!(!is_extension("post"));
+    }
+
+    #[test]
+    fn normalizes_request_url() {
+        let path_normalization = PathNormalization::default();
+        assert_eq!(
+            normalize_request_url("/weather?city=x", &path_normalization),
+            ("/weather".to_string(), "city=x")
+        );
+        assert_eq!(
+            normalize_request_url("/caf%C3%A9s/a%2Fb/", &path_normalization),
+            ("/caf\u{e9}s/a%2Fb/".to_string(), "")
+        );
+        assert_eq!(
+            normalize_request_url("//users//1", &path_normalization),
+            ("//users//1".to_string(), "")
+        );
+    }
+
+    #[test]
+    fn merges_slashes_and_ignores_trailing_slash() {
+        let path_normalization = PathNormalization {
+            merge_slashes: true,
+            ignore_trailing_slash: true,
+            case_insensitive: false,
+        };
+        assert_eq!(
+            normalize_request_url("//users///1/?page=2", &path_normalization),
+            ("/users/1".to_string(), "page=2")
+        );
+        assert_eq!(normalize_request_url("///", &path_normalization), ("/".to_string(), ""));
+    }
 }
@@ -0,0 +1,647 @@
+use std::{collections::BTreeMap, str::FromStr, sync::Arc};
+
+use json::JsonValue;
+
+use crate::{
+    config::Runtime,
+    models::{
+        EndpointConfiguration, MediaType, Method, OpenapiDocument, OpenapiVersion, Operation, Parameter, Response,
+        SecurityRequirement, Server, StatusCode,
+    },
+    utils::Error,
+};
+
+use super::{
+    common::{
+        expand_server_basepath, is_extension, is_path_item_field, join_basepath, parse_openapi_version,
+        push_unique_basepath,
+    },
+    location::SourceMap,
+    reference::ReferenceResolver,
+};
+
+const PATHS_KEY: &str = "paths";
+const SECURITY_STATUS_CODES: [StatusCode; 2] = [StatusCode::Exact(401), StatusCode::Exact(403)];
+
+/// Loads a spec, that was already parsed into a json tree, into an [`OpenapiDocument`].
+/// Locations are taken from the source map of the root document, if one is available.
+pub fn load_openapi_document(
+    spec: &JsonValue,
+    resolver: &mut ReferenceResolver,
+    source_map: Option<&SourceMap>,
+) -> Result<OpenapiDocument, Error> {
+    let version = parse_openapi_version(
+        get_version_str(&spec["swagger"]).as_deref(),
+        get_version_str(&spec["openapi"]).as_deref(),
+    )?;
+
+    let servers = match version {
+        OpenapiVersion::Swagger2 => match &spec["basePath"] {
+            JsonValue::Null => vec![],
+            base_path => match base_path.as_str() {
+                Some(base_path) => vec![Server::new(base_path)],
+                None => return Err(Error::InvalidBasePath),
+            },
+        },
+        OpenapiVersion::Openapi3 => get_servers(&spec["servers"])?,
+    };
+
+    let mut document = OpenapiDocument {
+        version,
+        servers,
+        host: spec["host"].as_str().map(|x| x.to_string()),
+        schemes: spec["schemes"]
+            .members()
+            .filter_map(|x| x.as_str())
+            .map(|x| x.to_string())
+            .collect(),
+        security: get_security(&spec["security"]),
+        operations: vec![],
+    };
+
+    match (&spec[PATHS_KEY], version) {
+        // openapi 3 documents are allowed to only describe components
+        (JsonValue::Null, OpenapiVersion::Openapi3) if !spec["components"].is_null() => return Ok(document),
+        (JsonValue::Object(_), _) => (),
+        _ => return Err(Error::InvalidParseSyntax),
+    };
+
+    let paths = resolver.resolve_paths(spec)?;
+    for (path_key, path_json) in paths.entries() {
+        if !path_json.is_object() {
+            return Err(Error::InvalidParseSyntax);
+        }
+
+        let path_parameters = get_parameters(&path_json["parameters"])?;
+        let path_extensions = get_extensions(path_json);
+        let path_servers = match version {
+            OpenapiVersion::Openapi3 if !path_json["servers"].is_null() => get_servers(&path_json["servers"])?,
+            _ => document.servers.clone(),
+        };
+        let path_location = source_map.and_then(|x| x.get(&[PATHS_KEY, path_key]));
+
+        for (method_key, method_json) in path_json.entries() {
+            // path level fields and extensions are handled above
+            if is_path_item_field(method_key) || is_extension(method_key) {
+                continue;
+            }
+            let method = match Method::from_str(method_key) {
+                Some(method) => method,
+                None => return Err(Error::InvalidParseMethod(method_key.to_string())),
+            };
+
+            let mut extensions = path_extensions.clone();
+            extensions.extend(get_extensions(method_json));
+
+            let operation = Operation {
+                method,
+                path: path_key.to_string(),
+                operation_id: method_json["operationId"].as_str().map(|x| x.to_string()),
+                // operations without a summary of their own are described by the path item
+                summary: method_json["summary"]
+                    .as_str()
+                    .or(path_json["summary"].as_str())
+                    .map(|x| x.to_string()),
+                deprecated: method_json["deprecated"].as_bool().unwrap_or(false),
+                servers: match version {
+                    OpenapiVersion::Openapi3 if !method_json["servers"].is_null() => {
+                        get_servers(&method_json["servers"])?
+                    }
+                    _ => path_servers.clone(),
+                },
+                parameters: Parameter::merge(&path_parameters, get_parameters(&method_json["parameters"])?),
+                request_media_types: get_request_media_types(spec, path_json, method_json, version),
+                responses: get_responses(&method_json["responses"], get_produces(spec, method_json), version)?,
+                security: get_security(&method_json["security"]),
+                tags: method_json["tags"]
+                    .members()
+                    .filter_map(|x| x.as_str())
+                    .map(|x| x.to_string())
+                    .collect(),
+                extensions,
+                location: source_map
+                    .and_then(|x| x.get(&[PATHS_KEY, path_key, method_key]))
+                    .or(path_location),
+            };
+            document.operations.push(Arc::new(operation));
+        }
+    }
+
+    Ok(document)
+}
+
+/// Generates the endpoints a runtime is expected to serve according to the document.
+pub fn get_document_endpoints(
+    document: &OpenapiDocument,
+    runtime: Arc<Runtime>,
+) -> Result<Vec<EndpointConfiguration>, Error> {
+    let mut endpoints = vec![];
+
+    for operation in &document.operations {
+        for base_path in get_base_paths(&operation.servers, &runtime) {
+            let path = join_basepath(&base_path, &operation.path);
+
+            if document.requires_authentication(operation) {
+                for status_code in SECURITY_STATUS_CODES {
+                    // responses the spec declares itself don't need to be generated
+                    if operation.responses.iter().any(|x| x.status_code == status_code) {
+                        continue;
+                    }
+                    endpoints.push(
+                        EndpointConfiguration::new(
+                            operation.method.clone(),
+                            &path,
+                            status_code,
+                            runtime.clone(),
+                            true,
+                        )?
+                        .with_operation(operation),
+                    );
+                }
+            }
+
+            for response in &operation.responses {
+                endpoints.push(
+                    EndpointConfiguration::new(
+                        operation.method.clone(),
+                        &path,
+                        response.status_code,
+                        runtime.clone(),
+                        false,
+                    )?
+                    .with_operation(operation),
+                );
+            }
+        }
+    }
+
+    Ok(endpoints)
+}
+
+fn get_base_paths(servers: &[Server], runtime: &Runtime) -> Vec<String> {
+    let mut base_paths = vec![];
+    for server in servers {
+        push_unique_basepath(
+            &mut base_paths,
+            &expand_server_basepath(&server.url, &server.variables, &runtime.server_variables),
+        );
+    }
+
+    // the spec defaults to a single server with the url "/"
+    if base_paths.is_empty() {
+        base_paths.push(String::new());
+    }
+    base_paths
+}
+
+/// Versions are strings by the spec, but unquoted versions (`swagger: 2.0`) in yaml are numbers.
+pub fn get_version_str(version: &JsonValue) -> Option<String> {
+    match version {
+        JsonValue::Number(_) => Some(version.dump()),
+        version => version.as_str().map(|x| x.to_string()),
+    }
+}
+
+fn get_servers(servers_json: &JsonValue) -> Result<Vec<Server>, Error> {
+    let mut servers = vec![];
+
+    for server_json in servers_json.members() {
+        let mut server = match server_json["url"].as_str() {
+            Some(url) => Server::new(url),
+            None => return Err(Error::InvalidBasePath),
+        };
+
+        for (name, variable) in server_json["variables"].entries() {
+            if let Some(default) = variable["default"].as_str() {
+                server.variables.insert(name.to_string(), default.to_string());
+            }
+        }
+        servers.push(server);
+    }
+
+    Ok(servers)
+}
+
+/// Loads the responses of an operation. Their media types are declared by `content` in openapi
+/// 3, while Swagger 2 declares them through `produces` for all responses with a `schema`.
+fn get_responses(
+    responses_json: &JsonValue,
+    produces: &JsonValue,
+    version: OpenapiVersion,
+) -> Result<Vec<Response>, Error> {
+    if !responses_json.is_object() {
+        return Err(Error::InvalidParseSyntax);
+    }
+
+    let mut responses = vec![];
+    for (status_key, response_json) in responses_json.entries() {
+        let media_types = match version {
+            OpenapiVersion::Openapi3 => response_json["content"]
+                .entries()
+                .map(|(media_type, _)| MediaType::new(media_type))
+                .collect(),
+            OpenapiVersion::Swagger2 if !response_json["schema"].is_null() => get_media_types(produces),
+            OpenapiVersion::Swagger2 => vec![],
+        };
+        responses.push(Response {
+            status_code: StatusCode::from_str(status_key)?,
+            media_types,
+        });
+    }
+    Ok(responses)
+}
+
+/// `produces` of a Swagger 2 operation, operations without their own inherit the one of the spec.
+fn get_produces<'a>(spec: &'a JsonValue, method_json: &'a JsonValue) -> &'a JsonValue {
+    if method_json["produces"].is_array() {
+        &method_json["produces"]
+    } else {
+        &spec["produces"]
+    }
+}
+
+fn get_media_types(media_types_json: &JsonValue) -> Vec<MediaType> {
+    media_types_json
+        .members()
+        .filter_map(|x| x.as_str())
+        .map(MediaType::new)
+        .collect()
+}
+
+pub fn get_parameters(parameters_json: &JsonValue) -> Result<Vec<Parameter>, Error> {
+    let mut parameters = vec![];
+
+    for parameter_json in parameters_json.members() {
+        let (name, location) = match (parameter_json["name"].as_str(), parameter_json["in"].as_str()) {
+            (Some(name), Some(location)) => (name, location),
+            _ => return Err(Error::InvalidParseParameter(parameter_json.dump())),
+        };
+        let required = parameter_json["required"].as_bool().unwrap_or(false);
+        parameters.push(Parameter::new(name, location, required)?.with_values(get_parameter_values(parameter_json)));
+    }
+    Ok(parameters)
+}
+
+/// Values a parameter is limited to by an enum or a boolean type. Swagger 2 declares the type on
+/// the parameter itself, openapi 3 in its schema. Values of arrays are taken from their items.
+fn get_parameter_values(parameter_json: &JsonValue) -> Vec<String> {
+    let schema = match &parameter_json["schema"] {
+        JsonValue::Object(_) => &parameter_json["schema"],
+        _ => parameter_json,
+    };
+    let schema = if schema["type"] == "array" {
+        &schema["items"]
+    } else {
+        schema
+    };
+
+    if schema["enum"].is_array() {
+        let mut values = vec![];
+        for member in schema["enum"].members() {
+            let value = match member {
+                JsonValue::Null => continue,
+                member => member.as_str().map(|x| x.to_string()).unwrap_or(member.dump()),
+            };
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        values
+    } else if schema["type"] == "boolean" {
+        vec!["true".to_string(), "false".to_string()]
+    } else {
+        vec![]
+    }
+}
+
+/// Media types of the request body, declared by `requestBody.content` in openapi 3. Swagger 2
+/// declares them through `consumes`, which only applies to operations with a body or form data.
+fn get_request_media_types(
+    spec: &JsonValue,
+    path_json: &JsonValue,
+    method_json: &JsonValue,
+    version: OpenapiVersion,
+) -> Vec<MediaType> {
+    match version {
+        OpenapiVersion::Openapi3 => method_json["requestBody"]["content"]
+            .entries()
+            .map(|(media_type, _)| MediaType::new(media_type))
+            .collect(),
+        OpenapiVersion::Swagger2 => {
+            let has_body = path_json["parameters"]
+                .members()
+                .chain(method_json["parameters"].members())
+                .any(|x| x["in"] == "body" || x["in"] == "formData");
+            if !has_body {
+                return vec![];
+            }
+
+            if method_json["consumes"].is_array() {
+                get_media_types(&method_json["consumes"])
+            } else {
+                get_media_types(&spec["consumes"])
+            }
+        }
+    }
+}
+
+fn get_security(security_json: &JsonValue) -> Option<Vec<SecurityRequirement>> {
+    if security_json.is_null() {
+        return None;
+    }
+
+    let mut requirements = vec![];
+    for requirement_json in security_json.members() {
+        let mut requirement = BTreeMap::new();
+        for (scheme, scopes) in requirement_json.entries() {
+            let scopes = scopes
+                .members()
+                .filter_map(|x| x.as_str())
+                .map(|x| x.to_string())
+                .collect();
+            requirement.insert(scheme.to_string(), scopes);
+        }
+        requirements.push(requirement);
+    }
+    Some(requirements)
+}
+
+fn get_extensions(json_obj: &JsonValue) -> BTreeMap<String, String> {
+    json_obj
+        .entries()
+        .filter(|x| is_extension(x.0))
+        .map(|x| (x.0.to_string(), x.1.dump()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use crate::{
+        models::{MediaType, Method, OpenapiVersion, SourceLocation, StatusCode},
+        parser::{location::SourceMap, reference::ReferenceResolver},
+        utils::test::create_mock_runtime,
+    };
+
+    use super::{get_document_endpoints, load_openapi_document};
+
+    const SPEC: &str = r#"{
+  "openapi": "3.0.3",
+  "security": [{ "apiKey": [] }],
+  "paths": {
+    "/users": {
+      "get": {
+        "tags": ["users"],
+        "operationId": "listUsers",
+        "summary": "List users",
+        "deprecated": true,
+        "security": [{ "oauth": ["read:users"] }],
+        "responses": { "200": { "description": "OK" } }
+      }
+    }
+  }
+}"#;
+
+    #[test]
+    fn loads_operations_into_document() {
+        let spec = json::parse(SPEC).unwrap();
+        let document = load_openapi_document(&spec, &mut ReferenceResolver::detached(), None).unwrap();
+
+        assert_eq!(document.version, OpenapiVersion::Openapi3);
+        assert_eq!(document.operations.len(), 1);
+
+        let operation = &document.operations[0];
+        assert_eq!(operation.method, Method::GET);
+        assert_eq!(operation.path, "/users");
+        assert_eq!(operation.tags, vec!["users".to_string()]);
+        assert_eq!(operation.operation_id.as_deref(), Some("listUsers"));
+        assert_eq!(operation.summary.as_deref(), Some("List users"));
+        assert!(operation.deprecated);
+        assert_eq!(operation.responses[0].status_code, StatusCode::Exact(200));
+        assert_eq!(operation.location, None);
+    }
+
+    #[test]
+    fn loads_security_requirements() {
+        let spec = json::parse(SPEC).unwrap();
+        let document = load_openapi_document(&spec, &mut ReferenceResolver::detached(), None).unwrap();
+
+        let mut global_requirement = BTreeMap::new();
+        global_requirement.insert("apiKey".to_string(), vec![]);
+        assert_eq!(document.security, Some(vec![global_requirement]));
+
+        let mut operation_requirement = BTreeMap::new();
+        operation_requirement.insert("oauth".to_string(), vec!["read:users".to_string()]);
+        assert_eq!(document.operations[0].security, Some(vec![operation_requirement]));
+    }
+
+    #[test]
+    fn locates_operations() {
+        let spec = json::parse(SPEC).unwrap();
+        let source_map = SourceMap::scan(SPEC).unwrap();
+        let document = load_openapi_document(&spec, &mut ReferenceResolver::detached(), Some(&source_map)).unwrap();
+
+        assert_eq!(
+            document.operations[0].location,
+            Some(SourceLocation { line: 6, column: 7 })
+        );
+    }
+
+    fn get_status_codes(spec: &str, method: Method) -> Vec<StatusCode> {
+        let spec = json::parse(spec).unwrap();
+        let document = load_openapi_document(&spec, &mut ReferenceResolver::detached(), None).unwrap();
+        get_document_endpoints(&document, Arc::new(create_mock_runtime()))
+            .unwrap()
+            .iter()
+            .filter(|x| x.method == method)
+            .map(|x| x.status_code)
+            .collect()
+    }
+
+    const SECURED_SPEC: &str = r#"{
+  "openapi": "3.0.3",
+  "security": [{ "apiKey": [] }],
+  "paths": {
+    "/users": {
+      "get": { "responses": { "200": {} } },
+      "post": { "security": [], "responses": { "201": {} } },
+      "put": { "security": [{}, { "apiKey": [] }], "responses": { "200": {} } },
+      "delete": { "responses": { "204": {}, "401": {} } }
+    }
+  }
+}"#;
+
+    #[test]
+    fn applies_document_security_to_operations() {
+        let status_codes = get_status_codes(SECURED_SPEC, Method::GET);
+        assert!(status_codes.contains(&StatusCode::Exact(401)));
+        assert!(status_codes.contains(&StatusCode::Exact(403)));
+    }
+
+    #[test]
+    fn empty_security_opts_out_of_document_security() {
+        assert_eq!(
+            get_status_codes(SECURED_SPEC, Method::POST),
+            vec![StatusCode::Exact(201)]
+        );
+    }
+
+    #[test]
+    fn optional_security_doesnt_generate_responses() {
+        assert_eq!(
+            get_status_codes(SECURED_SPEC, Method::PUT),
+            vec![StatusCode::Exact(200)]
+        );
+    }
+
+    #[test]
+    fn doesnt_generate_declared_responses() {
+        let status_codes = get_status_codes(SECURED_SPEC, Method::DELETE);
+        assert_eq!(status_codes.len(), 3);
+        assert_eq!(status_codes.iter().filter(|x| **x == StatusCode::Exact(401)).count(), 1);
+    }
+
+    #[test]
+    fn loads_values_of_enum_and_boolean_parameters() {
+        let spec = json::parse(
+            r##"{
+  "openapi": "3.0.3",
+  "paths": {
+    "/orders": {
+      "get": {
+        "parameters": [
+          { "name": "sort", "in": "query", "schema": { "$ref": "#/components/schemas/Sort" } },
+          { "name": "archived", "in": "query", "schema": { "type": "boolean" } },
+          { "name": "status", "in": "query", "schema": { "type": "array", "items": { "enum": ["open", "closed"] } } },
+          { "name": "limit", "in": "query", "schema": { "type": "integer" } },
+          { "name": "version", "in": "header", "type": "integer", "enum": [1, 2, null] }
+        ],
+        "responses": { "200": {} }
+      }
+    }
+  },
+  "components": { "schemas": { "Sort": { "type": "string", "enum": ["asc", "desc"] } } }
+}"##,
+        )
+        .unwrap();
+        let document = load_openapi_document(&spec, &mut ReferenceResolver::detached(), None).unwrap();
+
+        let values: Vec<Vec<String>> = document.operations[0]
+            .parameters
+            .iter()
+            .map(|x| x.values.clone())
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                vec!["asc".to_string(), "desc".to_string()],
+                vec!["true".to_string(), "false".to_string()],
+                vec!["open".to_string(), "closed".to_string()],
+                vec![],
+                vec!["1".to_string(), "2".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn loads_request_media_types() {
+        let spec = json::parse(
+            r##"{
+  "openapi": "3.0.3",
+  "paths": {
+    "/uploads": {
+      "post": {
+        "requestBody": { "$ref": "#/components/requestBodies/Upload" },
+        "responses": { "201": {} }
+      }
+    }
+  },
+  "components": {
+    "requestBodies": {
+      "Upload": { "content": { "application/json": {}, "multipart/form-data": {} } }
+    }
+  }
+}"##,
+        )
+        .unwrap();
+        let document = load_openapi_document(&spec, &mut ReferenceResolver::detached(), None).unwrap();
+
+        assert_eq!(
+            document.operations[0].request_media_types,
+            vec![
+                MediaType::new("application/json"),
+                MediaType::new("multipart/form-data")
+            ]
+        );
+    }
+
+    #[test]
+    fn swagger_consumes_only_applies_to_operations_with_body() {
+        let spec = json::parse(
+            r##"{
+  "swagger": "2.0",
+  "consumes": ["application/json"],
+  "paths": {
+    "/uploads": {
+      "get": { "responses": { "200": {} } },
+      "post": {
+        "consumes": ["multipart/form-data"],
+        "parameters": [{ "name": "file", "in": "formData", "type": "file" }],
+        "responses": { "201": {} }
+      },
+      "put": {
+        "parameters": [{ "name": "upload", "in": "body" }],
+        "responses": { "200": {} }
+      }
+    }
+  }
+}"##,
+        )
+        .unwrap();
+        let document = load_openapi_document(&spec, &mut ReferenceResolver::detached(), None).unwrap();
+
+        let get_media_types = |method| {
+            document
+                .operations
+                .iter()
+                .find(|x| x.method == method)
+                .unwrap()
+                .request_media_types
+                .clone()
+        };
+        assert_eq!(get_media_types(Method::GET), vec![]);
+        assert_eq!(
+            get_media_types(Method::POST),
+            vec![MediaType::new("multipart/form-data")]
+        );
+        assert_eq!(get_media_types(Method::PUT), vec![MediaType::new("application/json")]);
+    }
+
+    #[test]
+    fn loads_response_media_types() {
+        let spec = json::parse(
+            r##"{
+  "swagger": "2.0",
+  "produces": ["application/json"],
+  "paths": {
+    "/reports": {
+      "get": {
+        "produces": ["application/json", "text/csv"],
+        "responses": { "200": { "schema": { "type": "string" } }, "204": {} }
+      }
+    }
+  }
+}"##,
+        )
+        .unwrap();
+        let document = load_openapi_document(&spec, &mut ReferenceResolver::detached(), None).unwrap();
+
+        let responses = &document.operations[0].responses;
+        assert_eq!(
+            responses[0].media_types,
+            vec![MediaType::new("application/json"), MediaType::new("text/csv")]
+        );
+        assert_eq!(responses[1].media_types, vec![]);
+    }
+}
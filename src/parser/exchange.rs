@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use super::{
+    common::{normalize_request_url, parse_query},
+    graphql::get_request_fields,
+};
+use crate::{
+    config::Runtime,
+    models::{EndpointConfiguration, Exchange, MediaType, Method, ParameterLocation, RequestParameter, StatusCode},
+    utils::{print_debug_message, Error},
+};
+
+const CONTENT_TYPE_HEADER: &str = "content-type";
+
+/// Turns a recorded exchange into the endpoint it called, requests to the GraphQL path of a
+/// GraphQL runtime additionally result in one endpoint per field they selected.
+pub fn parse_exchange(
+    runtime: Arc<Runtime>,
+    exchange: &Exchange,
+    graphql_path: &str,
+) -> Result<Vec<EndpointConfiguration>, Error> {
+    let method = match Method::from_str(&exchange.method) {
+        Some(method) => method,
+        None => {
+            // methods unknown to openapi can't be part of the coverage
+            print_debug_message(format!("Ignoring request with method {}", exchange.method));
+            return Ok(vec![]);
+        }
+    };
+    let (path, query) = normalize_request_url(&exchange.url, &runtime.path_normalization);
+    let status = StatusCode::Exact(exchange.status);
+
+    let mut parameters = parse_query(query);
+    for (name, value) in &exchange.request_headers {
+        parameters.push(RequestParameter::new(ParameterLocation::Header, name, value));
+    }
+
+    let mut endpoints = vec![
+        EndpointConfiguration::new(method.clone(), &path, status, runtime.clone(), false)?
+            .with_parameters(parameters)
+            .with_request_media_type(parse_media_type(exchange.get_request_header(CONTENT_TYPE_HEADER)))
+            .with_response_media_type(parse_media_type(exchange.get_response_header(CONTENT_TYPE_HEADER))),
+    ];
+
+    if runtime.is_graphql() && path == graphql_path && !exchange.request_body.is_empty() {
+        let body = String::from_utf8_lossy(&exchange.request_body);
+        for field in get_request_fields(&body) {
+            endpoints.push(
+                EndpointConfiguration::new(method.clone(), &path, status, runtime.clone(), false)?
+                    .with_graphql_field(Some(field)),
+            );
+        }
+    }
+    Ok(endpoints)
+}
+
+fn parse_media_type(media_type_str: Option<&str>) -> Option<MediaType> {
+    match media_type_str {
+        Some(media_type_str) if !media_type_str.trim().is_empty() => Some(MediaType::new(media_type_str)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use crate::{
+        config::OpenapiSource,
+        models::{Exchange, MediaType, ParameterLocation, RequestParameter},
+        utils::test::create_mock_runtime,
+    };
+
+    use super::parse_exchange;
+
+    fn create_exchange(method: &str, url: &str, request_body: &str) -> Exchange {
+        Exchange {
+            method: method.to_string(),
+            url: url.to_string(),
+            request_headers: vec![
+                ("X-Tenant".to_string(), "acme".to_string()),
+                ("Content-Type".to_string(), "application/json".to_string()),
+            ],
+            request_body: request_body.as_bytes().to_vec(),
+            status: 200,
+            response_headers: vec![(
+                "content-type".to_string(),
+                "application/json; charset=utf-8".to_string(),
+            )],
+        }
+    }
+
+    #[test]
+    fn parses_exchange() {
+        let exchange = create_exchange("get", "/users/1?expand=true", "");
+        let endpoints = parse_exchange(Arc::new(create_mock_runtime()), &exchange, "/graphql").unwrap();
+
+        assert_eq!(endpoints.len(), 1);
+        assert_eq!(endpoints[0].status_code, 200);
+        assert_eq!(
+            endpoints[0].parameters,
+            vec![
+                RequestParameter::new(ParameterLocation::Query, "expand", "true"),
+                RequestParameter::new(ParameterLocation::Header, "x-tenant", "acme"),
+                RequestParameter::new(ParameterLocation::Header, "content-type", "application/json"),
+            ]
+        );
+        assert_eq!(
+            endpoints[0].request_media_type,
+            Some(MediaType::new("application/json"))
+        );
+        assert_eq!(
+            endpoints[0].response_media_type,
+            Some(MediaType::new("application/json"))
+        );
+    }
+
+    #[test]
+    fn parses_graphql_fields_of_exchange() {
+        let mut runtime = create_mock_runtime();
+        runtime.openapi_source = OpenapiSource::Graphql(vec![Box::from(Path::new("dump/graphql/schema.graphql"))]);
+        let exchange = create_exchange("POST", "/graphql", "{\"query\": \"{ user(id: 1) { name } }\"}");
+
+        let endpoints = parse_exchange(Arc::new(runtime), &exchange, "/graphql").unwrap();
+        let fields: Vec<_> = endpoints.iter().filter_map(|x| x.graphql_field.as_deref()).collect();
+        assert_eq!(fields, vec!["query.user.name", "query.user"]);
+    }
+
+    #[test]
+    fn ignores_unknown_methods() {
+        let exchange = create_exchange("CONNECT", "/", "");
+        assert!(parse_exchange(Arc::new(create_mock_runtime()), &exchange, "/graphql")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,900 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    path::Path,
+    sync::Arc,
+};
+
+use json::JsonValue;
+
+use crate::{
+    config::Runtime,
+    models::{EndpointConfiguration, Method, Operation, Response, SourceLocation, StatusCode},
+    utils::{read_file_to_string_or_err, Error},
+};
+
+use super::get_extended_path;
+
+const QUERY_OPERATION: &str = "query";
+const MUTATION_OPERATION: &str = "mutation";
+const SUBSCRIPTION_OPERATION: &str = "subscription";
+const DEFAULT_QUERY_TYPE: &str = "Query";
+const DEFAULT_MUTATION_TYPE: &str = "Mutation";
+const DEPRECATED_DIRECTIVE: &str = "deprecated";
+const INTROSPECTION_PREFIX: &str = "__";
+/// Token the lexer emits for the spread operator `...`.
+const SPREAD: char = '.';
+
+/// Syntax error in a GraphQL schema or query, along with the position it occurs at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphqlSyntaxError {
+    pub location: SourceLocation,
+    pub message: String,
+}
+
+impl Display for GraphqlSyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// Loads all schema files of a GraphQL source into one schema. Like with openapi specs, files
+/// without a pre merge version are skipped when reading the pre merge schema of several files.
+pub fn load_graphql_schema(
+    graphql_paths: &[Box<Path>],
+    mount_point: &str,
+    path_extension: &str,
+) -> Result<GraphqlSchema, Error> {
+    let mut schema = GraphqlSchema::default();
+    for graphql_path in graphql_paths {
+        let extended_path = get_extended_path(mount_point, graphql_path, path_extension)?;
+        if graphql_paths.len() > 1 && !path_extension.is_empty() && !extended_path.is_file() {
+            continue;
+        }
+
+        let source = read_file_to_string_or_err(
+            &extended_path,
+            Error::ProblemOpeningFile(Box::from(extended_path.as_path())),
+        )?;
+        if let Err(error) = schema.add_document(&source) {
+            return Err(Error::InvalidGraphqlSyntax(graphql_path.clone(), error.to_string()));
+        }
+    }
+    Ok(schema)
+}
+
+/// Root types and the fields of all object and interface types of a GraphQL schema, which can be
+/// spread over several SDL files.
+#[derive(Debug, Default)]
+pub struct GraphqlSchema {
+    root_types: HashMap<String, String>,
+    types: HashMap<String, Vec<SchemaField>>,
+}
+
+#[derive(Debug, Clone)]
+struct SchemaField {
+    name: String,
+    type_name: String,
+    deprecated: bool,
+    location: SourceLocation,
+}
+
+impl GraphqlSchema {
+    /// Adds the definitions of an SDL document to the schema, `extend` definitions add their
+    /// fields to the type they extend.
+    pub fn add_document(&mut self, source: &str) -> Result<(), GraphqlSyntaxError> {
+        let mut tokens = TokenStream::new(source)?;
+        while tokens.peek().is_some() {
+            // descriptions don't affect the coverage
+            if tokens.peek() == Some(&Token::Value) {
+                tokens.next()?;
+                continue;
+            }
+            let keyword = tokens.expect_name()?;
+            self.add_definition(&mut tokens, &keyword)?;
+        }
+        Ok(())
+    }
+
+    fn add_definition(&mut self, tokens: &mut TokenStream, keyword: &str) -> Result<(), GraphqlSyntaxError> {
+        match keyword {
+            "extend" => {
+                let keyword = tokens.expect_name()?;
+                self.add_definition(tokens, &keyword)?;
+            }
+            "schema" => {
+                tokens.parse_directives()?;
+                tokens.expect_punctuator('{')?;
+                while !tokens.consume_punctuator('}') {
+                    let operation = tokens.expect_name()?;
+                    tokens.expect_punctuator(':')?;
+                    let type_name = tokens.expect_name()?;
+                    self.root_types.insert(operation, type_name);
+                }
+            }
+            "type" | "interface" | "input" => {
+                let type_name = tokens.expect_name()?;
+                if tokens.consume_name("implements") {
+                    tokens.consume_punctuator('&');
+                    tokens.expect_name()?;
+                    while tokens.consume_punctuator('&') {
+                        tokens.expect_name()?;
+                    }
+                }
+                tokens.parse_directives()?;
+                if tokens.is_punctuator('{') {
+                    let fields = parse_field_definitions(tokens)?;
+                    // input types can't be selected, so only their syntax matters
+                    if keyword != "input" {
+                        self.types.entry(type_name).or_default().extend(fields);
+                    }
+                }
+            }
+            "enum" => {
+                tokens.expect_name()?;
+                tokens.parse_directives()?;
+                if tokens.is_punctuator('{') {
+                    tokens.skip_balanced('{', '}')?;
+                }
+            }
+            "union" => {
+                tokens.expect_name()?;
+                tokens.parse_directives()?;
+                if tokens.consume_punctuator('=') {
+                    tokens.consume_punctuator('|');
+                    tokens.expect_name()?;
+                    while tokens.consume_punctuator('|') {
+                        tokens.expect_name()?;
+                    }
+                }
+            }
+            "scalar" => {
+                tokens.expect_name()?;
+                tokens.parse_directives()?;
+            }
+            "directive" => {
+                tokens.expect_punctuator('@')?;
+                tokens.expect_name()?;
+                if tokens.is_punctuator('(') {
+                    tokens.skip_balanced('(', ')')?;
+                }
+                tokens.consume_name("repeatable");
+                if !tokens.consume_name("on") {
+                    return Err(tokens.error("expected \"on\""));
+                }
+                tokens.consume_punctuator('|');
+                tokens.expect_name()?;
+                while tokens.consume_punctuator('|') {
+                    tokens.expect_name()?;
+                }
+            }
+            keyword => return Err(tokens.error(&format!("unexpected \"{}\"", keyword))),
+        }
+        Ok(())
+    }
+
+    fn get_root_type(&self, operation: &str) -> &str {
+        match self.root_types.get(operation) {
+            Some(root_type) => root_type,
+            None if operation == MUTATION_OPERATION => DEFAULT_MUTATION_TYPE,
+            None => DEFAULT_QUERY_TYPE,
+        }
+    }
+
+    /// Generates an endpoint for every query and mutation of the schema. They are identified by
+    /// their operation type and name, like `query.user`. With `include_fields`, the fields of
+    /// the types they return, like `query.user.email`, are added as well.
+    pub fn get_endpoints(
+        &self,
+        graphql_path: &str,
+        include_fields: bool,
+        runtime: Arc<Runtime>,
+    ) -> Result<Vec<EndpointConfiguration>, Error> {
+        let mut endpoints = vec![];
+        for operation in [QUERY_OPERATION, MUTATION_OPERATION] {
+            let root_fields = match self.types.get(self.get_root_type(operation)) {
+                Some(root_fields) => root_fields,
+                None => continue,
+            };
+
+            let mut field_names = vec![];
+            for root_field in root_fields {
+                let field_name = format!("{}.{}", operation, root_field.name);
+                if field_names.contains(&field_name) {
+                    continue;
+                }
+                field_names.push(field_name.clone());
+                endpoints.push(create_field_endpoint(
+                    graphql_path,
+                    operation,
+                    &field_name,
+                    root_field,
+                    runtime.clone(),
+                )?);
+
+                if !include_fields {
+                    continue;
+                }
+                for field in self.types.get(&root_field.type_name).into_iter().flatten() {
+                    let field_name = format!("{}.{}.{}", operation, root_field.name, field.name);
+                    if !field_names.contains(&field_name) {
+                        field_names.push(field_name.clone());
+                        endpoints.push(create_field_endpoint(
+                            graphql_path,
+                            operation,
+                            &field_name,
+                            field,
+                            runtime.clone(),
+                        )?);
+                    }
+                }
+            }
+        }
+        Ok(endpoints)
+    }
+}
+
+/// GraphQL fields are represented by an operation of their own, so they can be selected by their
+/// name through `operationId:` and by their operation type through `tag:`.
+fn create_field_endpoint(
+    graphql_path: &str,
+    operation_type: &str,
+    field_name: &str,
+    field: &SchemaField,
+    runtime: Arc<Runtime>,
+) -> Result<EndpointConfiguration, Error> {
+    // graphql responds with 200 even if the operation failed
+    let status_code = StatusCode::Range(2);
+    let operation = Operation {
+        method: Method::POST,
+        path: graphql_path.to_string(),
+        operation_id: Some(field_name.to_string()),
+        summary: None,
+        deprecated: field.deprecated,
+        servers: vec![],
+        parameters: vec![],
+        request_media_types: vec![],
+        responses: vec![Response {
+            status_code,
+            media_types: vec![],
+        }],
+        security: None,
+        tags: vec![operation_type.to_string()],
+        extensions: BTreeMap::new(),
+        location: Some(field.location),
+    };
+
+    Ok(
+        EndpointConfiguration::new(Method::POST, graphql_path, status_code, runtime, false)?
+            .with_operation(&Arc::new(operation))
+            .with_graphql_field(Some(field_name.to_string())),
+    )
+}
+
+fn parse_field_definitions(tokens: &mut TokenStream) -> Result<Vec<SchemaField>, GraphqlSyntaxError> {
+    let mut fields = vec![];
+    tokens.expect_punctuator('{')?;
+    while !tokens.consume_punctuator('}') {
+        if tokens.peek() == Some(&Token::Value) {
+            tokens.next()?;
+            continue;
+        }
+        let location = tokens.location();
+        let name = tokens.expect_name()?;
+        if tokens.is_punctuator('(') {
+            tokens.skip_balanced('(', ')')?;
+        }
+        tokens.expect_punctuator(':')?;
+        let type_name = tokens.parse_type()?;
+        // default values of input fields
+        if tokens.consume_punctuator('=') {
+            tokens.skip_value()?;
+        }
+        let directives = tokens.parse_directives()?;
+
+        fields.push(SchemaField {
+            name,
+            type_name,
+            deprecated: directives.iter().any(|x| x == DEPRECATED_DIRECTIVE),
+            location,
+        });
+    }
+    Ok(fields)
+}
+
+/// Extracts the fields a GraphQL request selects from its body, which is either a json object
+/// with a `query`, a batch of them or the raw query. Root fields are named like `query.user`,
+/// the fields selected on them like `query.user.email`.
+pub fn get_request_fields(body: &str) -> Vec<String> {
+    let requests = match json::parse(body) {
+        Ok(JsonValue::Array(requests)) => requests,
+        Ok(request) if request.is_object() => vec![request],
+        _ => {
+            let mut request = JsonValue::new_object();
+            request["query"] = body.into();
+            vec![request]
+        }
+    };
+
+    let mut fields = vec![];
+    for request in requests {
+        let query = match request["query"].as_str() {
+            Some(query) => query,
+            None => continue,
+        };
+        // malformed queries are rejected by the server, so they can't cover anything
+        let (operations, fragments) = match parse_executable_document(query) {
+            Ok(document) => document,
+            Err(_) => continue,
+        };
+
+        let operation_name = request["operationName"].as_str();
+        for operation in &operations {
+            if operation_name.is_some() && operations.len() > 1 && operation.name.as_deref() != operation_name {
+                continue;
+            }
+            for (root_field, selections) in collect_fields(&operation.selections, &fragments, &mut vec![]) {
+                let root_field_name = format!("{}.{}", operation.operation_type, root_field);
+                for (field, _) in collect_fields(selections, &fragments, &mut vec![]) {
+                    push_unique(&mut fields, format!("{}.{}", root_field_name, field));
+                }
+                push_unique(&mut fields, root_field_name);
+            }
+        }
+    }
+    fields
+}
+
+fn push_unique(fields: &mut Vec<String>, field: String) {
+    if !fields.contains(&field) {
+        fields.push(field);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Selection {
+    Field(String, Vec<Selection>),
+    FragmentSpread(String),
+    InlineFragment(Vec<Selection>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OperationDefinition {
+    operation_type: String,
+    name: Option<String>,
+    selections: Vec<Selection>,
+}
+
+/// Resolves fragments and returns the names of the selected fields, along with their own
+/// selections. Introspection fields like `__typename` are left out.
+fn collect_fields<'a>(
+    selections: &'a [Selection],
+    fragments: &'a HashMap<String, Vec<Selection>>,
+    visited_fragments: &mut Vec<&'a str>,
+) -> Vec<(&'a str, &'a [Selection])> {
+    let mut fields = vec![];
+    for selection in selections {
+        match selection {
+            Selection::Field(name, selections) => {
+                if !name.starts_with(INTROSPECTION_PREFIX) {
+                    fields.push((name.as_str(), selections.as_slice()));
+                }
+            }
+            Selection::FragmentSpread(name) => {
+                if let Some(fragment) = fragments.get(name) {
+                    if !visited_fragments.contains(&name.as_str()) {
+                        visited_fragments.push(name);
+                        fields.append(&mut collect_fields(fragment, fragments, visited_fragments));
+                    }
+                }
+            }
+            Selection::InlineFragment(selections) => {
+                fields.append(&mut collect_fields(selections, fragments, visited_fragments));
+            }
+        }
+    }
+    fields
+}
+
+type ExecutableDocument = (Vec<OperationDefinition>, HashMap<String, Vec<Selection>>);
+
+fn parse_executable_document(source: &str) -> Result<ExecutableDocument, GraphqlSyntaxError> {
+    let mut tokens = TokenStream::new(source)?;
+    let mut operations = vec![];
+    let mut fragments = HashMap::new();
+
+    while tokens.peek().is_some() {
+        // a selection set on its own is a shorthand for an anonymous query
+        if tokens.is_punctuator('{') {
+            operations.push(OperationDefinition {
+                operation_type: QUERY_OPERATION.to_string(),
+                name: None,
+                selections: parse_selection_set(&mut tokens)?,
+            });
+            continue;
+        }
+
+        let keyword = tokens.expect_name()?;
+        match keyword.as_str() {
+            QUERY_OPERATION | MUTATION_OPERATION | SUBSCRIPTION_OPERATION => {
+                let name = match tokens.peek() {
+                    Some(Token::Name(_)) => Some(tokens.expect_name()?),
+                    _ => None,
+                };
+                if tokens.is_punctuator('(') {
+                    tokens.skip_balanced('(', ')')?;
+                }
+                tokens.parse_directives()?;
+                operations.push(OperationDefinition {
+                    operation_type: keyword,
+                    name,
+                    selections: parse_selection_set(&mut tokens)?,
+                });
+            }
+            "fragment" => {
+                let name = tokens.expect_name()?;
+                if !tokens.consume_name("on") {
+                    return Err(tokens.error("expected \"on\""));
+                }
+                tokens.expect_name()?;
+                tokens.parse_directives()?;
+                fragments.insert(name, parse_selection_set(&mut tokens)?);
+            }
+            keyword => return Err(tokens.error(&format!("unexpected \"{}\"", keyword))),
+        }
+    }
+    Ok((operations, fragments))
+}
+
+fn parse_selection_set(tokens: &mut TokenStream) -> Result<Vec<Selection>, GraphqlSyntaxError> {
+    let mut selections = vec![];
+    tokens.expect_punctuator('{')?;
+    while !tokens.consume_punctuator('}') {
+        if tokens.consume_punctuator(SPREAD) {
+            let is_fragment_spread = matches!(tokens.peek(), Some(Token::Name(name)) if name != "on");
+            if is_fragment_spread {
+                let name = tokens.expect_name()?;
+                tokens.parse_directives()?;
+                selections.push(Selection::FragmentSpread(name));
+            } else {
+                if tokens.consume_name("on") {
+                    tokens.expect_name()?;
+                }
+                tokens.parse_directives()?;
+                selections.push(Selection::InlineFragment(parse_selection_set(tokens)?));
+            }
+            continue;
+        }
+
+        let mut name = tokens.expect_name()?;
+        // the field name follows its alias
+        if tokens.consume_punctuator(':') {
+            name = tokens.expect_name()?;
+        }
+        if tokens.is_punctuator('(') {
+            tokens.skip_balanced('(', ')')?;
+        }
+        tokens.parse_directives()?;
+        let field_selections = if tokens.is_punctuator('{') {
+            parse_selection_set(tokens)?
+        } else {
+            vec![]
+        };
+        selections.push(Selection::Field(name, field_selections));
+    }
+    Ok(selections)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Name(String),
+    Punctuator(char),
+    /// Strings and numbers, their content doesn't matter for the coverage.
+    Value,
+}
+
+struct TokenStream {
+    tokens: Vec<(Token, SourceLocation)>,
+    index: usize,
+    end_location: SourceLocation,
+}
+
+impl TokenStream {
+    fn new(source: &str) -> Result<TokenStream, GraphqlSyntaxError> {
+        let (tokens, end_location) = tokenize(source)?;
+        Ok(TokenStream {
+            tokens,
+            index: 0,
+            end_location,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|x| &x.0)
+    }
+
+    fn location(&self) -> SourceLocation {
+        match self.tokens.get(self.index) {
+            Some((_, location)) => *location,
+            None => self.end_location,
+        }
+    }
+
+    fn error(&self, message: &str) -> GraphqlSyntaxError {
+        GraphqlSyntaxError {
+            location: self.location(),
+            message: message.to_string(),
+        }
+    }
+
+    fn next(&mut self) -> Result<Token, GraphqlSyntaxError> {
+        match self.tokens.get(self.index) {
+            Some((token, _)) => {
+                self.index += 1;
+                Ok(token.clone())
+            }
+            None => Err(self.error("unexpected end of document")),
+        }
+    }
+
+    fn is_punctuator(&self, punctuator: char) -> bool {
+        self.peek() == Some(&Token::Punctuator(punctuator))
+    }
+
+    fn consume_punctuator(&mut self, punctuator: char) -> bool {
+        let is_punctuator = self.is_punctuator(punctuator);
+        if is_punctuator {
+            self.index += 1;
+        }
+        is_punctuator
+    }
+
+    fn consume_name(&mut self, name: &str) -> bool {
+        let is_name = matches!(self.peek(), Some(Token::Name(x)) if x == name);
+        if is_name {
+            self.index += 1;
+        }
+        is_name
+    }
+
+    fn expect_punctuator(&mut self, punctuator: char) -> Result<(), GraphqlSyntaxError> {
+        if self.consume_punctuator(punctuator) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected \"{}\"", punctuator)))
+        }
+    }
+
+    fn expect_name(&mut self) -> Result<String, GraphqlSyntaxError> {
+        match self.peek() {
+            Some(Token::Name(name)) => {
+                let name = name.to_string();
+                self.index += 1;
+                Ok(name)
+            }
+            _ => Err(self.error("expected a name")),
+        }
+    }
+
+    /// Skips a bracketed part like arguments, including all nested brackets.
+    fn skip_balanced(&mut self, open: char, close: char) -> Result<(), GraphqlSyntaxError> {
+        self.expect_punctuator(open)?;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next()? {
+                Token::Punctuator(punctuator) if punctuator == open => depth += 1,
+                Token::Punctuator(punctuator) if punctuator == close => depth -= 1,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn skip_value(&mut self) -> Result<(), GraphqlSyntaxError> {
+        if self.is_punctuator('[') {
+            self.skip_balanced('[', ']')
+        } else if self.is_punctuator('{') {
+            self.skip_balanced('{', '}')
+        } else {
+            if self.consume_punctuator('$') {
+                self.expect_name()?;
+            } else {
+                self.next()?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Parses the directives at the current position and returns their names.
+    fn parse_directives(&mut self) -> Result<Vec<String>, GraphqlSyntaxError> {
+        let mut directives = vec![];
+        while self.consume_punctuator('@') {
+            directives.push(self.expect_name()?);
+            if self.is_punctuator('(') {
+                self.skip_balanced('(', ')')?;
+            }
+        }
+        Ok(directives)
+    }
+
+    /// Parses a type reference like `[User!]!` and returns the name of the type it wraps.
+    fn parse_type(&mut self) -> Result<String, GraphqlSyntaxError> {
+        let type_name = if self.consume_punctuator('[') {
+            let type_name = self.parse_type()?;
+            self.expect_punctuator(']')?;
+            type_name
+        } else {
+            self.expect_name()?
+        };
+        self.consume_punctuator('!');
+        Ok(type_name)
+    }
+}
+
+/// Splits a GraphQL document into tokens, commas and comments are ignored like whitespace.
+/// Returns the tokens along with the position at the end of the document.
+fn tokenize(source: &str) -> Result<(Vec<(Token, SourceLocation)>, SourceLocation), GraphqlSyntaxError> {
+    let characters: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut index = 0;
+    let mut line = 1;
+    let mut line_start = 0;
+
+    while index < characters.len() {
+        let location = SourceLocation {
+            line,
+            column: index - line_start + 1,
+        };
+        let character = characters[index];
+
+        match character {
+            '\n' => {
+                line += 1;
+                line_start = index + 1;
+                index += 1;
+            }
+            '#' => {
+                while index < characters.len() && characters[index] != '\n' {
+                    index += 1;
+                }
+            }
+            character if character.is_whitespace() || character == ',' || character == '\u{feff}' => index += 1,
+            '!' | '$' | '&' | '(' | ')' | ':' | '=' | '@' | '[' | ']' | '{' | '|' | '}' => {
+                tokens.push((Token::Punctuator(character), location));
+                index += 1;
+            }
+            '.' if characters[index..].starts_with(&['.', '.', '.']) => {
+                tokens.push((Token::Punctuator(SPREAD), location));
+                index += 3;
+            }
+            '"' => {
+                let is_block_string = characters[index..].starts_with(&['"', '"', '"']);
+                index += if is_block_string { 3 } else { 1 };
+                loop {
+                    match characters.get(index) {
+                        None => {
+                            return Err(GraphqlSyntaxError {
+                                location,
+                                message: "unterminated string".to_string(),
+                            })
+                        }
+                        Some('\\') => index += 2,
+                        Some('"') if !is_block_string => {
+                            index += 1;
+                            break;
+                        }
+                        Some('"') if characters[index..].starts_with(&['"', '"', '"']) => {
+                            index += 3;
+                            break;
+                        }
+                        Some('\n') if is_block_string => {
+                            line += 1;
+                            line_start = index + 1;
+                            index += 1;
+                        }
+                        Some('\n') => {
+                            return Err(GraphqlSyntaxError {
+                                location,
+                                message: "unterminated string".to_string(),
+                            })
+                        }
+                        Some(_) => index += 1,
+                    }
+                }
+                tokens.push((Token::Value, location));
+            }
+            character if character == '-' || character.is_ascii_digit() => {
+                index += 1;
+                while index < characters.len()
+                    && (characters[index].is_ascii_alphanumeric() || ['.', '+', '-'].contains(&characters[index]))
+                {
+                    index += 1;
+                }
+                tokens.push((Token::Value, location));
+            }
+            character if character == '_' || character.is_ascii_alphabetic() => {
+                let start = index;
+                while index < characters.len()
+                    && (characters[index] == '_' || characters[index].is_ascii_alphanumeric())
+                {
+                    index += 1;
+                }
+                tokens.push((Token::Name(characters[start..index].iter().collect()), location));
+            }
+            character => {
+                return Err(GraphqlSyntaxError {
+                    location,
+                    message: format!("unexpected character \"{}\"", character),
+                })
+            }
+        }
+    }
+
+    let end_location = SourceLocation {
+        line,
+        column: index - line_start + 1,
+    };
+    Ok((tokens, end_location))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{models::SourceLocation, utils::test::create_mock_runtime};
+
+    use super::{get_request_fields, GraphqlSchema};
+
+    const SCHEMA: &str = r#"
+"""
+Entry point of the api
+"""
+type Query {
+  "A single user"
+  user(id: ID!, filter: Filter = { active: true }): User
+  users(first: Int = 10): [User!]! @deprecated(reason: "use search")
+}
+
+extend type Query {
+  search(term: String!): [User!]!
+}
+
+type Mutation {
+  createUser(input: CreateUser!): User
+}
+
+# users and everything they own
+type User implements Node & Named @key(fields: "id") {
+  id: ID!
+  name: String
+  orders: [Order!]!
+}
+
+interface Node { id: ID! }
+interface Named { name: String }
+input CreateUser { name: String! = "anonymous" }
+input Filter { active: Boolean }
+enum Order { PENDING SHIPPED }
+union Result = | User | Order
+scalar Date @specifiedBy(url: "https://example.com")
+directive @key(fields: String!) repeatable on OBJECT | INTERFACE
+"#;
+
+    fn get_schema_fields(include_fields: bool) -> Vec<String> {
+        let mut schema = GraphqlSchema::default();
+        schema.add_document(SCHEMA).unwrap();
+        schema
+            .get_endpoints("/graphql", include_fields, Arc::new(create_mock_runtime()))
+            .unwrap()
+            .iter()
+            .map(|x| x.graphql_field.clone().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn lists_queries_and_mutations_of_schema() {
+        assert_eq!(
+            get_schema_fields(false),
+            vec!["query.user", "query.users", "query.search", "mutation.createUser"]
+        );
+    }
+
+    #[test]
+    fn lists_fields_of_returned_types() {
+        assert_eq!(
+            get_schema_fields(true),
+            vec![
+                "query.user",
+                "query.user.id",
+                "query.user.name",
+                "query.user.orders",
+                "query.users",
+                "query.users.id",
+                "query.users.name",
+                "query.users.orders",
+                "query.search",
+                "query.search.id",
+                "query.search.name",
+                "query.search.orders",
+                "mutation.createUser",
+                "mutation.createUser.id",
+                "mutation.createUser.name",
+                "mutation.createUser.orders",
+            ]
+        );
+    }
+
+    #[test]
+    fn marks_deprecated_fields() {
+        let mut schema = GraphqlSchema::default();
+        schema.add_document(SCHEMA).unwrap();
+        let endpoints = schema
+            .get_endpoints("/graphql", false, Arc::new(create_mock_runtime()))
+            .unwrap();
+
+        assert!(!endpoints[0].is_deprecated());
+        assert!(endpoints[1].is_deprecated());
+        assert_eq!(
+            endpoints[1].operation.as_ref().unwrap().location,
+            Some(SourceLocation { line: 8, column: 3 })
+        );
+    }
+
+    #[test]
+    fn uses_renamed_root_types() {
+        let mut schema = GraphqlSchema::default();
+        schema
+            .add_document("schema { query: Root }\ntype Root { version: String }")
+            .unwrap();
+        assert_eq!(
+            schema
+                .get_endpoints("/graphql", false, Arc::new(create_mock_runtime()))
+                .unwrap()[0]
+                .graphql_field,
+            Some("query.version".to_string())
+        );
+    }
+
+    #[test]
+    fn locates_syntax_errors() {
+        let mut schema = GraphqlSchema::default();
+        let error = schema
+            .add_document("type Query {\n  user(id: ID!) User\n}")
+            .unwrap_err();
+        assert_eq!(error.location, SourceLocation { line: 2, column: 17 });
+    }
+
+    #[test]
+    fn extracts_fields_of_request() {
+        let body = r#"{
+            "query": "query GetUser($id: ID!) { me: user(id: $id) { ...UserFields ... on User { orders } __typename } }\nfragment UserFields on User { id name }",
+            "variables": { "id": "1" }
+        }"#;
+        assert_eq!(
+            get_request_fields(body),
+            vec!["query.user.id", "query.user.name", "query.user.orders", "query.user"]
+        );
+    }
+
+    #[test]
+    fn extracts_fields_of_named_operation() {
+        let body = r#"{
+            "query": "query A { users { id } } mutation B { createUser(input: { name: \"x\" }) { id } }",
+            "operationName": "B"
+        }"#;
+        assert_eq!(
+            get_request_fields(body),
+            vec!["mutation.createUser.id", "mutation.createUser"]
+        );
+    }
+
+    #[test]
+    fn extracts_fields_of_batches_and_raw_queries() {
+        assert_eq!(
+            get_request_fields(r#"[{ "query": "{ users { id } }" }, { "query": "{ search(term: \"a\") { id } }" }]"#),
+            vec!["query.users.id", "query.users", "query.search.id", "query.search"]
+        );
+        assert_eq!(get_request_fields("{ users }"), vec!["query.users"]);
+        assert!(get_request_fields(r#"{ "query": "{ users " }"#).is_empty());
+    }
+}
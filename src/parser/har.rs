@@ -0,0 +1,298 @@
+use std::{fs, path::Path, sync::Arc};
+
+use json::JsonValue;
+use url::Url;
+
+use super::{
+    common::rewrite_host,
+    exchange::parse_exchange,
+    nginx_parser::{LogDiagnostics, LogRejection},
+};
+use crate::{
+    config::{HostRewrite, Runtime},
+    models::{EndpointConfiguration, Exchange, Method},
+    utils::{print_debug_message, Error},
+};
+
+/// Parses the entries of a HAR file, as exported by browsers and HTTP clients, into the endpoints
+/// they called. Entries that can't be parsed are counted and skipped unless the file is parsed
+/// strictly.
+pub fn parse_har_file(
+    runtimes: &[Arc<Runtime>],
+    path: &Path,
+    graphql_path: &str,
+    host_rewrites: &[HostRewrite],
+    strict: bool,
+) -> Result<(Vec<EndpointConfiguration>, LogDiagnostics), Error> {
+    let har_str = match fs::read_to_string(path) {
+        Ok(har_str) => har_str,
+        Err(why) => {
+            print_debug_message(why.to_string());
+            return Err(Error::ProblemOpeningFile(Box::from(path)));
+        }
+    };
+    let har = match json::parse(&har_str) {
+        Ok(har) if har["log"]["entries"].is_array() => har,
+        _ => return Err(Error::InvalidHarFile(Box::from(path))),
+    };
+
+    let mut endpoints = vec![];
+    let mut diagnostics = LogDiagnostics::default();
+    for (index, entry) in har["log"]["entries"].members().enumerate() {
+        match parse_har_entry(runtimes, entry, graphql_path, host_rewrites) {
+            Ok(mut entry_endpoints) => {
+                diagnostics.parsed_lines += 1;
+                endpoints.append(&mut entry_endpoints);
+            }
+            Err(rejection) => {
+                if strict && !rejection.is_tolerated() {
+                    return Err(Error::InvalidHarEntry(
+                        Box::from(path),
+                        index + 1,
+                        rejection.to_string(),
+                    ));
+                }
+                print_debug_message(format!("Skipping entry {} of {:?} {}", index + 1, path, rejection));
+                *diagnostics.rejected_lines.entry(rejection).or_insert(0) += 1;
+            }
+        }
+    }
+
+    Ok((endpoints, diagnostics))
+}
+
+fn parse_har_entry(
+    runtimes: &[Arc<Runtime>],
+    entry: &JsonValue,
+    graphql_path: &str,
+    host_rewrites: &[HostRewrite],
+) -> Result<Vec<EndpointConfiguration>, LogRejection> {
+    let request = &entry["request"];
+    let method = match request["method"].as_str() {
+        Some(method) if Method::from_str(method).is_some() => method,
+        Some(_) => return Err(LogRejection::UnknownMethod),
+        None => return Err(LogRejection::InvalidRequest),
+    };
+    let url = match request["url"].as_str().map(Url::parse) {
+        Some(Ok(url)) => url,
+        _ => return Err(LogRejection::InvalidRequest),
+    };
+    // browsers record requests that were blocked or aborted with a status of 0
+    let status = match entry["response"]["status"].as_u16() {
+        Some(status) if (100..600).contains(&status) => status,
+        _ => return Err(LogRejection::InvalidStatus),
+    };
+    let (runtime, request_url) = find_runtime_by_url(runtimes, &url, host_rewrites)?;
+
+    let exchange = Exchange {
+        method: method.to_string(),
+        url: request_url,
+        request_headers: parse_headers(&request["headers"]),
+        request_body: request["postData"]["text"].as_str().unwrap_or("").as_bytes().to_vec(),
+        status,
+        response_headers: parse_headers(&entry["response"]["headers"]),
+    };
+    match parse_exchange(runtime, &exchange, graphql_path) {
+        Ok(endpoints) => Ok(endpoints),
+        Err(_) => Err(LogRejection::InvalidPath),
+    }
+}
+
+/// Finds the runtime whose application the url was sent to and returns the path and query the
+/// proxy would have received, without the path of the application url. Urls of other hosts are
+/// assumed to have been sent to CoveAPI, so they belong to the runtime listening on their port.
+fn find_runtime_by_url(
+    runtimes: &[Arc<Runtime>],
+    url: &Url,
+    host_rewrites: &[HostRewrite],
+) -> Result<(Arc<Runtime>, String), LogRejection> {
+    let url = rewrite_host(url, host_rewrites);
+    let mut request_url = url.path().to_string();
+    if let Some(query) = url.query() {
+        request_url.push('?');
+        request_url.push_str(query);
+    }
+
+    // the runtime with the longest application path wins, if several applications share a host
+    let mut matching_runtime: Option<(&Arc<Runtime>, usize)> = None;
+    for runtime in runtimes {
+        let app_base_url = rewrite_host(&runtime.app_base_url, host_rewrites);
+        if app_base_url.scheme() != url.scheme()
+            || app_base_url.host_str() != url.host_str()
+            || app_base_url.port_or_known_default() != url.port_or_known_default()
+        {
+            continue;
+        }
+
+        let base_path = app_base_url.path().trim_end_matches('/');
+        let is_within_base_path = match request_url.strip_prefix(base_path) {
+            Some(rest) => rest.is_empty() || rest.starts_with(['/', '?']),
+            None => false,
+        };
+        if is_within_base_path && !matches!(matching_runtime, Some((_, length)) if length >= base_path.len()) {
+            matching_runtime = Some((runtime, base_path.len()));
+        }
+    }
+
+    if let Some((runtime, base_path_length)) = matching_runtime {
+        let relative_url = &request_url[base_path_length..];
+        if relative_url.starts_with('/') {
+            return Ok((runtime.clone(), relative_url.to_string()));
+        }
+        return Ok((runtime.clone(), format!("/{}", relative_url)));
+    }
+
+    match url.port_or_known_default() {
+        Some(port) => match runtimes.iter().find(|x| x.port == port) {
+            Some(runtime) => Ok((runtime.clone(), request_url)),
+            None => Err(LogRejection::UnknownUrl),
+        },
+        None => Err(LogRejection::UnknownUrl),
+    }
+}
+
+/// Reads the name and value of every header, leaving out the pseudo-headers of HTTP/2 like
+/// `:authority`.
+fn parse_headers(headers: &JsonValue) -> Vec<(String, String)> {
+    headers
+        .members()
+        .filter_map(|header| match (header["name"].as_str(), header["value"].as_str()) {
+            (Some(name), Some(value)) if !name.starts_with(':') => Some((name.to_string(), value.to_string())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::Path, str::FromStr, sync::Arc};
+
+    use url::Url;
+
+    use crate::{
+        config::{HostRewrite, Runtime},
+        models::{MediaType, Method, OpenapiPath, ParameterLocation, RequestParameter},
+        parser::nginx_parser::LogRejection,
+        utils::{test::create_mock_runtime, Error},
+    };
+
+    use super::{find_runtime_by_url, parse_har_file};
+
+    fn generate_runtimes() -> Vec<Arc<Runtime>> {
+        let mut api_runtime = create_mock_runtime();
+        api_runtime.app_base_url = Url::from_str("http://localhost:8080/api/").unwrap();
+        api_runtime.port = 13750;
+        let mut health_runtime = create_mock_runtime();
+        health_runtime.app_base_url = Url::from_str("http://localhost:8080").unwrap();
+        health_runtime.port = 13751;
+        vec![Arc::new(api_runtime), Arc::new(health_runtime)]
+    }
+
+    fn find_runtime_port(url: &str, host_rewrites: &[HostRewrite]) -> Result<(u16, String), LogRejection> {
+        find_runtime_by_url(&generate_runtimes(), &Url::from_str(url).unwrap(), host_rewrites)
+            .map(|(runtime, request_url)| (runtime.port, request_url))
+    }
+
+    #[test]
+    fn maps_urls_to_runtimes() {
+        assert_eq!(
+            find_runtime_port("http://localhost:8080/api/users?page=2", &[]),
+            Ok((13750, "/users?page=2".to_string()))
+        );
+        assert_eq!(
+            find_runtime_port("http://localhost:8080/api", &[]),
+            Ok((13750, "/".to_string()))
+        );
+        assert_eq!(
+            find_runtime_port("http://localhost:8080/apis", &[]),
+            Ok((13751, "/apis".to_string()))
+        );
+        assert_eq!(
+            find_runtime_port("http://127.0.0.1:13750/users", &[]),
+            Ok((13750, "/users".to_string()))
+        );
+        assert_eq!(
+            find_runtime_port("https://localhost:8080/health", &[]),
+            Err(LogRejection::UnknownUrl)
+        );
+    }
+
+    #[test]
+    fn rewrites_hosts_of_urls() {
+        let host_rewrites = [HostRewrite {
+            host: "localhost".to_string(),
+            replacement: "172.17.0.1".to_string(),
+        }];
+        let mut runtime = create_mock_runtime();
+        runtime.app_base_url = Url::from_str("http://172.17.0.1:8080").unwrap();
+
+        let (_, request_url) = find_runtime_by_url(
+            &[Arc::new(runtime)],
+            &Url::from_str("http://localhost:8080/health").unwrap(),
+            &host_rewrites,
+        )
+        .unwrap();
+        assert_eq!(request_url, "/health");
+    }
+
+    #[test]
+    fn imports_har_entries() {
+        let path = Path::new("./dump/traffic.har");
+        let (endpoints, diagnostics) = parse_har_file(&generate_runtimes(), path, "/graphql", &[], false).unwrap();
+
+        assert_eq!(endpoints.len(), 4);
+        assert_eq!(endpoints[0].method, Method::GET);
+        assert_eq!(endpoints[0].path, OpenapiPath::from_str("/users").unwrap());
+        assert_eq!(endpoints[0].status_code, 200);
+        assert_eq!(endpoints[0].runtime.port, 13750);
+        assert_eq!(
+            endpoints[0].response_media_type,
+            Some(MediaType::new("application/json"))
+        );
+        assert_eq!(
+            endpoints[0].parameters,
+            vec![
+                RequestParameter::new(ParameterLocation::Query, "page", "2"),
+                RequestParameter::new(ParameterLocation::Header, "x-request-id", "5f2a9c"),
+            ]
+        );
+        assert_eq!(endpoints[1].method, Method::POST);
+        assert_eq!(
+            endpoints[1].request_media_type,
+            Some(MediaType::new("application/json"))
+        );
+        assert_eq!(endpoints[2].path, OpenapiPath::from_str("/health").unwrap());
+        assert_eq!(endpoints[2].runtime.port, 13751);
+        assert_eq!(endpoints[3].status_code, 503);
+        assert_eq!(endpoints[3].runtime.port, 13751);
+
+        assert_eq!(diagnostics.parsed_lines, 4);
+        assert_eq!(
+            diagnostics.rejected_lines,
+            BTreeMap::from([
+                (LogRejection::InvalidRequest, 1),
+                (LogRejection::UnknownMethod, 1),
+                (LogRejection::InvalidStatus, 1),
+                (LogRejection::UnknownUrl, 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn fails_on_rejected_entries_when_strict() {
+        let path = Path::new("./dump/traffic.har");
+        assert!(matches!(
+            parse_har_file(&generate_runtimes(), path, "/graphql", &[], true),
+            Err(Error::InvalidHarEntry(_, 5, _))
+        ));
+    }
+
+    #[test]
+    fn rejects_files_without_entries() {
+        let path = Path::new("./dump/swagger.json");
+        assert!(matches!(
+            parse_har_file(&generate_runtimes(), path, "/graphql", &[], false),
+            Err(Error::InvalidHarFile(_))
+        ));
+    }
+}
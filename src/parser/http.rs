@@ -1,43 +1,264 @@
-use std::sync::Arc;
+use std::{
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use reqwest::blocking::Client;
+use url::Url;
 
 use crate::{
-    config::{OpenapiSource, Runtime},
-    models::EndpointConfiguration,
+    config::FetchConfig,
+    models::OpenapiDocument,
     utils::{print_debug_message, Error},
 };
 
-use super::{json_parser::parse_json_doc, yaml_parser::parse_yaml_doc};
+use super::{
+    common::rewrite_host, json_parser::load_json_doc, reference::ReferenceResolver, yaml_parser::load_yaml_doc,
+};
+
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(8);
 
-pub fn fetch_openapi_endpoints_for_runtime(runtime: Arc<Runtime>) -> Result<Vec<EndpointConfiguration>, Error> {
-    let mut openapi_url = match &runtime.openapi_source {
-        OpenapiSource::Url(openapi_url) => openapi_url.clone(),
-        OpenapiSource::Path(_) => return Err(Error::UnknownInternalError("ota fetch with path".to_string())),
+/// Fetches and parses the spec, which is stored in the snapshot file if one is given.
+pub fn fetch_openapi_document(
+    openapi_url: &Url,
+    fetch_config: &FetchConfig,
+    mount_point: &str,
+    openapi_snapshot: Option<&Path>,
+) -> Result<OpenapiDocument, Error> {
+    let openapi_spec = fetch_openapi_spec(openapi_url, fetch_config, mount_point)?;
+
+    // attempt to parse as json -> on syntax err attempt yaml
+    let document = match load_json_doc(&openapi_spec, ReferenceResolver::detached()) {
+        Ok(document) => document,
+        Err(Error::InvalidParseSyntax) => load_yaml_doc(&openapi_spec, ReferenceResolver::detached())?,
+        Err(error) => return Err(error),
     };
 
-    if openapi_url.host_str() == Some("localhost") {
-        // unwrap here is fine, since the IP address provided is allways valid
-        openapi_url.set_host(Some("172.17.0.1")).unwrap();
+    // only valid specs are stored, so a broken response doesn't replace a previous snapshot
+    if let Some(openapi_snapshot) = openapi_snapshot {
+        write_openapi_snapshot(openapi_snapshot, &openapi_spec)?;
+    }
+    Ok(document)
+}
+
+fn write_openapi_snapshot(openapi_snapshot: &Path, openapi_spec: &str) -> Result<(), Error> {
+    print_debug_message(format!("Storing the openapi spec in {:?}", openapi_snapshot));
+    if let Some(parent) = openapi_snapshot.parent() {
+        if std::fs::create_dir_all(parent).is_err() {
+            return Err(Error::ProblemWritingFile(Box::from(openapi_snapshot)));
+        }
+    }
+    match std::fs::write(openapi_snapshot, openapi_spec) {
+        Ok(()) => Ok(()),
+        Err(_) => Err(Error::ProblemWritingFile(Box::from(openapi_snapshot))),
+    }
+}
+
+/// Fetches the spec, failed requests are retried with an increasing delay until the retry
+/// deadline is reached, since the application might still be starting.
+pub fn fetch_openapi_spec(openapi_url: &Url, fetch_config: &FetchConfig, mount_point: &str) -> Result<String, Error> {
+    let openapi_url = rewrite_host(openapi_url, &fetch_config.host_rewrites);
+    let client = build_client(fetch_config, mount_point)?;
+
+    let started_at = Instant::now();
+    let mut attempt = 0;
+    loop {
+        let (error, is_retryable) = match fetch_spec_once(&client, &openapi_url, fetch_config) {
+            Ok(openapi_spec) => return Ok(openapi_spec),
+            Err(failure) => failure,
+        };
+
+        let delay = get_retry_delay(attempt);
+        if !is_retryable || started_at.elapsed() + delay > fetch_config.retry_deadline {
+            return Err(error);
+        }
+        print_debug_message(format!(
+            "Fetching the openapi spec failed, retrying in {:?}: {}",
+            delay,
+            error.get_error_msg()
+        ));
+        thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+/// Fetches the spec a single time, failures are returned along with whether they are worth
+/// retrying.
+fn fetch_spec_once(client: &Client, openapi_url: &Url, fetch_config: &FetchConfig) -> Result<String, (Error, bool)> {
+    let mut request = client.get(openapi_url.as_str());
+    for (name, value) in &fetch_config.headers {
+        request = request.header(name, value);
     }
 
     // note: using blocking client here because all following steps require it
-    let openapi_spec = match reqwest::blocking::get(openapi_url.as_str()) {
-        Ok(openapi_response) => match openapi_response.text() {
-            Ok(openapi_spec) => openapi_spec,
-            Err(why) => {
-                print_debug_message(format!("{}", why));
-                return Err(Error::OpenapiMalformedOnlineComponents);
-            }
-        },
+    let openapi_response = match request.send() {
+        Ok(openapi_response) => openapi_response,
         Err(why) => {
             print_debug_message(format!("{}", why));
-            return Err(Error::OpenapiFetchConnectionFailure);
+            let is_retryable = why.is_connect() || why.is_timeout() || why.is_request();
+            return Err((Error::OpenapiFetchConnectionFailure, is_retryable));
         }
     };
 
-    // attempt to parse as json -> on syntax err attempt yaml
-    match parse_json_doc(&openapi_spec, runtime.clone()) {
-        Ok(endpoints) => Ok(endpoints),
-        Err(Error::InvalidParseSyntax) => parse_yaml_doc(&openapi_spec, runtime.clone()),
-        Err(error) => Err(error),
+    let status = openapi_response.status();
+    if !status.is_success() {
+        // the application might still be starting or is overloaded
+        let is_retryable = status.is_server_error() || status.as_u16() == 408 || status.as_u16() == 429;
+        return Err((
+            Error::OpenapiFetchFailedStatus(openapi_url.to_string(), status.as_u16()),
+            is_retryable,
+        ));
+    }
+
+    match openapi_response.text() {
+        Ok(openapi_spec) => Ok(openapi_spec),
+        Err(why) => {
+            print_debug_message(format!("{}", why));
+            Err((Error::OpenapiMalformedOnlineComponents, false))
+        }
+    }
+}
+
+fn build_client(fetch_config: &FetchConfig, mount_point: &str) -> Result<Client, Error> {
+    let mut client_builder = Client::builder().timeout(fetch_config.timeout);
+
+    if let Some(ca_bundle) = &fetch_config.ca_bundle {
+        let ca_bundle_path = Path::new(mount_point).join(ca_bundle);
+        let ca_bundle_pem = match std::fs::read(&ca_bundle_path) {
+            Ok(ca_bundle_pem) => ca_bundle_pem,
+            Err(_) => return Err(Error::ProblemOpeningFile(Box::from(ca_bundle_path.as_path()))),
+        };
+        let certificates = match reqwest::Certificate::from_pem_bundle(&ca_bundle_pem) {
+            Ok(certificates) if !certificates.is_empty() => certificates,
+            _ => return Err(Error::InvalidCaBundle(ca_bundle.clone())),
+        };
+        for certificate in certificates {
+            client_builder = client_builder.add_root_certificate(certificate);
+        }
+    }
+
+    match client_builder.build() {
+        Ok(client) => Ok(client),
+        Err(why) => Err(Error::UnknownInternalError(format!("http client {}", why))),
+    }
+}
+
+fn get_retry_delay(attempt: u32) -> Duration {
+    INITIAL_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_RETRY_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        path::Path,
+        str::FromStr,
+        thread,
+        time::Duration,
+    };
+
+    use url::Url;
+
+    use crate::{config::FetchConfig, utils::Error};
+
+    use super::{build_client, fetch_openapi_document, fetch_openapi_spec, get_retry_delay};
+
+    /// Serves the given responses to the requests in order and returns the requests received.
+    fn serve_responses(responses: Vec<&'static str>) -> (Url, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::from_str(&format!(
+            "http://127.0.0.1:{}/openapi.json",
+            listener.local_addr().unwrap().port()
+        ))
+        .unwrap();
+
+        let handle = thread::spawn(move || {
+            let mut requests = vec![];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buffer = [0; 4096];
+                let length = stream.read(&mut buffer).unwrap();
+                requests.push(String::from_utf8_lossy(&buffer[..length]).to_string());
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn retries_until_spec_is_available() {
+        let (url, handle) = serve_responses(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+        ]);
+        let fetch_config = FetchConfig {
+            headers: vec![("Authorization".to_string(), "Bearer abc".to_string())],
+            retry_deadline: Duration::from_secs(5),
+            ..FetchConfig::default()
+        };
+
+        assert_eq!(fetch_openapi_spec(&url, &fetch_config, "./").unwrap(), "{}");
+        let requests = handle.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].to_lowercase().contains("authorization: bearer abc"));
+    }
+
+    #[test]
+    fn fails_on_client_errors() {
+        let (url, handle) = serve_responses(vec![
+            "HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+        let fetch_config = FetchConfig {
+            retry_deadline: Duration::from_secs(5),
+            ..FetchConfig::default()
+        };
+
+        assert!(matches!(
+            fetch_openapi_spec(&url, &fetch_config, "./"),
+            Err(Error::OpenapiFetchFailedStatus(_, 401))
+        ));
+        assert_eq!(handle.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn stores_fetched_spec_in_snapshot() {
+        let (url, handle) = serve_responses(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 33\r\nConnection: close\r\n\r\n{\"openapi\": \"3.0.0\", \"paths\": {}}",
+        ]);
+        let snapshot_dir = std::env::temp_dir().join(format!("coveapi-snapshot-{}", url.port().unwrap()));
+        let snapshot_path = snapshot_dir.join("openapi.json");
+
+        fetch_openapi_document(&url, &FetchConfig::default(), "./", Some(&snapshot_path)).unwrap();
+        handle.join().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&snapshot_path).unwrap(),
+            "{\"openapi\": \"3.0.0\", \"paths\": {}}"
+        );
+        std::fs::remove_dir_all(snapshot_dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_ca_bundles() {
+        let fetch_config = FetchConfig {
+            ca_bundle: Some(Box::from(Path::new("dump/swagger.yaml"))),
+            ..FetchConfig::default()
+        };
+        assert!(matches!(
+            build_client(&fetch_config, "./"),
+            Err(Error::InvalidCaBundle(_))
+        ));
+    }
+
+    #[test]
+    fn increases_retry_delay() {
+        assert_eq!(get_retry_delay(0), Duration::from_millis(500));
+        assert_eq!(get_retry_delay(2), Duration::from_secs(2));
+        assert_eq!(get_retry_delay(10), Duration::from_secs(8));
     }
 }
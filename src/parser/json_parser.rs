@@ -1,101 +1,14 @@
-use std::sync::Arc;
+use crate::{models::OpenapiDocument, utils::Error};
 
-use json::JsonValue;
-
-use crate::{
-    config::Runtime,
-    models::{EndpointConfiguration, Method},
-    utils::Error,
-};
-
-use super::common::format_basepath;
-
-pub fn parse_json_doc(json_string: &str, runtime: Arc<Runtime>) -> Result<Vec<EndpointConfiguration>, Error> {
-    let mut endpoints = vec![];
+use super::{document::load_openapi_document, location::SourceMap, reference::ReferenceResolver};
 
+pub fn load_json_doc(json_string: &str, mut resolver: ReferenceResolver) -> Result<OpenapiDocument, Error> {
     let json_obj = match json::parse(json_string) {
         Ok(json_obj) => json_obj,
         Err(_) => return Err(Error::InvalidParseSyntax),
     };
 
-    let base_path = match &json_obj["basePath"] {
-        JsonValue::Null => "",
-        base_path => match base_path.as_str() {
-            Some(base_path) => base_path,
-            None => return Err(Error::InvalidBasePath),
-        },
-    };
-    let base_path = format_basepath(base_path);
-
-    let paths = match &json_obj["paths"] {
-        json::Null => return Err(Error::InvalidParseSyntax),
-        responses => responses,
-    };
-
-    for path_json in paths.entries() {
-        let mut path = String::from(base_path);
-
-        match path_json.0 {
-            "/" => (),
-            _ => path.push_str(path_json.0),
-        }
-        if path.is_empty() {
-            path.push('/');
-        }
-
-        for (method, method_json) in get_methods_from_path(path_json.1)?.into_iter() {
-            let responses = match &method_json["responses"] {
-                json::Null => return Err(Error::InvalidParseSyntax),
-                responses => responses,
-            };
-
-            if !&method_json["security"].is_null() {
-                endpoints.push(EndpointConfiguration::new(
-                    method.clone(),
-                    &path,
-                    401,
-                    runtime.clone(),
-                    false,
-                )?);
-                endpoints.push(EndpointConfiguration::new(
-                    method.clone(),
-                    &path,
-                    403,
-                    runtime.clone(),
-                    false,
-                )?);
-            }
-
-            for response in responses.entries() {
-                let status_code = match response.0.parse() {
-                    Ok(status_code) => status_code,
-                    Err(_) => return Err(Error::InvalidParseStatusCode(response.0.to_string())),
-                };
-                endpoints.push(EndpointConfiguration::new(
-                    method.clone(),
-                    &path,
-                    status_code,
-                    runtime.clone(),
-                    false,
-                )?)
-            }
-        }
-    }
-
-    Ok(endpoints)
-}
-
-fn get_methods_from_path(path_json: &JsonValue) -> Result<Vec<(Method, &JsonValue)>, Error> {
-    let mut methods = vec![];
-
-    for method_entry in path_json.entries() {
-        let method = match Method::from_str(method_entry.0) {
-            Some(method) => method,
-            None => return Err(Error::InvalidParseMethod(method_entry.0.to_string())),
-        };
-        methods.push((method, method_entry.1));
-    }
-    Ok(methods)
+    load_openapi_document(&json_obj, &mut resolver, SourceMap::scan(json_string).as_ref())
 }
 
 #[cfg(test)]
@@ -104,18 +17,25 @@ mod test {
     use std::{str::FromStr, sync::Arc};
 
     use crate::{
-        models::{Method, OpenapiPath},
-        parser::json_parser::parse_json_doc,
-        utils::test::create_mock_runtime,
+        config::Runtime,
+        models::{EndpointConfiguration, Method, OpenapiPath, ParameterLocation, StatusCode},
+        parser::{document::get_document_endpoints, reference::ReferenceResolver},
+        utils::{test::create_mock_runtime, Error},
     };
 
+    use super::load_json_doc;
+
+    fn parse_json_doc(json_string: &str, runtime: Arc<Runtime>) -> Result<Vec<EndpointConfiguration>, Error> {
+        get_document_endpoints(&load_json_doc(json_string, ReferenceResolver::detached())?, runtime)
+    }
+
     const JSON_STRING: &str = r#"
     {
         "basePath": "/",
         "paths" : {
             "/": {
                 "get": {
-                    "security": [],
+                    "security": [{ "BasicAuth": [] }],
                     "responses": {
                         "200": {
                             "description": "OK",
@@ -236,6 +156,19 @@ mod test {
         );
     }
 
+    #[test]
+    fn marks_security_responses_as_generated() {
+        let endpoints = parse_json_doc(JSON_STRING, Arc::from(create_mock_runtime())).unwrap();
+        assert!(endpoints
+            .iter()
+            .filter(|x| x.status_code == 401 || x.status_code == 403)
+            .all(|x| x.is_generated));
+        assert!(endpoints
+            .iter()
+            .filter(|x| x.status_code == 200)
+            .all(|x| !x.is_generated));
+    }
+
     const JSON_STRING_DIFF_BASEPATH: &str = r#"
     {
         "basePath": "/foo",
@@ -283,4 +216,208 @@ mod test {
                 .any(|x| x.path == OpenapiPath::from_str("/foo/bar").unwrap())
         );
     }
+
+    const JSON_STRING_OPENAPI_3: &str = r##"
+    {
+        "openapi": "3.0.3",
+        "servers": [
+            { "url": "https://example.com/api/v1" },
+            { "url": "http://localhost:8080/api/v1/" }
+        ],
+        "paths" : {
+            "/users": {
+                "get": {
+                    "responses": {
+                        "200": {
+                            "description": "OK",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/User" }
+                                }
+                            }
+                        },
+                        "404": { "$ref": "#/components/responses/NotFound" }
+                    }
+                }
+            }
+        },
+        "components": {
+            "schemas": { "User": { "type": "object" } },
+            "responses": { "NotFound": { "description": "Not Found" } }
+        }
+    }
+    "##;
+
+    #[test]
+    fn parses_openapi_3_document() {
+        let endpoints = parse_json_doc(JSON_STRING_OPENAPI_3, Arc::from(create_mock_runtime())).unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert!(endpoints
+            .iter()
+            .all(|x| x.path == OpenapiPath::from_str("/api/v1/users").unwrap()));
+        assert!(endpoints.iter().any(|x| x.status_code == 404));
+    }
+
+    #[test]
+    fn openapi_3_without_servers_uses_root() {
+        let endpoints = parse_json_doc(
+            r#"{"openapi": "3.0.0", "paths": {"/foo": {"get": {"responses": {"200": {"description": "OK"}}}}}}"#,
+            Arc::from(create_mock_runtime()),
+        )
+        .unwrap();
+        assert_eq!(endpoints[0].path, OpenapiPath::from_str("/foo").unwrap());
+    }
+
+    #[test]
+    fn rejects_unknown_openapi_version() {
+        assert!(parse_json_doc(r#"{"openapi": "4.0.0", "paths": {}}"#, Arc::from(create_mock_runtime())).is_err());
+    }
+
+    const JSON_STRING_SERVER_OVERRIDES: &str = r#"
+    {
+        "openapi": "3.0.3",
+        "servers": [
+            {
+                "url": "https://{env}.example.com/api/{version}",
+                "variables": {
+                    "env": { "default": "prod" },
+                    "version": { "default": "v1", "enum": ["v1", "v2"] }
+                }
+            }
+        ],
+        "paths" : {
+            "/users": {
+                "get": { "responses": { "200": { "description": "OK" } } },
+                "post": {
+                    "servers": [{ "url": "/upload" }],
+                    "responses": { "201": { "description": "Created" } }
+                }
+            },
+            "/health": {
+                "servers": [{ "url": "/" }],
+                "get": { "responses": { "200": { "description": "OK" } } }
+            }
+        }
+    }
+    "#;
+
+    #[test]
+    fn applies_server_variables_and_overrides() {
+        let endpoints = parse_json_doc(JSON_STRING_SERVER_OVERRIDES, Arc::from(create_mock_runtime())).unwrap();
+        assert!(endpoints
+            .iter()
+            .any(|x| x.method == Method::GET && x.path == OpenapiPath::from_str("/api/v1/users").unwrap()));
+        assert!(endpoints
+            .iter()
+            .any(|x| x.method == Method::POST && x.path == OpenapiPath::from_str("/upload/users").unwrap()));
+        assert!(endpoints
+            .iter()
+            .any(|x| x.path == OpenapiPath::from_str("/health").unwrap()));
+        assert_eq!(endpoints.len(), 3);
+    }
+
+    #[test]
+    fn configured_server_variables_replace_defaults() {
+        let mut runtime = create_mock_runtime();
+        runtime.server_variables.insert("version".to_string(), "v2".to_string());
+        assert!(parse_json_doc(JSON_STRING_SERVER_OVERRIDES, Arc::from(runtime))
+            .unwrap()
+            .iter()
+            .any(|x| x.path == OpenapiPath::from_str("/api/v2/users").unwrap()));
+    }
+
+    const JSON_STRING_PATH_ITEM_FIELDS: &str = r#"
+    {
+        "swagger": "2.0",
+        "paths" : {
+            "/users/{id}": {
+                "summary": "A single user",
+                "description": "Operations on a single user",
+                "x-owner": "team-users",
+                "parameters": [
+                    { "name": "id", "in": "path", "type": "string" },
+                    { "name": "X-Tenant", "in": "header", "type": "string" }
+                ],
+                "get": {
+                    "parameters": [{ "name": "X-Tenant", "in": "header", "required": true, "type": "string" }],
+                    "x-rate-limit": 10,
+                    "responses": { "200": { "description": "OK" } }
+                },
+                "delete": {
+                    "responses": { "204": { "description": "No Content" } }
+                }
+            }
+        }
+    }
+    "#;
+
+    #[test]
+    fn ignores_path_item_fields_and_extensions() {
+        let endpoints = parse_json_doc(JSON_STRING_PATH_ITEM_FIELDS, Arc::from(create_mock_runtime())).unwrap();
+        assert_eq!(endpoints.len(), 2);
+    }
+
+    #[test]
+    fn merges_path_level_parameters_into_operations() {
+        let endpoints = parse_json_doc(JSON_STRING_PATH_ITEM_FIELDS, Arc::from(create_mock_runtime())).unwrap();
+        let get_operation = endpoints
+            .iter()
+            .find(|x| x.method == Method::GET)
+            .unwrap()
+            .operation
+            .clone()
+            .unwrap();
+        assert_eq!(get_operation.parameters.len(), 2);
+        assert!(get_operation
+            .parameters
+            .iter()
+            .any(|x| x.name == "X-Tenant" && x.location == ParameterLocation::Header && x.required));
+
+        let delete_operation = endpoints
+            .iter()
+            .find(|x| x.method == Method::DELETE)
+            .unwrap()
+            .operation
+            .clone()
+            .unwrap();
+        assert!(delete_operation
+            .parameters
+            .iter()
+            .any(|x| x.name == "X-Tenant" && !x.required));
+    }
+
+    #[test]
+    fn keeps_extensions() {
+        let endpoints = parse_json_doc(JSON_STRING_PATH_ITEM_FIELDS, Arc::from(create_mock_runtime())).unwrap();
+        let get_operation = endpoints
+            .iter()
+            .find(|x| x.method == Method::GET)
+            .unwrap()
+            .operation
+            .clone()
+            .unwrap();
+        assert_eq!(get_operation.extensions.get("x-owner").unwrap(), "\"team-users\"");
+        assert_eq!(get_operation.extensions.get("x-rate-limit").unwrap(), "10");
+    }
+
+    #[test]
+    fn rejects_unknown_path_item_keys() {
+        assert!(parse_json_doc(
+            r#"{"paths": {"/foo": {"fetch": {"responses": {"200": {"description": "OK"}}}}}}"#,
+            Arc::from(create_mock_runtime())
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parses_default_and_range_status_codes() {
+        let endpoints = parse_json_doc(
+            r#"{"swagger": "2.0", "paths": {"/foo": {"get": {"responses": {"2XX": {}, "default": {}}}}}}"#,
+            Arc::from(create_mock_runtime()),
+        )
+        .unwrap();
+
+        assert!(endpoints.iter().any(|x| x.status_code == StatusCode::Range(2)));
+        assert!(endpoints.iter().any(|x| x.status_code == StatusCode::Default));
+    }
 }
@@ -0,0 +1,685 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use json::JsonValue;
+use url::Url;
+use yaml_rust::YamlLoader;
+
+use crate::{
+    config::{FetchConfig, OpenapiSource},
+    models::{Method, OpenapiDocument, OpenapiVersion, SourceLocation, StatusCode},
+    utils::{read_file_to_string_or_err, Error},
+};
+
+use super::{
+    common::{is_extension, is_path_item_field, parse_openapi_version, yaml_to_json},
+    document::{get_parameters, get_version_str, load_openapi_document},
+    graphql::GraphqlSchema,
+    http::fetch_openapi_spec,
+    location::SourceMap,
+    reference::ReferenceResolver,
+    routes::parse_routes,
+    sources::{find_openapi_files, merge_documents},
+};
+
+const PATHS_KEY: &str = "paths";
+const PARAMETERS_KEY: &str = "parameters";
+const RESPONSES_KEY: &str = "responses";
+const REFERENCE_KEY: &str = "$ref";
+
+/// A problem found while validating a spec, along with the file and position it occurs at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintProblem {
+    pub file: String,
+    pub location: Option<SourceLocation>,
+    pub message: String,
+}
+
+/// Position and message of a syntax error, files can only have one of them.
+type SyntaxProblem = (Option<SourceLocation>, String);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpecFormat {
+    Json,
+    Yaml,
+}
+
+impl LintProblem {
+    fn new(file: &str, location: Option<SourceLocation>, message: String) -> LintProblem {
+        LintProblem {
+            file: file.to_string(),
+            location,
+            message,
+        }
+    }
+}
+
+impl Display for LintProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.location {
+            Some(location) => write!(f, "{}:{}: {}", self.file, location, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+/// Validates every spec file of a source. Unlike loading the spec, which stops at the first
+/// error, all problems that can be found are reported.
+pub fn lint_openapi_source(
+    openapi_source: &OpenapiSource,
+    mount_point: &str,
+    fetch_config: &FetchConfig,
+) -> Vec<LintProblem> {
+    match openapi_source {
+        OpenapiSource::Url(openapi_url) => match lint_openapi_url(openapi_url, fetch_config, mount_point) {
+            Ok(_) => vec![],
+            Err(problems) => problems,
+        },
+        OpenapiSource::Path(openapi_path) => lint_openapi_files(std::slice::from_ref(openapi_path), mount_point),
+        OpenapiSource::Paths(openapi_paths) => lint_openapi_files(openapi_paths, mount_point),
+        OpenapiSource::Graphql(graphql_paths) => lint_source_files(graphql_paths, mount_point, lint_graphql_schema),
+        OpenapiSource::Routes(route_paths) => lint_source_files(route_paths, mount_point, lint_route_inventory),
+    }
+}
+
+fn lint_graphql_schema(source: &str) -> Result<(), SyntaxProblem> {
+    match GraphqlSchema::default().add_document(source) {
+        Ok(_) => Ok(()),
+        Err(error) => Err((Some(error.location), error.message)),
+    }
+}
+
+fn lint_route_inventory(source: &str) -> Result<(), SyntaxProblem> {
+    match parse_routes(source) {
+        Ok(_) => Ok(()),
+        Err(error) => Err((error.location, error.message)),
+    }
+}
+
+/// Lints every file of a GraphQL schema or route inventory on its own, so syntax errors are
+/// reported with the file they occur in.
+fn lint_source_files(
+    source_paths: &[Box<Path>],
+    mount_point: &str,
+    lint_source: fn(&str) -> Result<(), SyntaxProblem>,
+) -> Vec<LintProblem> {
+    let mut problems = vec![];
+    for source_path in source_paths {
+        let file = source_path.display().to_string();
+        let full_path = Path::new(mount_point).join(source_path);
+        let source =
+            match read_file_to_string_or_err(&full_path, Error::ProblemOpeningFile(Box::from(full_path.as_path()))) {
+                Ok(source) => source,
+                Err(error) => {
+                    problems.push(LintProblem::new(&file, None, error.get_error_msg()));
+                    continue;
+                }
+            };
+
+        if let Err((location, message)) = lint_source(&source) {
+            problems.push(LintProblem::new(&file, location, message));
+        }
+    }
+    problems
+}
+
+fn lint_openapi_url(
+    openapi_url: &Url,
+    fetch_config: &FetchConfig,
+    mount_point: &str,
+) -> Result<OpenapiDocument, Vec<LintProblem>> {
+    let spec_str = match fetch_openapi_spec(openapi_url, fetch_config, mount_point) {
+        Ok(spec_str) => spec_str,
+        Err(error) => {
+            return Err(vec![LintProblem::new(
+                openapi_url.as_str(),
+                None,
+                error.get_error_msg(),
+            )])
+        }
+    };
+
+    // there is no file extension to go by, but json specs always start with an object
+    let format = if spec_str.trim_start().starts_with('{') {
+        SpecFormat::Json
+    } else {
+        SpecFormat::Yaml
+    };
+    lint_spec(openapi_url.as_str(), &spec_str, format, ReferenceResolver::detached())
+}
+
+fn lint_openapi_files(openapi_paths: &[Box<Path>], mount_point: &str) -> Vec<LintProblem> {
+    let openapi_files = match find_openapi_files(Path::new(mount_point), openapi_paths) {
+        Ok(openapi_files) => openapi_files,
+        Err(error) => {
+            let file = openapi_paths
+                .iter()
+                .map(|x| x.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            return vec![LintProblem::new(&file, None, error.get_error_msg())];
+        }
+    };
+
+    let mut problems = vec![];
+    let mut documents = vec![];
+    for openapi_file in openapi_files {
+        match lint_openapi_file(&openapi_file, mount_point) {
+            Ok(document) => documents.push((openapi_file, document)),
+            Err(mut file_problems) => problems.append(&mut file_problems),
+        }
+    }
+
+    // conflicts between files can only be detected once all of them load
+    if problems.is_empty() && documents.len() > 1 {
+        if let Err(error) = merge_documents(documents) {
+            let file = match &error {
+                Error::ConflictingOperation(_, _, file) => file.display().to_string(),
+                _ => String::new(),
+            };
+            problems.push(LintProblem::new(&file, None, error.get_error_msg()));
+        }
+    }
+    problems
+}
+
+fn lint_openapi_file(openapi_file: &Path, mount_point: &str) -> Result<OpenapiDocument, Vec<LintProblem>> {
+    let file = openapi_file.display().to_string();
+    let format = match openapi_file.extension().and_then(|x| x.to_str()) {
+        Some("json") => SpecFormat::Json,
+        Some("yaml") | Some("yml") => SpecFormat::Yaml,
+        _ => {
+            return Err(vec![LintProblem::new(
+                &file,
+                None,
+                Error::UnknownOpenApiFormat.get_error_msg(),
+            )])
+        }
+    };
+
+    let openapi_path = Path::new(mount_point).join(openapi_file);
+    let spec_str = match read_file_to_string_or_err(
+        &openapi_path,
+        Error::ProblemOpeningFile(Box::from(openapi_path.as_path())),
+    ) {
+        Ok(spec_str) => spec_str,
+        Err(error) => return Err(vec![LintProblem::new(&file, None, error.get_error_msg())]),
+    };
+
+    lint_spec(
+        &file,
+        &spec_str,
+        format,
+        ReferenceResolver::for_document(Path::new(mount_point), &openapi_path),
+    )
+}
+
+/// Lints the source of a single spec and returns the loaded document if no problems were found.
+fn lint_spec(
+    file: &str,
+    spec_str: &str,
+    format: SpecFormat,
+    resolver: ReferenceResolver,
+) -> Result<OpenapiDocument, Vec<LintProblem>> {
+    let spec = match parse_spec(spec_str, format) {
+        Ok(spec) => spec,
+        Err((location, message)) => return Err(vec![LintProblem::new(file, location, message)]),
+    };
+    let source_map = SourceMap::scan(spec_str);
+
+    let mut linter = Linter {
+        file,
+        source_map: source_map.as_ref(),
+        resolver,
+        problems: vec![],
+    };
+    linter.lint(&spec);
+    if !linter.problems.is_empty() {
+        return Err(linter.problems);
+    }
+
+    // loading the document covers everything not checked above, like malformed servers
+    match load_openapi_document(&spec, &mut linter.resolver, source_map.as_ref()) {
+        Ok(document) => Ok(document),
+        Err(error) => Err(vec![LintProblem::new(file, None, error.get_error_msg())]),
+    }
+}
+
+fn parse_spec(spec_str: &str, format: SpecFormat) -> Result<JsonValue, (Option<SourceLocation>, String)> {
+    match format {
+        SpecFormat::Json => json::parse(spec_str).map_err(|error| match error {
+            json::Error::UnexpectedCharacter { ch, line, column } => (
+                Some(SourceLocation { line, column }),
+                format!(
+                    "The syntax of the openapi file is incorrect, unexpected character '{}'.",
+                    ch
+                ),
+            ),
+            error => (None, format!("The syntax of the openapi file is incorrect, {}.", error)),
+        }),
+        SpecFormat::Yaml => {
+            let specs = YamlLoader::load_from_str(spec_str).map_err(|error| {
+                let location = SourceLocation {
+                    line: error.marker().line(),
+                    column: error.marker().col() + 1,
+                };
+                // the message of a scan error ends with its position, which is reported separately
+                let message = error.to_string();
+                let message = message.split(" at line ").next().unwrap_or_default();
+                (
+                    Some(location),
+                    format!("The syntax of the openapi file is incorrect, {}.", message),
+                )
+            })?;
+            match specs.first() {
+                Some(spec) if spec.as_hash().is_some() => Ok(yaml_to_json(spec)),
+                _ => Err((
+                    None,
+                    "The syntax of the openapi file is incorrect, the spec has to be a mapping.".to_string(),
+                )),
+            }
+        }
+    }
+}
+
+struct Linter<'a> {
+    file: &'a str,
+    source_map: Option<&'a SourceMap>,
+    resolver: ReferenceResolver,
+    problems: Vec<LintProblem>,
+}
+
+impl Linter<'_> {
+    fn lint(&mut self, spec: &JsonValue) {
+        let version = match parse_openapi_version(
+            get_version_str(&spec["swagger"]).as_deref(),
+            get_version_str(&spec["openapi"]).as_deref(),
+        ) {
+            Ok(version) => version,
+            Err(error) => {
+                let version_key = if spec.has_key("openapi") { "openapi" } else { "swagger" };
+                self.report(&[version_key.to_string()], error.get_error_msg());
+                return;
+            }
+        };
+
+        match &spec[PATHS_KEY] {
+            JsonValue::Object(_) => (),
+            // openapi 3 documents are allowed to only describe components
+            JsonValue::Null if version == OpenapiVersion::Openapi3 && !spec["components"].is_null() => return,
+            _ => {
+                self.report(
+                    &[PATHS_KEY.to_string()],
+                    "The spec has to contain an object of paths.".to_string(),
+                );
+                return;
+            }
+        }
+
+        let root_document_path = self.resolver.add_root_document(spec);
+        let mut path_templates: HashMap<String, &str> = HashMap::new();
+
+        for (path_key, path_item) in spec[PATHS_KEY].entries() {
+            let key_path = vec![PATHS_KEY.to_string(), path_key.to_string()];
+
+            if let Err(message) = check_path_template(path_key) {
+                self.report(&key_path, message);
+            }
+            match path_templates.entry(normalize_path_template(path_key)) {
+                Entry::Occupied(entry) => self.report(
+                    &key_path,
+                    format!(
+                        "The path {} only differs from {} in the names of its templates.",
+                        path_key,
+                        entry.get()
+                    ),
+                ),
+                Entry::Vacant(entry) => {
+                    entry.insert(path_key);
+                }
+            }
+
+            if let Some((path_item, document_path)) = self.resolve(path_item, &key_path, &root_document_path) {
+                self.lint_path_item(&path_item, &document_path, &key_path);
+            }
+        }
+    }
+
+    fn lint_path_item(&mut self, path_item: &JsonValue, document_path: &Path, key_path: &[String]) {
+        if !path_item.is_object() {
+            self.report(key_path, "The path has to be an object of operations.".to_string());
+            return;
+        }
+
+        self.lint_parameters(
+            &path_item[PARAMETERS_KEY],
+            document_path,
+            &child(key_path, PARAMETERS_KEY),
+        );
+
+        for (method_key, operation) in path_item.entries() {
+            // path level fields and extensions aren't operations
+            if is_path_item_field(method_key) || is_extension(method_key) {
+                continue;
+            }
+            let key_path = child(key_path, method_key);
+            if Method::from_str(method_key).is_none() {
+                self.report(
+                    &key_path,
+                    Error::InvalidParseMethod(method_key.to_string()).get_error_msg(),
+                );
+                continue;
+            }
+
+            self.lint_parameters(
+                &operation[PARAMETERS_KEY],
+                document_path,
+                &child(&key_path, PARAMETERS_KEY),
+            );
+            self.lint_responses(&operation[RESPONSES_KEY], document_path, &key_path);
+        }
+    }
+
+    fn lint_parameters(&mut self, parameters: &JsonValue, document_path: &Path, key_path: &[String]) {
+        for (index, parameter) in parameters.members().enumerate() {
+            let key_path = child(key_path, &index.to_string());
+            if let Some((parameter, _)) = self.resolve(parameter, &key_path, document_path) {
+                if let Err(error) = get_parameters(&json::array![parameter]) {
+                    self.report(&key_path, error.get_error_msg());
+                }
+            }
+        }
+    }
+
+    fn lint_responses(&mut self, responses: &JsonValue, document_path: &Path, operation_key_path: &[String]) {
+        let key_path = child(operation_key_path, RESPONSES_KEY);
+        if responses.is_null() || (responses.is_object() && responses.is_empty()) {
+            self.report(&key_path, "The operation doesn't define any responses.".to_string());
+            return;
+        }
+        if !responses.is_object() {
+            self.report(
+                &key_path,
+                "The responses of an operation have to be an object of status codes.".to_string(),
+            );
+            return;
+        }
+
+        for (status_key, response) in responses.entries() {
+            let key_path = child(&key_path, status_key);
+            if let Err(error) = StatusCode::from_str(status_key) {
+                self.report(&key_path, error.get_error_msg());
+            }
+            self.resolve(response, &key_path, document_path);
+        }
+    }
+
+    /// Resolves a value that might be a reference and reports it if that isn't possible.
+    fn resolve(
+        &mut self,
+        value: &JsonValue,
+        key_path: &[String],
+        document_path: &Path,
+    ) -> Option<(JsonValue, PathBuf)> {
+        match self.resolver.resolve_value(value, document_path) {
+            Ok(resolved) => Some(resolved),
+            Err(error) => {
+                self.report(&child(key_path, REFERENCE_KEY), error.get_error_msg());
+                None
+            }
+        }
+    }
+
+    /// Reports a problem at the location of a key. Keys that can't be located, since they are
+    /// part of a referenced object, fall back to the closest parent that can.
+    fn report(&mut self, key_path: &[String], message: String) {
+        let key_path: Vec<&str> = key_path.iter().map(|x| x.as_str()).collect();
+        let location = match self.source_map {
+            Some(source_map) => (1..=key_path.len())
+                .rev()
+                .find_map(|length| source_map.get(&key_path[..length])),
+            None => None,
+        };
+        self.problems.push(LintProblem::new(self.file, location, message));
+    }
+}
+
+fn child(key_path: &[String], key: &str) -> Vec<String> {
+    let mut key_path = key_path.to_vec();
+    key_path.push(key.to_string());
+    key_path
+}
+
+/// Checks that every template of a path is closed before the next one starts and isn't empty,
+/// since unbalanced braces would otherwise be matched as fixed text.
+fn check_path_template(path: &str) -> Result<(), String> {
+    let mut template_start = None;
+    for (index, character) in path.char_indices() {
+        match (character, template_start) {
+            ('{', Some(_)) => return Err(format!("The path {} opens a template within another template.", path)),
+            ('{', None) => template_start = Some(index),
+            ('}', None) => return Err(format!("The path {} closes a template that was never opened.", path)),
+            ('}', Some(start)) if start + 1 == index => {
+                return Err(format!("The path {} contains an empty template.", path))
+            }
+            ('}', Some(_)) => template_start = None,
+            _ => (),
+        }
+    }
+
+    match template_start {
+        Some(_) => Err(format!("The path {} contains a template that is never closed.", path)),
+        None => Ok(()),
+    }
+}
+
+/// Removes the names of all templates, paths that only differ in them match the same requests.
+fn normalize_path_template(path: &str) -> String {
+    let mut normalized_path = String::new();
+    let mut is_in_template = false;
+    for character in path.chars() {
+        match character {
+            '{' => is_in_template = true,
+            '}' => is_in_template = false,
+            _ if is_in_template => continue,
+            _ => (),
+        }
+        normalized_path.push(character);
+    }
+    normalized_path
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{
+        config::{FetchConfig, OpenapiSource},
+        models::SourceLocation,
+        parser::reference::ReferenceResolver,
+    };
+
+    use super::{
+        check_path_template, lint_graphql_schema, lint_openapi_source, lint_route_inventory, lint_source_files,
+        lint_spec, normalize_path_template, LintProblem, SpecFormat,
+    };
+
+    const BROKEN_SPEC: &str = r##"openapi: 3.0.3
+paths:
+  /users/{id}:
+    get:
+      responses:
+        "200":
+          description: OK
+        "2XXX":
+          description: Invalid
+  /users/{name}:
+    get:
+      responses: {}
+  /orders/{id:
+    post:
+      parameters:
+        - $ref: '#/components/parameters/Missing'
+      responses:
+        "201":
+          $ref: '#/components/responses/Missing'
+"##;
+
+    fn lint_yaml(spec: &str) -> Vec<LintProblem> {
+        match lint_spec("openapi.yaml", spec, SpecFormat::Yaml, ReferenceResolver::detached()) {
+            Ok(_) => vec![],
+            Err(problems) => problems,
+        }
+    }
+
+    fn get_location(problems: &[LintProblem], message_part: &str) -> Option<SourceLocation> {
+        problems
+            .iter()
+            .find(|x| x.message.contains(message_part))
+            .unwrap()
+            .location
+    }
+
+    #[test]
+    fn reports_all_problems_with_locations() {
+        let problems = lint_yaml(BROKEN_SPEC);
+
+        assert_eq!(problems.len(), 6);
+        assert_eq!(
+            get_location(&problems, "invalid status code"),
+            Some(SourceLocation { line: 8, column: 9 })
+        );
+        assert_eq!(
+            get_location(&problems, "names of its templates"),
+            Some(SourceLocation { line: 10, column: 3 })
+        );
+        assert_eq!(
+            get_location(&problems, "doesn't define any responses"),
+            Some(SourceLocation { line: 12, column: 7 })
+        );
+        assert_eq!(
+            get_location(&problems, "never closed"),
+            Some(SourceLocation { line: 13, column: 3 })
+        );
+        assert_eq!(
+            get_location(&problems, "#/components/parameters/Missing"),
+            Some(SourceLocation { line: 16, column: 11 })
+        );
+        assert_eq!(
+            get_location(&problems, "#/components/responses/Missing"),
+            Some(SourceLocation { line: 19, column: 11 })
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_location() {
+        let problems = match lint_spec(
+            "openapi.json",
+            "{\n  \"openapi\": \"3.0.3\",\n  \"paths\": {]\n}",
+            SpecFormat::Json,
+            ReferenceResolver::detached(),
+        ) {
+            Ok(_) => vec![],
+            Err(problems) => problems,
+        };
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].location, Some(SourceLocation { line: 3, column: 13 }));
+        assert_eq!(
+            problems[0].to_string(),
+            "openapi.json:3:13: The syntax of the openapi file is incorrect, unexpected character ']'."
+        );
+    }
+
+    #[test]
+    fn reports_missing_responses() {
+        let problems = lint_yaml("swagger: \"2.0\"\npaths:\n  /users:\n    get:\n      tags: [users]\n");
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].location, Some(SourceLocation { line: 4, column: 5 }));
+    }
+
+    #[test]
+    fn reports_non_ascii_status_codes() {
+        let problems = lint_yaml("openapi: 3.0.3\npaths:\n  /users:\n    get:\n      responses:\n        é1: {}\n");
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("invalid status code"));
+    }
+
+    #[test]
+    fn accepts_valid_specs() {
+        let openapi_source = OpenapiSource::Paths(vec![
+            Box::from(Path::new("dump/swagger.yaml")),
+            Box::from(Path::new("dump/multi")),
+        ]);
+        assert_eq!(
+            lint_openapi_source(&openapi_source, "./", &FetchConfig::default()),
+            vec![]
+        );
+
+        let openapi_source = OpenapiSource::Path(Box::from(Path::new("dump/split/openapi.yaml")));
+        assert_eq!(
+            lint_openapi_source(&openapi_source, "./", &FetchConfig::default()),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn reports_graphql_syntax_errors() {
+        let openapi_source = OpenapiSource::Graphql(vec![Box::from(Path::new("dump/graphql/schema.graphql"))]);
+        assert_eq!(
+            lint_openapi_source(&openapi_source, "./", &FetchConfig::default()),
+            vec![]
+        );
+
+        let problems = lint_source_files(&[Box::from(Path::new("dump/swagger.yaml"))], "./", lint_graphql_schema);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].file, "dump/swagger.yaml");
+        assert!(problems[0].location.is_some());
+    }
+
+    #[test]
+    fn reports_invalid_route_inventories() {
+        let openapi_source = OpenapiSource::Routes(vec![Box::from(Path::new("dump/routes/routes.txt"))]);
+        assert_eq!(
+            lint_openapi_source(&openapi_source, "./", &FetchConfig::default()),
+            vec![]
+        );
+
+        let problems = lint_source_files(&[Box::from(Path::new("dump/swagger.yaml"))], "./", lint_route_inventory);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].location, Some(SourceLocation { line: 1, column: 1 }));
+    }
+
+    #[test]
+    fn reports_conflicts_between_files() {
+        let openapi_source = OpenapiSource::Paths(vec![
+            Box::from(Path::new("dump/multi")),
+            Box::from(Path::new("dump/multi-conflict")),
+        ]);
+        let problems = lint_openapi_source(&openapi_source, "./", &FetchConfig::default());
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].file, "dump/multi-conflict/health.yaml");
+    }
+
+    #[test]
+    fn checks_path_templates() {
+        assert!(check_path_template("/users/{id}/posts/{post_id}").is_ok());
+        assert!(check_path_template("/users/{id").is_err());
+        assert!(check_path_template("/users/id}").is_err());
+        assert!(check_path_template("/users/{{id}}").is_err());
+        assert!(check_path_template("/users/{}").is_err());
+    }
+
+    #[test]
+    fn normalizes_path_templates() {
+        assert_eq!(normalize_path_template("/a/{x}/b/{y}"), "/a/{}/b/{}");
+        assert_eq!(normalize_path_template("/a/b"), "/a/b");
+    }
+}
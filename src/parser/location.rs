@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use yaml_rust::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::Marker,
+};
+
+use crate::models::SourceLocation;
+
+/// Locations of all mapping keys of a spec, addressed by the keys leading up to them
+/// (`["paths", "/users", "get"]`). Since json is a subset of yaml, both formats are scanned with
+/// the yaml parser.
+pub struct SourceMap {
+    locations: HashMap<Vec<String>, SourceLocation>,
+}
+
+impl SourceMap {
+    /// Scans the source of a spec, returns `None` if it can't be scanned.
+    pub fn scan(source: &str) -> Option<SourceMap> {
+        let mut receiver = SourceMapReceiver {
+            locations: HashMap::new(),
+            containers: vec![],
+            key_path: vec![],
+        };
+        Parser::new(source.chars()).load(&mut receiver, false).ok()?;
+
+        Some(SourceMap {
+            locations: receiver.locations,
+        })
+    }
+
+    pub fn get(&self, key_path: &[&str]) -> Option<SourceLocation> {
+        let key_path: Vec<String> = key_path.iter().map(|x| x.to_string()).collect();
+        self.locations.get(&key_path).copied()
+    }
+}
+
+enum Container {
+    Mapping { key: Option<String> },
+    Sequence { index: usize },
+}
+
+struct SourceMapReceiver {
+    locations: HashMap<Vec<String>, SourceLocation>,
+    containers: Vec<Container>,
+    key_path: Vec<String>,
+}
+
+impl SourceMapReceiver {
+    fn start_container(&mut self, container: Container) {
+        if let Some(parent) = self.containers.last() {
+            let key = match parent {
+                Container::Mapping { key } => key.clone().unwrap_or_default(),
+                Container::Sequence { index } => index.to_string(),
+            };
+            self.key_path.push(key);
+        }
+        self.containers.push(container);
+    }
+
+    fn end_container(&mut self) {
+        self.containers.pop();
+        if !self.containers.is_empty() {
+            self.key_path.pop();
+        }
+        self.end_value();
+    }
+
+    fn end_value(&mut self) {
+        match self.containers.last_mut() {
+            Some(Container::Mapping { key }) => *key = None,
+            Some(Container::Sequence { index }) => *index += 1,
+            None => (),
+        }
+    }
+}
+
+impl MarkedEventReceiver for SourceMapReceiver {
+    fn on_event(&mut self, event: Event, marker: Marker) {
+        match event {
+            Event::Scalar(value, ..) => match self.containers.last_mut() {
+                Some(Container::Mapping { key }) if key.is_none() => {
+                    let mut key_path = self.key_path.clone();
+                    key_path.push(value.clone());
+                    self.locations.insert(
+                        key_path,
+                        SourceLocation {
+                            line: marker.line(),
+                            column: marker.col() + 1,
+                        },
+                    );
+                    *key = Some(value);
+                }
+                _ => self.end_value(),
+            },
+            Event::Alias(_) => self.end_value(),
+            Event::MappingStart(_) => self.start_container(Container::Mapping { key: None }),
+            Event::SequenceStart(_) => self.start_container(Container::Sequence { index: 0 }),
+            Event::MappingEnd | Event::SequenceEnd => self.end_container(),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::SourceLocation;
+
+    use super::SourceMap;
+
+    #[test]
+    fn locates_yaml_keys() {
+        let source_map = SourceMap::scan("paths:\n  /users:\n    get:\n      tags: [a, b]\n    post: {}\n").unwrap();
+
+        assert_eq!(
+            source_map.get(&["paths", "/users", "get"]),
+            Some(SourceLocation { line: 3, column: 5 })
+        );
+        assert_eq!(
+            source_map.get(&["paths", "/users", "post"]),
+            Some(SourceLocation { line: 5, column: 5 })
+        );
+        assert_eq!(source_map.get(&["paths", "/users", "put"]), None);
+    }
+
+    #[test]
+    fn locates_json_keys() {
+        let source_map = SourceMap::scan("{\n  \"paths\": {\n    \"/users\": { \"get\": {} }\n  }\n}").unwrap();
+
+        assert_eq!(
+            source_map.get(&["paths", "/users", "get"]),
+            Some(SourceLocation { line: 3, column: 17 })
+        );
+    }
+
+    #[test]
+    fn locates_keys_within_sequences() {
+        let source_map = SourceMap::scan("servers:\n  - url: /a\n  - url: /b\n").unwrap();
+
+        assert_eq!(
+            source_map.get(&["servers", "1", "url"]),
+            Some(SourceLocation { line: 3, column: 5 })
+        );
+    }
+}
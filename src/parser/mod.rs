@@ -1,104 +1,319 @@
 mod common;
+mod document;
+mod exchange;
+mod graphql;
+mod har;
 mod http;
 mod json_parser;
+mod lint;
+mod location;
 mod nginx_parser;
+mod reference;
+mod routes;
+mod sources;
 mod yaml_parser;
 
-use std::{path::Path, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-pub use nginx_parser::parse_nginx_access_log;
+pub use exchange::parse_exchange;
+pub use lint::LintProblem;
+pub use nginx_parser::{parse_nginx_access_log, parse_nginx_graphql_log, LogDiagnostics, LogRejection};
+use url::Url;
 
 use crate::{
-    config::{CoveAPIConfig, OpenapiSource, Runtime},
-    models::EndpointConfiguration,
+    config::{CoveAPIConfig, FetchConfig, HostRewrite, OpenapiSource, Runtime},
+    models::{EndpointConfiguration, OpenapiDocument},
     utils::{read_file_to_string_or_err, Error},
 };
 
-use self::{http::fetch_openapi_endpoints_for_runtime, json_parser::parse_json_doc, yaml_parser::parse_yaml_doc};
+use self::{
+    common::rewrite_host, document::get_document_endpoints, graphql::load_graphql_schema, har::parse_har_file,
+    http::fetch_openapi_document, json_parser::load_json_doc, lint::lint_openapi_source, reference::ReferenceResolver,
+    routes::load_route_inventory, sources::find_openapi_files, sources::merge_documents, yaml_parser::load_yaml_doc,
+};
 
-const OPENAPI_MOUNT_POINT: &str = "/repo";
 const PRE_MERGE_PATH_EXTENSION: &str = ".coveapi.old";
 
 pub fn get_openapi_endpoint_configs(config: &CoveAPIConfig) -> Result<Vec<EndpointConfiguration>, Error> {
     let mut openapi_endpoints = vec![];
     for runtime in &config.runtimes {
-        let mut endpoints = match get_runtime_openapi_endpoint_configs(runtime.clone()) {
-            Ok(endpoints) => endpoints,
-            Err(err) => return Err(err),
-        };
-        openapi_endpoints.append(&mut endpoints);
+        match &runtime.openapi_source {
+            OpenapiSource::Graphql(graphql_paths) => {
+                let schema = load_graphql_schema(graphql_paths, &config.mount_point, "")?;
+                openapi_endpoints.append(&mut schema.get_endpoints(
+                    &config.graphql_path,
+                    config.graphql_fields,
+                    runtime.clone(),
+                )?);
+                continue;
+            }
+            OpenapiSource::Routes(route_paths) => {
+                openapi_endpoints.append(&mut load_route_inventory(
+                    route_paths,
+                    &config.mount_point,
+                    "",
+                    runtime.clone(),
+                )?);
+                continue;
+            }
+            _ => (),
+        }
+
+        let openapi_snapshot = runtime
+            .openapi_snapshot
+            .as_ref()
+            .map(|x| Path::new(&config.mount_point).join(x));
+        let document = get_openapi_document(
+            &runtime.openapi_source,
+            "",
+            &config.fetch_config,
+            &config.mount_point,
+            openapi_snapshot.as_deref(),
+        )?;
+        if let Some(spec_app_base_url) = document.get_app_base_url() {
+            let host_rewrites = &config.fetch_config.host_rewrites;
+            if !is_same_app_base_url(&spec_app_base_url, &runtime.app_base_url, host_rewrites) {
+                println!(
+                    "WARNING: the application URL {} differs from the host {} of the openapi spec, requests might be proxied to the wrong service.",
+                    runtime.app_base_url, spec_app_base_url
+                );
+            }
+        }
+
+        let mut endpoints = get_document_endpoints(&document, runtime.clone())?;
+        endpoints.retain(|x| config.expects_endpoint(x));
+        openapi_endpoints.append(&mut config.apply_request_media_types(endpoints));
     }
     Ok(openapi_endpoints)
 }
 
-pub fn get_runtime_openapi_endpoint_configs(runtime: Arc<Runtime>) -> Result<Vec<EndpointConfiguration>, Error> {
-    match runtime.openapi_source {
-        OpenapiSource::Url(_) => fetch_openapi_endpoints_for_runtime(runtime),
-        OpenapiSource::Path(_) => parse_openapi_file(runtime, OPENAPI_MOUNT_POINT, ""),
+/// Reads the requests of a HAR file, whose path is relative to the repository.
+pub fn get_har_endpoint_configs(
+    config: &CoveAPIConfig,
+    har_file: &Path,
+) -> Result<(Vec<EndpointConfiguration>, LogDiagnostics), Error> {
+    parse_har_file(
+        &config.runtimes,
+        &Path::new(&config.mount_point).join(har_file),
+        &config.graphql_path,
+        &config.fetch_config.host_rewrites,
+        config.strict_logs,
+    )
+}
+
+/// Reads the endpoints of the configured base source of a runtime, or of the pre merge versions
+/// of its files if there is none.
+pub fn get_pre_merge_openapi_endpoints(
+    config: &CoveAPIConfig,
+    runtime: Arc<Runtime>,
+) -> Result<Vec<EndpointConfiguration>, Error> {
+    let (openapi_source, path_extension) = match &runtime.base_openapi_source {
+        Some(base_openapi_source) => (base_openapi_source, ""),
+        None => (&runtime.openapi_source, PRE_MERGE_PATH_EXTENSION),
+    };
+
+    match openapi_source {
+        OpenapiSource::Graphql(graphql_paths) => {
+            let schema = load_graphql_schema(graphql_paths, &config.mount_point, path_extension)?;
+            return schema.get_endpoints(&config.graphql_path, config.graphql_fields, runtime.clone());
+        }
+        OpenapiSource::Routes(route_paths) => {
+            return load_route_inventory(route_paths, &config.mount_point, path_extension, runtime.clone());
+        }
+        _ => (),
+    }
+
+    let document = get_openapi_document(
+        openapi_source,
+        path_extension,
+        &config.fetch_config,
+        &config.mount_point,
+        None,
+    )?;
+    get_document_endpoints(&document, runtime.clone())
+}
+
+/// Validates the specs of all runtimes, specs shared by several runtimes are only checked once.
+pub fn get_openapi_lint_problems(config: &CoveAPIConfig) -> Vec<LintProblem> {
+    let mut linted_sources = vec![];
+    let mut problems = vec![];
+    for runtime in &config.runtimes {
+        if linted_sources.contains(&&runtime.openapi_source) {
+            continue;
+        }
+        linted_sources.push(&runtime.openapi_source);
+        problems.append(&mut lint_openapi_source(
+            &runtime.openapi_source,
+            &config.mount_point,
+            &config.fetch_config,
+        ));
     }
+    problems
 }
 
-pub fn get_pre_merge_openapi_endpoints(runtime: Arc<Runtime>) -> Result<Vec<EndpointConfiguration>, Error> {
-    match runtime.openapi_source {
-        OpenapiSource::Url(_) => fetch_openapi_endpoints_for_runtime(runtime),
-        OpenapiSource::Path(_) => parse_openapi_file(runtime, OPENAPI_MOUNT_POINT, PRE_MERGE_PATH_EXTENSION),
+/// Derives the url of the application from the spec, for when none was configured.
+pub fn get_openapi_app_base_url(
+    openapi_source: &OpenapiSource,
+    fetch_config: &FetchConfig,
+    mount_point: &str,
+) -> Result<Url, Error> {
+    // graphql schemas and route inventories don't declare any servers
+    if matches!(openapi_source, OpenapiSource::Graphql(_) | OpenapiSource::Routes(_)) {
+        return Err(Error::MissingApplicationURL);
+    }
+    match get_openapi_document(openapi_source, "", fetch_config, mount_point, None)?.get_app_base_url() {
+        Some(app_base_url) => Ok(rewrite_host(&app_base_url, &fetch_config.host_rewrites)),
+        None => Err(Error::MissingApplicationURL),
     }
 }
 
+fn get_openapi_document(
+    openapi_source: &OpenapiSource,
+    path_extension: &str,
+    fetch_config: &FetchConfig,
+    mount_point: &str,
+    openapi_snapshot: Option<&Path>,
+) -> Result<OpenapiDocument, Error> {
+    match openapi_source {
+        OpenapiSource::Url(openapi_url) => {
+            fetch_openapi_document(openapi_url, fetch_config, mount_point, openapi_snapshot)
+        }
+        OpenapiSource::Path(_) | OpenapiSource::Paths(_) => {
+            load_openapi_files(openapi_source, mount_point, path_extension)
+        }
+        OpenapiSource::Graphql(_) | OpenapiSource::Routes(_) => Err(Error::UnknownInternalError(
+            "openapi document read on graphql schema or route inventory".to_string(),
+        )),
+    }
+}
+
+fn is_same_app_base_url(spec_app_base_url: &Url, app_base_url: &Url, host_rewrites: &[HostRewrite]) -> bool {
+    let spec_app_base_url = rewrite_host(spec_app_base_url, host_rewrites);
+    let app_base_url = rewrite_host(app_base_url, host_rewrites);
+
+    spec_app_base_url.scheme() == app_base_url.scheme()
+        && spec_app_base_url.host_str() == app_base_url.host_str()
+        && spec_app_base_url.port_or_known_default() == app_base_url.port_or_known_default()
+}
+
 pub fn parse_openapi_file(
     runtime: Arc<Runtime>,
     mount_point: &str,
     path_extension: &str,
 ) -> Result<Vec<EndpointConfiguration>, Error> {
-    let openapi_path = match &runtime.openapi_source {
-        OpenapiSource::Path(path) => path,
+    let document = load_openapi_files(&runtime.openapi_source, mount_point, path_extension)?;
+    get_document_endpoints(&document, runtime)
+}
+
+/// Loads all spec files of a source and merges them into a single document. When reading the
+/// pre merge specs of several files, files without a pre merge version are skipped, since they
+/// were only added by the merge.
+pub fn load_openapi_files(
+    openapi_source: &OpenapiSource,
+    mount_point: &str,
+    path_extension: &str,
+) -> Result<OpenapiDocument, Error> {
+    let openapi_paths = match openapi_source {
+        OpenapiSource::Path(path) => vec![path.clone()],
+        OpenapiSource::Paths(paths) => paths.clone(),
         OpenapiSource::Url(_) => return Err(Error::UnknownInternalError("open api path read on url".to_string())),
+        OpenapiSource::Graphql(_) | OpenapiSource::Routes(_) => {
+            return Err(Error::UnknownInternalError(
+                "open api path read on graphql schema or route inventory".to_string(),
+            ))
+        }
     };
 
-    let mut buf = openapi_path.clone().into_path_buf();
-    let extension = match buf.extension() {
-        Some(extension) => match extension.to_str() {
-            Some(extension) => extension.to_string(),
-            None => return Err(Error::UnknownOpenApiFormat),
-        },
-        None => return Err(Error::UnknownOpenApiFormat),
-    };
+    let openapi_files = find_openapi_files(Path::new(mount_point), &openapi_paths)?;
+    let is_single_file = openapi_files.len() == 1;
 
-    let full_extension = format!("{}{}", extension, path_extension);
+    let mut documents = vec![];
+    for openapi_file in openapi_files {
+        if !is_single_file
+            && !path_extension.is_empty()
+            && !get_extended_path(mount_point, &openapi_file, path_extension)?.is_file()
+        {
+            continue;
+        }
+        let document = load_openapi_file(&openapi_file, mount_point, path_extension)?;
+        documents.push((openapi_file, document));
+    }
 
-    buf.set_extension(full_extension);
+    if documents.is_empty() {
+        return Err(Error::NoMatchingOpenapiFiles(format!("{:?}", openapi_paths)));
+    }
+    merge_documents(documents)
+}
 
-    let openapi_path = Path::new(mount_point).join(buf);
+pub fn load_openapi_file(
+    openapi_path: &Path,
+    mount_point: &str,
+    path_extension: &str,
+) -> Result<OpenapiDocument, Error> {
+    let extension = get_extension(openapi_path)?;
+    let openapi_path = get_extended_path(mount_point, openapi_path, path_extension)?;
+    let resolver =
+        ReferenceResolver::for_document(Path::new(mount_point), &openapi_path).with_path_extension(path_extension);
 
     if extension == "json" {
-        Ok(parse_json_doc(
+        load_json_doc(
             &read_file_to_string_or_err(
                 &openapi_path,
                 Error::ProblemOpeningFile(Box::from(openapi_path.as_path())),
             )?,
-            runtime,
-        )?)
+            resolver,
+        )
     } else if extension == "yaml" || extension == "yml" {
-        Ok(parse_yaml_doc(
+        load_yaml_doc(
             &read_file_to_string_or_err(
                 &openapi_path,
                 Error::ProblemOpeningFile(Box::from(openapi_path.as_path())),
             )?,
-            runtime,
-        )?)
+            resolver,
+        )
     } else {
         Err(Error::UnknownOpenApiFormat)
     }
 }
 
+fn get_extension(openapi_path: &Path) -> Result<String, Error> {
+    match openapi_path.extension() {
+        Some(extension) => match extension.to_str() {
+            Some(extension) => Ok(extension.to_string()),
+            None => Err(Error::UnknownOpenApiFormat),
+        },
+        None => Err(Error::UnknownOpenApiFormat),
+    }
+}
+
+fn get_extended_path(mount_point: &str, openapi_path: &Path, path_extension: &str) -> Result<PathBuf, Error> {
+    let mut buf = openapi_path.to_path_buf();
+    let full_extension = format!("{}{}", get_extension(openapi_path)?, path_extension);
+    buf.set_extension(full_extension);
+    Ok(Path::new(mount_point).join(buf))
+}
+
 #[cfg(test)]
 mod tests {
-    use std::{path::Path, sync::Arc};
+    use std::{collections::HashSet, path::Path, str::FromStr, sync::Arc};
+
+    use url::Url;
 
     use crate::{
-        config::OpenapiSource,
-        parser::{parse_openapi_file, PRE_MERGE_PATH_EXTENSION},
-        utils::test::create_mock_runtime,
+        config::{HostRewrite, OpenapiSource},
+        evaluator::evaluate,
+        models::{Method, OpenapiPath, StatusCode},
+        parser::{
+            is_same_app_base_url, load_graphql_schema, load_openapi_file, load_route_inventory, parse_openapi_file,
+            PRE_MERGE_PATH_EXTENSION,
+        },
+        utils::{
+            test::{create_mock_config, create_mock_runtime},
+            Error,
+        },
     };
 
     #[test]
@@ -117,6 +332,48 @@ mod tests {
         assert_eq!(parse_openapi_file(Arc::from(runtime), "./", "").unwrap().len(), 6);
     }
 
+    #[test]
+    fn parses_graphql_schema() {
+        let mut config = create_mock_config();
+        let mut runtime = create_mock_runtime();
+        runtime.openapi_source = OpenapiSource::Graphql(vec![Box::from(Path::new("dump/graphql/schema.graphql"))]);
+        config.runtimes = vec![Arc::new(runtime)];
+
+        let graphql_paths = vec![Box::from(Path::new("dump/graphql/schema.graphql"))];
+        let schema = load_graphql_schema(&graphql_paths, "./", "").unwrap();
+        let endpoints = schema
+            .get_endpoints(&config.graphql_path, false, config.runtimes[0].clone())
+            .unwrap();
+        assert_eq!(endpoints.len(), 4);
+        assert!(endpoints
+            .iter()
+            .all(|x| x.path == OpenapiPath::from_str("/graphql").unwrap()));
+
+        let schema = load_graphql_schema(&graphql_paths, "./", PRE_MERGE_PATH_EXTENSION).unwrap();
+        assert_eq!(
+            schema
+                .get_endpoints(&config.graphql_path, true, config.runtimes[0].clone())
+                .unwrap()
+                .len(),
+            9
+        );
+    }
+
+    #[test]
+    fn parses_route_inventories() {
+        let route_paths = vec![
+            Box::from(Path::new("dump/routes/routes.txt")),
+            Box::from(Path::new("dump/routes/rails.txt")),
+        ];
+        let endpoints = load_route_inventory(&route_paths, "./", "", Arc::new(create_mock_runtime())).unwrap();
+
+        // routes listed by both files are only evaluated once
+        assert_eq!(endpoints.len(), 7);
+        assert!(endpoints.iter().any(|x| x.method == Method::GET
+            && x.path == OpenapiPath::from_str("/cities").unwrap()
+            && x.status_code == StatusCode::Range(2)));
+    }
+
     #[test]
     fn throws_error_when_providing_absolute_path() {
         let path = Path::new("/test");
@@ -147,4 +404,164 @@ mod tests {
             6
         );
     }
+
+    #[test]
+    fn resolves_references_to_other_files() {
+        let path = Path::new("./dump/split/openapi.yaml");
+        let mut runtime = create_mock_runtime();
+        runtime.openapi_source = OpenapiSource::Path(Box::from(path));
+        let endpoints = parse_openapi_file(Arc::from(runtime), "./", "").unwrap();
+
+        assert_eq!(endpoints.len(), 5);
+        assert!(endpoints
+            .iter()
+            .any(|x| x.method == Method::POST && x.path == OpenapiPath::from_str("/api/users").unwrap()));
+        assert!(endpoints
+            .iter()
+            .any(|x| x.status_code == 404 && x.path == OpenapiPath::from_str("/api/users/{id}").unwrap()));
+    }
+
+    #[test]
+    fn resolves_references_to_old_files() {
+        let path = Path::new("./dump/split/openapi.yaml");
+        let mut runtime = create_mock_runtime();
+        runtime.openapi_source = OpenapiSource::Path(Box::from(path));
+        let endpoints = parse_openapi_file(Arc::from(runtime), "./", PRE_MERGE_PATH_EXTENSION).unwrap();
+
+        // the post operation was only added to the referenced file
+        assert_eq!(endpoints.len(), 4);
+        assert!(!endpoints.iter().any(|x| x.method == Method::POST));
+    }
+
+    #[test]
+    fn ignores_endpoints_of_moved_operations_in_diff() {
+        let path = Path::new("./dump/moved/openapi.yaml");
+        let mut runtime = create_mock_runtime();
+        runtime.openapi_source = OpenapiSource::Path(Box::from(path));
+        let runtime = Arc::from(runtime);
+        let endpoints = parse_openapi_file(runtime.clone(), "./", "").unwrap();
+        let pre_merge_endpoints = parse_openapi_file(runtime, "./", PRE_MERGE_PATH_EXTENSION).unwrap();
+
+        // the operation only moved to other lines of the spec
+        let evaluation = evaluate(&endpoints, &Some(pre_merge_endpoints), &vec![], &HashSet::new());
+        assert!(evaluation.relevant_endpoints.is_empty());
+    }
+
+    #[test]
+    fn reports_cyclic_references() {
+        let path = Path::new("./dump/split/cyclic.json");
+        let mut runtime = create_mock_runtime();
+        runtime.openapi_source = OpenapiSource::Path(Box::from(path));
+        assert!(matches!(
+            parse_openapi_file(Arc::from(runtime), "./", ""),
+            Err(Error::CyclicReference(_))
+        ));
+    }
+
+    #[test]
+    fn merges_operations_of_a_directory() {
+        let path = Path::new("./dump/multi");
+        let mut runtime = create_mock_runtime();
+        runtime.openapi_source = OpenapiSource::Path(Box::from(path));
+        let endpoints = parse_openapi_file(Arc::from(runtime), "./", "").unwrap();
+
+        assert_eq!(endpoints.len(), 5);
+        assert_eq!(
+            endpoints
+                .iter()
+                .filter(|x| x.path == OpenapiPath::from_str("/api/health").unwrap())
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn merges_operations_of_a_list_and_glob() {
+        let paths = vec![
+            Box::from(Path::new("./dump/multi/*.yaml")),
+            Box::from(Path::new("./dump/multi/orders.json")),
+        ];
+        let mut runtime = create_mock_runtime();
+        runtime.openapi_source = OpenapiSource::Paths(paths);
+
+        assert_eq!(parse_openapi_file(Arc::from(runtime), "./", "").unwrap().len(), 5);
+    }
+
+    #[test]
+    fn skips_files_without_pre_merge_version() {
+        let path = Path::new("./dump/multi");
+        let mut runtime = create_mock_runtime();
+        runtime.openapi_source = OpenapiSource::Path(Box::from(path));
+
+        assert_eq!(
+            parse_openapi_file(Arc::from(runtime), "./", PRE_MERGE_PATH_EXTENSION)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn reports_conflicting_operations() {
+        let paths = vec![
+            Box::from(Path::new("./dump/multi")),
+            Box::from(Path::new("./dump/multi-conflict/health.yaml")),
+        ];
+        let mut runtime = create_mock_runtime();
+        runtime.openapi_source = OpenapiSource::Paths(paths);
+
+        assert!(matches!(
+            parse_openapi_file(Arc::from(runtime), "./", ""),
+            Err(Error::ConflictingOperation(..))
+        ));
+    }
+
+    #[test]
+    fn reports_globs_without_matches() {
+        let path = Path::new("./dump/multi/*.toml");
+        let mut runtime = create_mock_runtime();
+        runtime.openapi_source = OpenapiSource::Path(Box::from(path));
+
+        assert!(matches!(
+            parse_openapi_file(Arc::from(runtime), "./", ""),
+            Err(Error::NoMatchingOpenapiFiles(_))
+        ));
+    }
+
+    #[test]
+    fn derives_app_base_url_from_host() {
+        let document = load_openapi_file(Path::new("./dump/swagger.yaml"), "./", "").unwrap();
+
+        assert_eq!(document.get_app_base_url().unwrap().as_str(), "http://localhost:8080/");
+    }
+
+    #[test]
+    fn compares_app_base_urls() {
+        let spec_app_base_url = Url::from_str("http://localhost:8080").unwrap();
+        let host_rewrites = [HostRewrite {
+            host: "localhost".to_string(),
+            replacement: "172.17.0.1".to_string(),
+        }];
+
+        assert!(is_same_app_base_url(
+            &spec_app_base_url,
+            &Url::from_str("http://172.17.0.1:8080/").unwrap(),
+            &host_rewrites
+        ));
+        assert!(!is_same_app_base_url(
+            &spec_app_base_url,
+            &Url::from_str("http://localhost:8081").unwrap(),
+            &host_rewrites
+        ));
+        assert!(!is_same_app_base_url(
+            &spec_app_base_url,
+            &Url::from_str("https://localhost:8080").unwrap(),
+            &host_rewrites
+        ));
+        assert!(!is_same_app_base_url(
+            &spec_app_base_url,
+            &Url::from_str("http://172.17.0.1:8080/").unwrap(),
+            &[]
+        ));
+    }
 }
@@ -1,24 +1,263 @@
 use std::{
+    collections::BTreeMap,
+    fmt::Display,
     fs::File,
     io::{BufRead, BufReader},
     path::Path,
     sync::Arc,
 };
 
+use super::{
+    common::{normalize_request_url, parse_query},
+    graphql::get_request_fields,
+};
 use crate::{
     config::Runtime,
-    models::{EndpointConfiguration, Method},
+    models::{
+        EndpointConfiguration, LogEscape, LogFormat, LogSegment, MediaType, Method, ParameterLocation,
+        RequestParameter, StatusCode,
+    },
     utils::{print_debug_message, Error},
 };
-use lazy_static::lazy_static;
 use regex::Regex;
 
-pub fn parse_nginx_access_log(runtimes: &Vec<Arc<Runtime>>) -> Result<Vec<EndpointConfiguration>, Error> {
-    parse_access_log(runtimes, Path::new("/var/log/nginx/access.log"))
+/// Value nginx logs for empty variables, like headers that weren't sent.
+const MISSING_VALUE_STR: &str = "-";
+/// Prefix of the variables nginx provides the request headers as.
+const HEADER_VARIABLE_PREFIX: &str = "http_";
+const REQUEST_CONTENT_TYPE_VARIABLES: [&str; 2] = ["content_type", "http_content_type"];
+const RESPONSE_CONTENT_TYPE_VARIABLE: &str = "sent_http_content_type";
+
+pub fn parse_nginx_access_log(
+    runtimes: &Vec<Arc<Runtime>>,
+    log_format: &LogFormat,
+    strict: bool,
+) -> Result<(Vec<EndpointConfiguration>, LogDiagnostics), Error> {
+    parse_access_log(runtimes, log_format, Path::new("/var/log/nginx/access.log"), strict)
+}
+
+/// Parses the log of requests to GraphQL schemas into one endpoint per field they selected.
+pub fn parse_nginx_graphql_log(
+    runtimes: &Vec<Arc<Runtime>>,
+    log_format: &LogFormat,
+    strict: bool,
+) -> Result<(Vec<EndpointConfiguration>, LogDiagnostics), Error> {
+    parse_graphql_log(runtimes, log_format, Path::new("/var/log/nginx/graphql.log"), strict)
+}
+
+/// Why a line of a log wasn't turned into endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogRejection {
+    /// The line wasn't written with the configured log format.
+    InvalidFormat,
+    /// The request line is missing or doesn't target a path, like the ones of `CONNECT` requests.
+    InvalidRequest,
+    /// Methods unknown to openapi can't be part of the coverage, so these lines are skipped even
+    /// when parsing strictly.
+    UnknownMethod,
+    InvalidStatus,
+    /// None of the runtimes listens on the port of the request.
+    UnknownPort,
+    /// The url of the request neither belongs to an application nor to the port of a runtime.
+    UnknownUrl,
+    InvalidPath,
+}
+
+impl LogRejection {
+    pub(super) fn is_tolerated(&self) -> bool {
+        *self == LogRejection::UnknownMethod
+    }
+}
+
+impl Display for LogRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            LogRejection::InvalidFormat => "not matching the log format",
+            LogRejection::InvalidRequest => "without a request to a path",
+            LogRejection::UnknownMethod => "with an unknown method",
+            LogRejection::InvalidStatus => "with an invalid status code",
+            LogRejection::UnknownPort => "with a port no service is configured for",
+            LogRejection::UnknownUrl => "with a url no service is configured for",
+            LogRejection::InvalidPath => "with an invalid path",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// Number of lines of a log, or entries of a HAR file, that were parsed or skipped.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LogDiagnostics {
+    pub parsed_lines: usize,
+    pub rejected_lines: BTreeMap<LogRejection, usize>,
+}
+
+impl LogDiagnostics {
+    pub fn rejected_line_count(&self) -> usize {
+        self.rejected_lines.values().sum()
+    }
+
+    /// Prints the number of skipped lines by reason, if any lines were skipped. The source
+    /// describes what was parsed, like `lines of the nginx access log`.
+    pub fn print(&self, source: &str) {
+        let rejected_line_count = self.rejected_line_count();
+        if rejected_line_count == 0 {
+            return;
+        }
+        println!(
+            "Skipped {} of {} {}:",
+            rejected_line_count,
+            rejected_line_count + self.parsed_lines,
+            source
+        );
+        for (rejection, count) in &self.rejected_lines {
+            println!(" - {} {}", count, rejection);
+        }
+    }
+}
+
+/// Matches the lines written with a log format and reads the values of its variables.
+struct LogLineParser {
+    line_regex: Regex,
+    /// Variable of every capture group of the regex.
+    variables: Vec<String>,
+    escape: LogEscape,
+}
+
+impl LogLineParser {
+    fn new(log_format: &LogFormat) -> Result<LogLineParser, Error> {
+        let segments = log_format.segments();
+        let mut pattern = String::from("^");
+        let mut variables = vec![];
+        for (index, segment) in segments.iter().enumerate() {
+            match segment {
+                LogSegment::Literal(literal) => pattern.push_str(&regex::escape(literal)),
+                LogSegment::Variable(variable) => {
+                    // quoted values can't contain quotes, unless escaping is turned off
+                    let is_quoted =
+                        matches!(segments.get(index + 1), Some(LogSegment::Literal(x)) if x.starts_with('"'));
+                    pattern.push_str(match (segments.get(index + 1), log_format.escape) {
+                        (None, _) => "(.*)",
+                        (_, LogEscape::Default) if is_quoted => "([^\"]*)",
+                        (_, LogEscape::Json) if is_quoted => "((?:[^\"\\\\]|\\\\.)*)",
+                        _ => "(.*?)",
+                    });
+                    variables.push(variable.clone());
+                }
+            }
+        }
+        pattern.push('$');
+
+        match Regex::new(&pattern) {
+            Ok(line_regex) => Ok(LogLineParser {
+                line_regex,
+                variables,
+                escape: log_format.escape,
+            }),
+            Err(why) => Err(Error::UnknownInternalError(format!("log format regex {}", why))),
+        }
+    }
+
+    /// Reads the unescaped values of the variables in the order of the format, variables without a
+    /// value are left out.
+    fn parse<'a>(&'a self, line: &str) -> Result<LogLine<'a>, LogRejection> {
+        let captures = match self.line_regex.captures(line) {
+            Some(captures) => captures,
+            None => return Err(LogRejection::InvalidFormat),
+        };
+
+        let mut values: Vec<(&str, String)> = vec![];
+        for (index, variable) in self.variables.iter().enumerate() {
+            let value = match captures.get(index + 1) {
+                Some(value) => value.as_str(),
+                None => continue,
+            };
+            if value.is_empty() || value == MISSING_VALUE_STR || values.iter().any(|(x, _)| x == variable) {
+                continue;
+            }
+            let value = match self.escape {
+                LogEscape::Default => unescape_nginx_value(value),
+                LogEscape::Json => unescape_json_value(value),
+                LogEscape::None => value.to_string(),
+            };
+            values.push((variable, value));
+        }
+        Ok(LogLine { values })
+    }
+}
+
+struct LogLine<'a> {
+    values: Vec<(&'a str, String)>,
 }
 
-fn parse_access_log(runtimes: &Vec<Arc<Runtime>>, path: &Path) -> Result<Vec<EndpointConfiguration>, Error> {
+impl LogLine<'_> {
+    fn get(&self, variable: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(name, _)| *name == variable)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Method and url of the request, either from the request line or the separate variables.
+    fn get_request(&self) -> Result<(Method, &str), LogRejection> {
+        let (method_str, url) = match self.get("request") {
+            Some(request) => {
+                let mut request_parts = request.split(' ');
+                match (request_parts.next(), request_parts.next()) {
+                    (Some(method_str), Some(url)) => (method_str, url),
+                    _ => return Err(LogRejection::InvalidRequest),
+                }
+            }
+            None => match (self.get("request_method"), self.get("request_uri")) {
+                (Some(method_str), Some(url)) => (method_str, url),
+                _ => return Err(LogRejection::InvalidRequest),
+            },
+        };
+        if !url.starts_with('/') {
+            return Err(LogRejection::InvalidRequest);
+        }
+
+        match Method::from_str(method_str) {
+            Some(method) => Ok((method, url)),
+            None => Err(LogRejection::UnknownMethod),
+        }
+    }
+
+    fn get_status(&self) -> Result<StatusCode, LogRejection> {
+        match self.get("status").map(|x| x.parse()) {
+            Some(Ok(status)) => Ok(StatusCode::Exact(status)),
+            _ => Err(LogRejection::InvalidStatus),
+        }
+    }
+
+    fn get_runtime(&self, runtimes: &Vec<Arc<Runtime>>) -> Result<Arc<Runtime>, LogRejection> {
+        match self.get("server_port").map(|x| x.parse()) {
+            Some(Ok(port)) => find_runtime_by_port(runtimes, port),
+            _ => Err(LogRejection::UnknownPort),
+        }
+    }
+
+    /// Headers logged as `$http_<name>` variables.
+    fn get_headers(&self) -> Vec<RequestParameter> {
+        self.values
+            .iter()
+            .filter_map(|(variable, value)| {
+                variable
+                    .strip_prefix(HEADER_VARIABLE_PREFIX)
+                    .map(|name| RequestParameter::new(ParameterLocation::Header, &name.replace('_', "-"), value))
+            })
+            .collect()
+    }
+}
+
+/// Parses every line of a log, lines that can't be parsed are counted and skipped unless the log
+/// is parsed strictly.
+fn parse_log(
+    path: &Path,
+    strict: bool,
+    parse_line: impl Fn(&str) -> Result<Vec<EndpointConfiguration>, LogRejection>,
+) -> Result<(Vec<EndpointConfiguration>, LogDiagnostics), Error> {
     let mut endpoints = Vec::new();
+    let mut diagnostics = LogDiagnostics::default();
     let reader = match File::open(path) {
         Ok(file) => BufReader::new(file),
         Err(why) => {
@@ -27,7 +266,7 @@ fn parse_access_log(runtimes: &Vec<Arc<Runtime>>, path: &Path) -> Result<Vec<End
         }
     };
 
-    for line in reader.lines() {
+    for (index, line) in reader.lines().enumerate() {
         let line_str = match line {
             Ok(line_str) => line_str,
             Err(why) => {
@@ -35,125 +274,204 @@ fn parse_access_log(runtimes: &Vec<Arc<Runtime>>, path: &Path) -> Result<Vec<End
                 return Err(Error::ProblemOpeningFile(Box::from(path)));
             }
         };
+        if line_str.trim().is_empty() {
+            continue;
+        }
 
-        endpoints.push(parse_nginx_line(runtimes, &line_str)?);
+        match parse_line(&line_str) {
+            Ok(mut line_endpoints) => {
+                diagnostics.parsed_lines += 1;
+                endpoints.append(&mut line_endpoints);
+            }
+            Err(rejection) => {
+                if strict && !rejection.is_tolerated() {
+                    return Err(Error::InvalidLogLine(Box::from(path), index + 1, rejection.to_string()));
+                }
+                print_debug_message(format!(
+                    "Skipping line {} of {:?} {}: {}",
+                    index + 1,
+                    path,
+                    rejection,
+                    line_str
+                ));
+                *diagnostics.rejected_lines.entry(rejection).or_insert(0) += 1;
+            }
+        }
     }
 
-    Ok(endpoints)
+    Ok((endpoints, diagnostics))
 }
 
-fn parse_nginx_line(runtimes: &Vec<Arc<Runtime>>, line: &str) -> Result<EndpointConfiguration, Error> {
-    lazy_static! {
-        static ref NGINX_LINE_REGEX: Regex =
-            Regex::new("^(\\[.+\\]) \"(\\w{3, 4}) (/\\S*) HTTP/\\d\\.\\d\" (\\d{3}) (\\d{1, 5})").unwrap();
-    }
+fn parse_graphql_log(
+    runtimes: &Vec<Arc<Runtime>>,
+    log_format: &LogFormat,
+    path: &Path,
+    strict: bool,
+) -> Result<(Vec<EndpointConfiguration>, LogDiagnostics), Error> {
+    let line_parser = LogLineParser::new(log_format)?;
+    parse_log(path, strict, |line| parse_graphql_line(runtimes, &line_parser, line))
+}
 
-    let captures = match NGINX_LINE_REGEX.captures(line) {
-        Some(captures) => captures,
-        None => return Err(Error::InvalidParseSyntax),
+fn parse_graphql_line(
+    runtimes: &Vec<Arc<Runtime>>,
+    line_parser: &LogLineParser,
+    line: &str,
+) -> Result<Vec<EndpointConfiguration>, LogRejection> {
+    let log_line = line_parser.parse(line)?;
+    let (method, url) = log_line.get_request()?;
+    let status = log_line.get_status()?;
+    let runtime = log_line.get_runtime(runtimes)?;
+    let (path, _) = normalize_request_url(url, &runtime.path_normalization);
+
+    // requests without a body don't select any fields
+    let request_body = match log_line.get("request_body") {
+        Some(request_body) => request_body,
+        None => return Ok(vec![]),
     };
+    let mut endpoints = vec![];
+    for field in get_request_fields(request_body) {
+        endpoints.push(
+            EndpointConfiguration::new(method.clone(), &path, status, runtime.clone(), false)
+                .map_err(|_| LogRejection::InvalidPath)?
+                .with_graphql_field(Some(field)),
+        );
+    }
+    Ok(endpoints)
+}
 
-    let status = {
-        let status_string = match captures.get(4) {
-            Some(status_string) => status_string,
-            None => return Err(Error::InvalidParseSyntax),
-        };
+fn parse_access_log(
+    runtimes: &Vec<Arc<Runtime>>,
+    log_format: &LogFormat,
+    path: &Path,
+    strict: bool,
+) -> Result<(Vec<EndpointConfiguration>, LogDiagnostics), Error> {
+    let line_parser = LogLineParser::new(log_format)?;
+    parse_log(path, strict, |line| {
+        Ok(vec![parse_nginx_line(runtimes, &line_parser, line)?])
+    })
+}
 
-        match status_string.as_str().parse() {
-            Ok(status) => status,
-            Err(..) => return Err(Error::InvalidParseStatusCode(status_string.as_str().to_string())),
-        }
-    };
+fn parse_nginx_line(
+    runtimes: &Vec<Arc<Runtime>>,
+    line_parser: &LogLineParser,
+    line: &str,
+) -> Result<EndpointConfiguration, LogRejection> {
+    let log_line = line_parser.parse(line)?;
+    let (method, url) = log_line.get_request()?;
+    let status = log_line.get_status()?;
+    let runtime = log_line.get_runtime(runtimes)?;
+    let (path, query) = normalize_request_url(url, &runtime.path_normalization);
 
-    let method = {
-        let method_string = match captures.get(2) {
-            Some(method_string) => method_string.as_str(),
-            None => return Err(Error::UnknownInternalError("no method nginx logs".to_string())),
-        };
+    let request_media_type = REQUEST_CONTENT_TYPE_VARIABLES
+        .iter()
+        .find_map(|x| log_line.get(x))
+        .map(MediaType::new);
+    let response_media_type = log_line.get(RESPONSE_CONTENT_TYPE_VARIABLE).map(MediaType::new);
 
-        match Method::from_str(method_string) {
-            Some(method) => method,
-            None => {
-                return Err(Error::UnknownInternalError(format!(
-                    "invalid method nginx {}",
-                    method_string
-                )))
-            }
-        }
-    };
+    let mut parameters = parse_query(query);
+    parameters.append(&mut log_line.get_headers());
 
-    let path = match captures.get(3) {
-        Some(path) => String::from(path.as_str()),
-        None => return Err(Error::UnknownInternalError("invalid path nginx logs".to_string())),
-    };
+    Ok(EndpointConfiguration::new(method, &path, status, runtime, false)
+        .map_err(|_| LogRejection::InvalidPath)?
+        .with_parameters(parameters)
+        .with_request_media_type(request_media_type)
+        .with_response_media_type(response_media_type))
+}
 
-    let port = match captures.get(5) {
-        Some(port_string) => match port_string.as_str().parse() {
-            Ok(port) => port,
-            Err(_) => return Err(Error::UnknownInternalError("invalid port nginx logs".to_string())),
-        },
-        None => return Err(Error::UnknownInternalError("no port number nginx logs".to_string())),
-    };
+/// Reverts the escaping nginx applies to logged variables, which replaces quotes, backslashes
+/// and non printable characters by `\xHH`.
+fn unescape_nginx_value(value: &str) -> String {
+    let value = value.as_bytes();
+    let mut bytes = vec![];
+    let mut index = 0;
+    while index < value.len() {
+        if value[index..].starts_with(b"\\x") && value.len() >= index + 4 {
+            let escaped_byte = std::str::from_utf8(&value[index + 2..index + 4])
+                .ok()
+                .and_then(|x| u8::from_str_radix(x, 16).ok());
+            if let Some(byte) = escaped_byte {
+                bytes.push(byte);
+                index += 4;
+                continue;
+            }
+        }
+        bytes.push(value[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
 
-    EndpointConfiguration::new(method, &path, status, find_runtime_by_port(runtimes, port)?, false)
+/// Reverts the escaping of values logged with `escape=json`, which are valid JSON strings.
+fn unescape_json_value(value: &str) -> String {
+    match json::parse(&format!("\"{}\"", value)) {
+        Ok(json::JsonValue::String(value)) => value,
+        Ok(json::JsonValue::Short(value)) => value.to_string(),
+        _ => value.to_string(),
+    }
 }
 
-fn find_runtime_by_port(runtimes: &Vec<Arc<Runtime>>, port: u16) -> Result<Arc<Runtime>, Error> {
+fn find_runtime_by_port(runtimes: &Vec<Arc<Runtime>>, port: u16) -> Result<Arc<Runtime>, LogRejection> {
     for runtime in runtimes {
         if runtime.port == port {
             return Ok(runtime.clone());
         }
     }
-    Err(Error::UnknownInternalError("unknown port in nginx logs".to_string()))
+    Err(LogRejection::UnknownPort)
 }
 
 #[cfg(test)]
 mod test {
-    use std::{path::Path, str::FromStr, sync::Arc};
-
-    use reqwest::Url;
+    use std::{collections::BTreeMap, path::Path, str::FromStr, sync::Arc};
 
     use crate::{
         config::{OpenapiSource, Runtime},
-        models::{Method, OpenapiPath},
-        parser::nginx_parser::{parse_access_log, parse_nginx_line},
+        models::{
+            EndpointConfiguration, LogFormat, MediaType, Method, OpenapiPath, ParameterLocation, RequestParameter,
+        },
+        parser::nginx_parser::{parse_access_log, parse_graphql_line, parse_nginx_line},
+        utils::{test::create_mock_runtime, Error},
     };
 
-    use super::find_runtime_by_port;
+    use super::{find_runtime_by_port, LogLineParser, LogRejection};
+
+    const BASIC_LOG_FORMAT: &str = "'[$time_local] \"$request\" $status $server_port'";
 
     fn generate_runtimes() -> Vec<Arc<Runtime>> {
         vec![
             Arc::from(Runtime {
                 port: 13750,
                 openapi_source: OpenapiSource::Path(Box::from(Path::new("./dump"))),
-                app_base_url: Url::from_str("http://example.con").unwrap(),
+                ..create_mock_runtime()
             }),
             Arc::from(Runtime {
                 port: 8080,
                 openapi_source: OpenapiSource::Path(Box::from(Path::new("./dump"))),
-                app_base_url: Url::from_str("http://example.con").unwrap(),
+                ..create_mock_runtime()
             }),
         ]
     }
 
+    fn parse_line(log_format: &str, line: &str) -> Result<EndpointConfiguration, LogRejection> {
+        let line_parser = LogLineParser::new(&LogFormat::from_str(log_format).unwrap()).unwrap();
+        parse_nginx_line(&generate_runtimes(), &line_parser, line)
+    }
+
+    fn parse_basic_line(line: &str) -> Result<EndpointConfiguration, LogRejection> {
+        parse_line(BASIC_LOG_FORMAT, line)
+    }
+
     #[test]
     fn parses_correct_status() {
         assert_eq!(
-            parse_nginx_line(
-                &generate_runtimes(),
-                "[11/Jul/2023:08:50:03 +0000] \"GET /weather HTTP/1.1\" 200 8080"
-            )
-            .unwrap()
-            .status_code,
+            parse_basic_line("[11/Jul/2023:08:50:03 +0000] \"GET /weather HTTP/1.1\" 200 8080")
+                .unwrap()
+                .status_code,
             200
         );
         assert_eq!(
-            parse_nginx_line(
-                &generate_runtimes(),
-                "[11/Jul/2023:08:52:45 +0000] \"GET /user HTTP/1.1\" 404 8080"
-            )
-            .unwrap()
-            .status_code,
+            parse_basic_line("[11/Jul/2023:08:52:45 +0000] \"GET /user HTTP/1.1\" 404 8080")
+                .unwrap()
+                .status_code,
             404
         );
     }
@@ -161,21 +479,15 @@ mod test {
     #[test]
     fn parses_correct_method() {
         assert_eq!(
-            parse_nginx_line(
-                &generate_runtimes(),
-                "[11/Jul/2023:08:50:03 +0000] \"GET /weather HTTP/1.1\" 200 8080"
-            )
-            .unwrap()
-            .method,
+            parse_basic_line("[11/Jul/2023:08:50:03 +0000] \"GET /weather HTTP/1.1\" 200 8080")
+                .unwrap()
+                .method,
             Method::GET
         );
         assert_eq!(
-            parse_nginx_line(
-                &generate_runtimes(),
-                "[11/Jul/2023:08:50:03 +0000] \"POST /weather HTTP/1.1\" 200 8080"
-            )
-            .unwrap()
-            .method,
+            parse_basic_line("[11/Jul/2023:08:50:03 +0000] \"POST /weather HTTP/1.1\" 200 8080")
+                .unwrap()
+                .method,
             Method::POST
         );
     }
@@ -183,54 +495,112 @@ mod test {
     #[test]
     fn parses_correct_path() {
         assert_eq!(
-            parse_nginx_line(
-                &generate_runtimes(),
-                "[11/Jul/2023:08:50:03 +0000] \"GET /weather HTTP/1.1\" 200 8080"
-            )
-            .unwrap()
-            .path,
+            parse_basic_line("[11/Jul/2023:08:50:03 +0000] \"GET /weather HTTP/1.1\" 200 8080")
+                .unwrap()
+                .path,
             OpenapiPath::from_str("/weather").unwrap(),
         );
         assert_eq!(
-            parse_nginx_line(
-                &generate_runtimes(),
-                "[11/Jul/2023:08:52:45 +0000] \"GET /user HTTP/1.1\" 404 8080"
-            )
-            .unwrap()
-            .path,
+            parse_basic_line("[11/Jul/2023:08:52:45 +0000] \"GET /user HTTP/1.1\" 404 8080")
+                .unwrap()
+                .path,
             OpenapiPath::from_str("/user").unwrap(),
         );
         assert_eq!(
-            parse_nginx_line(
-                &generate_runtimes(),
-                "[11/Jul/2023:08:52:45 +0000] \"GET / HTTP/1.1\" 404 8080"
-            )
-            .unwrap()
-            .path,
+            parse_basic_line("[11/Jul/2023:08:52:45 +0000] \"GET / HTTP/1.1\" 404 8080")
+                .unwrap()
+                .path,
             OpenapiPath::from_str("/").unwrap(),
         );
     }
 
+    #[test]
+    fn splits_query_from_path() {
+        let endpoint = parse_basic_line(
+            "[11/Jul/2023:08:50:03 +0000] \"GET /orders?status=open%20now&tag=a+b&empty= HTTP/1.1\" 200 8080",
+        )
+        .unwrap();
+
+        assert_eq!(endpoint.path, OpenapiPath::from_str("/orders").unwrap());
+        assert_eq!(
+            endpoint.parameters,
+            vec![
+                RequestParameter::new(ParameterLocation::Query, "status", "open now"),
+                RequestParameter::new(ParameterLocation::Query, "tag", "a b"),
+                RequestParameter::new(ParameterLocation::Query, "empty", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn normalizes_path() {
+        let endpoint = parse_basic_line(
+            "[11/Jul/2023:08:50:03 +0000] \"GET /cities/S%C3%A3o%20Paulo?units=metric HTTP/1.1\" 200 8080",
+        )
+        .unwrap();
+
+        assert_eq!(endpoint.path, OpenapiPath::from_str("/cities/S\u{e3}o Paulo").unwrap());
+        assert_eq!(
+            endpoint.parameters,
+            vec![RequestParameter::new(ParameterLocation::Query, "units", "metric")]
+        );
+    }
+
+    #[test]
+    fn parses_logged_headers() {
+        let log_format = LogFormat::default()
+            .with_variable("http_accept_language")
+            .with_variable("http_x_tenant");
+        let endpoint = parse_line(
+            &log_format.to_string(),
+            "[11/Jul/2023:08:50:03 +0000] \"GET /orders HTTP/1.1\" 200 8080 \"-\" \"-\" \"-\" \"say \\x22hi\\x22\"",
+        )
+        .unwrap();
+
+        assert_eq!(
+            endpoint.parameters,
+            vec![RequestParameter::new(
+                ParameterLocation::Header,
+                "x-tenant",
+                "say \"hi\""
+            )]
+        );
+    }
+
+    #[test]
+    fn parses_media_types() {
+        let log_format = LogFormat::default().to_string();
+        let endpoint = parse_line(
+            &log_format,
+            "[11/Jul/2023:08:50:03 +0000] \"POST /upload HTTP/1.1\" 201 8080 \"multipart/form-data; boundary=x\" \"text/csv\"",
+        )
+        .unwrap();
+        assert_eq!(endpoint.request_media_type, Some(MediaType::new("multipart/form-data")));
+        assert_eq!(endpoint.response_media_type, Some(MediaType::new("text/csv")));
+
+        let endpoint = parse_line(
+            &log_format,
+            "[11/Jul/2023:08:50:03 +0000] \"GET /upload HTTP/1.1\" 204 8080 \"-\" \"-\"",
+        )
+        .unwrap();
+        assert_eq!(endpoint.request_media_type, None);
+        assert_eq!(endpoint.response_media_type, None);
+    }
+
     #[test]
     fn parses_correct_port() {
         assert_eq!(
-            parse_nginx_line(
-                &generate_runtimes(),
-                "[11/Jul/2023:08:50:03 +0000] \"GET /weather HTTP/1.1\" 200 8080"
-            )
-            .unwrap()
-            .runtime
-            .port,
+            parse_basic_line("[11/Jul/2023:08:50:03 +0000] \"GET /weather HTTP/1.1\" 200 8080")
+                .unwrap()
+                .runtime
+                .port,
             8080
         );
         assert_eq!(
-            parse_nginx_line(
-                &generate_runtimes(),
-                "[11/Jul/2023:08:50:03 +0000] \"POST /weather HTTP/1.1\" 200 13750"
-            )
-            .unwrap()
-            .runtime
-            .port,
+            parse_basic_line("[11/Jul/2023:08:50:03 +0000] \"POST /weather HTTP/1.1\" 200 13750")
+                .unwrap()
+                .runtime
+                .port,
             13750
         );
     }
@@ -238,7 +608,141 @@ mod test {
     #[test]
     fn parses_full_access_log() {
         let path = Path::new("./dump/access.log");
-        assert_eq!(parse_access_log(&generate_runtimes(), path).unwrap().len(), 9);
+        let (endpoints, diagnostics) =
+            parse_access_log(&generate_runtimes(), &LogFormat::default(), path, true).unwrap();
+        assert_eq!(endpoints.len(), 9);
+        assert_eq!(diagnostics.parsed_lines, 9);
+        assert_eq!(diagnostics.rejected_line_count(), 0);
+    }
+
+    #[test]
+    fn parses_every_method() {
+        for method in [
+            Method::GET,
+            Method::PUT,
+            Method::POST,
+            Method::DELETE,
+            Method::OPTIONS,
+            Method::HEAD,
+            Method::PATCH,
+            Method::TRACE,
+        ] {
+            let line = format!("[11/Jul/2023:08:50:03 +0000] \"{} /weather HTTP/1.1\" 200 8080", method);
+            assert_eq!(parse_basic_line(&line).unwrap().method, method);
+        }
+        assert_eq!(
+            parse_basic_line("[11/Jul/2023:08:50:03 +0000] \"PROPFIND /weather HTTP/1.1\" 207 8080"),
+            Err(LogRejection::UnknownMethod)
+        );
+    }
+
+    #[test]
+    fn skips_and_counts_rejected_lines() {
+        let path = Path::new("./dump/access_mixed.log");
+        let (endpoints, diagnostics) =
+            parse_access_log(&generate_runtimes(), &LogFormat::default(), path, false).unwrap();
+
+        assert_eq!(endpoints.len(), 3);
+        assert_eq!(diagnostics.parsed_lines, 3);
+        assert_eq!(
+            diagnostics.rejected_lines,
+            BTreeMap::from([
+                (LogRejection::InvalidFormat, 1),
+                (LogRejection::InvalidRequest, 1),
+                (LogRejection::UnknownMethod, 1),
+                (LogRejection::UnknownPort, 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn fails_on_rejected_lines_when_strict() {
+        let path = Path::new("./dump/access_mixed.log");
+        assert!(matches!(
+            parse_access_log(&generate_runtimes(), &LogFormat::default(), path, true),
+            Err(Error::InvalidLogLine(_, 6, _))
+        ));
+    }
+
+    #[test]
+    fn parses_custom_fields() {
+        let endpoint = parse_line(
+            "'$remote_addr $request_method $request_uri $status $server_port $request_time \"$http_content_type\" \"$upstream_status\" $request_id'",
+            "172.17.0.1 DELETE /orders/1?force=true 204 8080 0.012 \"application/json\" \"204\" 5f2a9c",
+        )
+        .unwrap();
+
+        assert_eq!(endpoint.method, Method::DELETE);
+        assert_eq!(endpoint.path, OpenapiPath::from_str("/orders/1").unwrap());
+        assert_eq!(endpoint.status_code, 204);
+        assert_eq!(endpoint.request_media_type, Some(MediaType::new("application/json")));
+        assert_eq!(
+            endpoint.parameters,
+            vec![
+                RequestParameter::new(ParameterLocation::Query, "force", "true"),
+                RequestParameter::new(ParameterLocation::Header, "content-type", "application/json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_json_log_format() {
+        let log_format = LogFormat::from_str(
+            "escape=json '{\"time\":\"$time_iso8601\",\"request\":\"$request\",\"status\":$status,\"port\":$server_port}'",
+        )
+        .unwrap()
+        .with_variable("http_x_tenant");
+        let endpoint = parse_line(
+            &log_format.to_string(),
+            "{\"time\":\"2023-07-11T08:50:03+00:00\",\"request\":\"GET /orders HTTP/1.1\",\"status\":200,\"port\":8080,\"http_x_tenant\":\"say \\\"hi\\\" \\u00e4\"}",
+        )
+        .unwrap();
+
+        assert_eq!(endpoint.path, OpenapiPath::from_str("/orders").unwrap());
+        assert_eq!(
+            endpoint.parameters,
+            vec![RequestParameter::new(
+                ParameterLocation::Header,
+                "x-tenant",
+                "say \"hi\" \u{e4}"
+            )]
+        );
+    }
+
+    #[test]
+    fn rejects_lines_of_other_formats() {
+        assert!(matches!(
+            parse_basic_line("11/Jul/2023:08:50:03 +0000 \"GET /weather HTTP/1.1\" 200 8080"),
+            Err(LogRejection::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn parses_graphql_fields_of_request_body() {
+        let log_format = LogFormat::from_str(BASIC_LOG_FORMAT)
+            .unwrap()
+            .with_variable("request_body");
+        let line_parser = LogLineParser::new(&log_format).unwrap();
+        let endpoints = parse_graphql_line(
+            &generate_runtimes(),
+            &line_parser,
+            "[11/Jul/2023:08:50:03 +0000] \"POST /graphql HTTP/1.1\" 200 8080 \"{\\x22query\\x22:\\x22{ user(id: 1) { name } }\\x22}\"",
+        )
+        .unwrap();
+
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints[0].graphql_field, Some("query.user.name".to_string()));
+        assert_eq!(endpoints[1].graphql_field, Some("query.user".to_string()));
+        assert_eq!(endpoints[1].path, OpenapiPath::from_str("/graphql").unwrap());
+        assert_eq!(endpoints[1].status_code, 200);
+
+        assert!(parse_graphql_line(
+            &generate_runtimes(),
+            &line_parser,
+            "[11/Jul/2023:08:50:03 +0000] \"POST /graphql HTTP/1.1\" 200 8080 \"-\""
+        )
+        .unwrap()
+        .is_empty());
     }
 
     #[test]
@@ -247,17 +751,17 @@ mod test {
             Arc::from(Runtime {
                 port: 8080,
                 openapi_source: OpenapiSource::Path(Box::from(Path::new("./dump"))),
-                app_base_url: Url::from_str("http://example.con").unwrap(),
+                ..create_mock_runtime()
             }),
             Arc::from(Runtime {
                 port: 7890,
                 openapi_source: OpenapiSource::Path(Box::from(Path::new("./dump"))),
-                app_base_url: Url::from_str("http://example.con").unwrap(),
+                ..create_mock_runtime()
             }),
             Arc::from(Runtime {
                 port: 443,
                 openapi_source: OpenapiSource::Path(Box::from(Path::new("./dump"))),
-                app_base_url: Url::from_str("http://example.con").unwrap(),
+                ..create_mock_runtime()
             }),
         ];
         assert_eq!(find_runtime_by_port(&runtimes, 7890).unwrap(), runtimes[1]);
@@ -0,0 +1,385 @@
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use json::JsonValue;
+use yaml_rust::YamlLoader;
+
+use crate::utils::{read_file_to_string_or_err, Error};
+
+use super::common::yaml_to_json;
+
+const REFERENCE_KEY: &str = "$ref";
+const PARAMETERS_KEY: &str = "parameters";
+const RESPONSES_KEY: &str = "responses";
+const REQUEST_BODY_KEY: &str = "requestBody";
+const SCHEMA_KEY: &str = "schema";
+const ITEMS_KEY: &str = "items";
+
+/// Inlines `$ref` objects of path items, parameters, their schemas, request bodies and responses.
+/// References can either point into the same document (`#/components/responses/NotFound`) or to
+/// local files relative to the document they are used in (`./paths/users.yaml`,
+/// `common.yaml#/responses/NotFound`).
+pub struct ReferenceResolver {
+    mount_point: Option<PathBuf>,
+    root_document_path: PathBuf,
+    /// Extension of the pre merge versions of referenced files, which are read instead of the
+    /// files if they exist.
+    path_extension: String,
+    documents: HashMap<PathBuf, JsonValue>,
+}
+
+impl ReferenceResolver {
+    /// Resolver for a spec on disk, external references are not allowed to leave the mount point.
+    pub fn for_document(mount_point: &Path, document_path: &Path) -> ReferenceResolver {
+        ReferenceResolver {
+            mount_point: Some(normalize_path(mount_point)),
+            root_document_path: normalize_path(document_path),
+            path_extension: String::new(),
+            documents: HashMap::new(),
+        }
+    }
+
+    /// Reads the pre merge versions of referenced files, files without one didn't change.
+    pub fn with_path_extension(mut self, path_extension: &str) -> ReferenceResolver {
+        self.path_extension = path_extension.to_string();
+        self
+    }
+
+    /// Resolver for specs without a location on disk, only references within the document work.
+    pub fn detached() -> ReferenceResolver {
+        ReferenceResolver {
+            mount_point: None,
+            root_document_path: PathBuf::new(),
+            path_extension: String::new(),
+            documents: HashMap::new(),
+        }
+    }
+
+    pub fn resolve_paths(&mut self, root_document: &JsonValue) -> Result<JsonValue, Error> {
+        let root_document_path = self.add_root_document(root_document);
+
+        let mut resolved_paths = JsonValue::new_object();
+        for (path_key, path_item) in root_document["paths"].entries() {
+            let (path_item, document_path) = self.resolve(path_item, &root_document_path, &mut vec![])?;
+            resolved_paths[path_key] = self.resolve_path_item(&path_item, &document_path)?;
+        }
+        Ok(resolved_paths)
+    }
+
+    /// Registers the root document, which references without a file point into, and returns its
+    /// path.
+    pub fn add_root_document(&mut self, root_document: &JsonValue) -> PathBuf {
+        self.documents
+            .insert(self.root_document_path.clone(), root_document.clone());
+        self.root_document_path.clone()
+    }
+
+    /// Resolves a single value, which might be a reference, without resolving its members.
+    /// Returns the value along with the document it is located in.
+    pub fn resolve_value(&mut self, value: &JsonValue, document_path: &Path) -> Result<(JsonValue, PathBuf), Error> {
+        self.resolve(value, document_path, &mut vec![])
+    }
+
+    fn resolve_path_item(&mut self, path_item: &JsonValue, document_path: &Path) -> Result<JsonValue, Error> {
+        let mut resolved_path_item = JsonValue::new_object();
+        for (key, value) in path_item.entries() {
+            resolved_path_item[key] = if key == PARAMETERS_KEY {
+                self.resolve_parameters(value, document_path)?
+            } else if value.is_object() && !value[RESPONSES_KEY].is_null() {
+                self.resolve_operation(value, document_path)?
+            } else {
+                value.clone()
+            };
+        }
+        Ok(resolved_path_item)
+    }
+
+    fn resolve_operation(&mut self, operation: &JsonValue, document_path: &Path) -> Result<JsonValue, Error> {
+        let mut resolved_operation = operation.clone();
+        if operation.has_key(PARAMETERS_KEY) {
+            resolved_operation[PARAMETERS_KEY] = self.resolve_parameters(&operation[PARAMETERS_KEY], document_path)?;
+        }
+        if operation.has_key(REQUEST_BODY_KEY) {
+            resolved_operation[REQUEST_BODY_KEY] = self
+                .resolve(&operation[REQUEST_BODY_KEY], document_path, &mut vec![])?
+                .0;
+        }
+
+        let mut resolved_responses = JsonValue::new_object();
+        for (status, response) in operation[RESPONSES_KEY].entries() {
+            resolved_responses[status] = self.resolve(response, document_path, &mut vec![])?.0;
+        }
+        resolved_operation[RESPONSES_KEY] = resolved_responses;
+
+        Ok(resolved_operation)
+    }
+
+    fn resolve_parameters(&mut self, list: &JsonValue, document_path: &Path) -> Result<JsonValue, Error> {
+        if !list.is_array() {
+            return Ok(list.clone());
+        }
+        let mut resolved_list = JsonValue::new_array();
+        for member in list.members() {
+            // unwrap is fine here, since the resolved list is always an array
+            resolved_list
+                .push(self.resolve_parameter(member, document_path)?)
+                .unwrap();
+        }
+        Ok(resolved_list)
+    }
+
+    /// Resolves a parameter along with its schema and the items of the schema, which describe
+    /// the values it can take. Schemas that can't be resolved are kept as they are, since they
+    /// aren't required to generate endpoints.
+    fn resolve_parameter(&mut self, parameter: &JsonValue, document_path: &Path) -> Result<JsonValue, Error> {
+        let (mut parameter, parameter_document_path) = self.resolve(parameter, document_path, &mut vec![])?;
+        if !parameter[SCHEMA_KEY].is_object() {
+            return Ok(parameter);
+        }
+
+        if let Ok((mut schema, schema_document_path)) =
+            self.resolve(&parameter[SCHEMA_KEY], &parameter_document_path, &mut vec![])
+        {
+            if let Ok((items, _)) = self.resolve(&schema[ITEMS_KEY], &schema_document_path, &mut vec![]) {
+                if !items.is_null() {
+                    schema[ITEMS_KEY] = items;
+                }
+            }
+            parameter[SCHEMA_KEY] = schema;
+        }
+        Ok(parameter)
+    }
+
+    /// Follows a chain of references until it reaches an object without `$ref` and returns it
+    /// along with the document it is located in.
+    fn resolve(
+        &mut self,
+        value: &JsonValue,
+        document_path: &Path,
+        visited_references: &mut Vec<String>,
+    ) -> Result<(JsonValue, PathBuf), Error> {
+        let reference = match value[REFERENCE_KEY].as_str() {
+            Some(reference) => reference,
+            None => return Ok((value.clone(), document_path.to_path_buf())),
+        };
+
+        let (file, pointer) = match reference.split_once('#') {
+            Some((file, pointer)) => (file, pointer),
+            None => (reference, ""),
+        };
+        let target_document_path = if file.is_empty() {
+            document_path.to_path_buf()
+        } else {
+            self.get_external_document_path(reference, file, document_path)?
+        };
+
+        let reference_id = format!("{}#{}", target_document_path.display(), pointer);
+        if visited_references.contains(&reference_id) {
+            visited_references.push(reference_id);
+            return Err(Error::CyclicReference(visited_references.join(" -> ")));
+        }
+        visited_references.push(reference_id);
+
+        let target = {
+            let document = self.load_document(reference, &target_document_path)?;
+            match get_pointer_target(document, pointer) {
+                Some(target) => target.clone(),
+                None => {
+                    return Err(Error::UnresolvedReference(
+                        reference.to_string(),
+                        format!("\"{}\" doesn't exist in the referenced document", pointer),
+                    ))
+                }
+            }
+        };
+
+        self.resolve(&target, &target_document_path, visited_references)
+    }
+
+    fn get_external_document_path(&self, reference: &str, file: &str, document_path: &Path) -> Result<PathBuf, Error> {
+        if file.contains("://") {
+            return Err(Error::UnresolvedReference(
+                reference.to_string(),
+                "only references to local files are supported".to_string(),
+            ));
+        }
+        let mount_point = match &self.mount_point {
+            Some(mount_point) => mount_point,
+            None => {
+                return Err(Error::UnresolvedReference(
+                    reference.to_string(),
+                    "references to other files require the spec to be a local file".to_string(),
+                ))
+            }
+        };
+
+        let target_document_path = match document_path.parent() {
+            Some(parent) => normalize_path(&parent.join(file)),
+            None => normalize_path(Path::new(file)),
+        };
+
+        if !target_document_path.starts_with(mount_point)
+            || target_document_path.components().any(|x| x == Component::ParentDir)
+        {
+            return Err(Error::UnresolvedReference(
+                reference.to_string(),
+                "the referenced file is outside of the repository".to_string(),
+            ));
+        }
+        Ok(target_document_path)
+    }
+
+    fn load_document(&mut self, reference: &str, document_path: &Path) -> Result<&JsonValue, Error> {
+        if !self.documents.contains_key(document_path) {
+            let mut extended_document_path = document_path.as_os_str().to_os_string();
+            extended_document_path.push(&self.path_extension);
+            let extended_document_path = PathBuf::from(extended_document_path);
+            let document_string = read_file_to_string_or_err(
+                if extended_document_path.is_file() {
+                    &extended_document_path
+                } else {
+                    document_path
+                },
+                Error::UnresolvedReference(
+                    reference.to_string(),
+                    format!("the file {:?} can't be opened", document_path),
+                ),
+            )?;
+            let document = parse_document(&document_string, document_path).ok_or(Error::UnresolvedReference(
+                reference.to_string(),
+                format!("the file {:?} isn't valid json or yaml", document_path),
+            ))?;
+            self.documents.insert(document_path.to_path_buf(), document);
+        }
+        // unwrap is fine, since the document was inserted above
+        Ok(self.documents.get(document_path).unwrap())
+    }
+}
+
+fn parse_document(document_string: &str, document_path: &Path) -> Option<JsonValue> {
+    let is_json = document_path.extension().and_then(|x| x.to_str()) == Some("json");
+    if is_json {
+        json::parse(document_string).ok()
+    } else {
+        let documents = YamlLoader::load_from_str(document_string).ok()?;
+        documents.first().map(yaml_to_json)
+    }
+}
+
+fn get_pointer_target<'a>(document: &'a JsonValue, pointer: &str) -> Option<&'a JsonValue> {
+    let mut target = document;
+    for token in pointer.split('/').filter(|x| !x.is_empty()) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        target = if target.is_array() {
+            target.members().nth(token.parse().ok()?)?
+        } else if target.has_key(&token) {
+            &target[token.as_str()]
+        } else {
+            return None;
+        };
+    }
+    Some(target)
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized_path = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => match normalized_path.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized_path.pop();
+                }
+                Some(Component::RootDir) => (),
+                _ => normalized_path.push(component),
+            },
+            component => normalized_path.push(component),
+        }
+    }
+    normalized_path
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{get_pointer_target, normalize_path, ReferenceResolver};
+
+    #[test]
+    fn resolves_local_references() {
+        let document = json::parse(
+            r##"{
+                "paths": {
+                    "/users": {
+                        "get": {
+                            "parameters": [{ "$ref": "#/components/parameters/Limit" }],
+                            "responses": { "404": { "$ref": "#/components/responses/NotFound" } }
+                        }
+                    }
+                },
+                "components": {
+                    "parameters": { "Limit": { "name": "limit", "in": "query" } },
+                    "responses": { "NotFound": { "description": "Not Found" } }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let paths = ReferenceResolver::detached().resolve_paths(&document).unwrap();
+        assert_eq!(paths["/users"]["get"]["responses"]["404"]["description"], "Not Found");
+        assert_eq!(paths["/users"]["get"]["parameters"][0]["name"], "limit");
+    }
+
+    #[test]
+    fn detects_cyclic_references() {
+        let document = json::parse(
+            r##"{
+                "paths": { "/users": { "$ref": "#/x-paths/a" } },
+                "x-paths": { "a": { "$ref": "#/x-paths/b" }, "b": { "$ref": "#/x-paths/a" } }
+            }"##,
+        )
+        .unwrap();
+
+        assert!(ReferenceResolver::detached().resolve_paths(&document).is_err());
+    }
+
+    #[test]
+    fn reports_missing_reference_targets() {
+        let document = json::parse(r##"{ "paths": { "/users": { "$ref": "#/x-paths/missing" } } }"##).unwrap();
+
+        assert!(ReferenceResolver::detached().resolve_paths(&document).is_err());
+    }
+
+    #[test]
+    fn detached_resolver_rejects_external_files() {
+        let document = json::parse(r#"{ "paths": { "/users": { "$ref": "./users.yaml" } } }"#).unwrap();
+
+        assert!(ReferenceResolver::detached().resolve_paths(&document).is_err());
+    }
+
+    #[test]
+    fn rejects_files_outside_of_mount_point() {
+        let document = json::parse(r#"{ "paths": { "/users": { "$ref": "../../etc/users.yaml" } } }"#).unwrap();
+
+        assert!(
+            ReferenceResolver::for_document(Path::new("./dump"), Path::new("./dump/openapi.json"))
+                .resolve_paths(&document)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn follows_json_pointer_escapes() {
+        let document = json::parse(r#"{ "paths": { "/users/{id}": { "get": {} } } }"#).unwrap();
+        assert!(get_pointer_target(&document, "/paths/~1users~1{id}/get").is_some());
+    }
+
+    #[test]
+    fn normalizes_paths() {
+        assert_eq!(normalize_path(Path::new("./a/b/../c")), PathBuf::from("a/c"));
+        assert_eq!(normalize_path(Path::new("/repo/../etc")), PathBuf::from("/etc"));
+        assert_eq!(normalize_path(Path::new("../a")), PathBuf::from("../a"));
+    }
+}
@@ -0,0 +1,593 @@
+use std::{collections::BTreeMap, fmt::Display, path::Path, str::FromStr, sync::Arc};
+
+use json::JsonValue;
+
+use crate::{
+    config::Runtime,
+    models::{EndpointConfiguration, Method, Operation, Response, SourceLocation, StatusCode},
+    utils::{read_file_to_string_or_err, Error},
+};
+
+use super::get_extended_path;
+
+const COMMENT_PREFIX: char = '#';
+const RAILS_HEADER: &str = "URI Pattern";
+const FLASK_HEADER: &str = "Endpoint";
+const FLASK_RULE_HEADER: &str = "Rule";
+const RAILS_VERB_SEPERATOR: char = '|';
+const STATUS_CODE_SEPERATOR: char = ',';
+/// Routes don't declare their responses, so they are tested by any successful response.
+const DEFAULT_STATUS_CODE: StatusCode = StatusCode::Range(2);
+
+/// The tools an inventory of routes can be generated by, detected by the content of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteFormat {
+    /// Output of `rails routes`.
+    Rails,
+    /// Output of `flask routes`.
+    Flask,
+    /// Response of the `/actuator/mappings` endpoint of Spring Boot.
+    SpringActuator,
+    /// One `METHOD /path/{param} [status,...]` route per line.
+    Plain,
+}
+
+/// Problem found while parsing an inventory of routes, along with the line it occurs at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteSyntaxError {
+    pub location: Option<SourceLocation>,
+    pub message: String,
+}
+
+impl Display for RouteSyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.location {
+            Some(location) => write!(f, "{}: {}", location, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub method: Method,
+    pub path: String,
+    pub status_codes: Vec<StatusCode>,
+    /// Controller action or handler serving the route, used as the operationId.
+    pub name: Option<String>,
+    pub location: Option<SourceLocation>,
+}
+
+/// Loads the routes of all inventory files of a source. Like with openapi specs, files without a
+/// pre merge version are skipped when reading the pre merge inventory of several files.
+pub fn load_route_inventory(
+    route_paths: &[Box<Path>],
+    mount_point: &str,
+    path_extension: &str,
+    runtime: Arc<Runtime>,
+) -> Result<Vec<EndpointConfiguration>, Error> {
+    let mut routes: Vec<Route> = vec![];
+    for route_path in route_paths {
+        let extended_path = get_extended_path(mount_point, route_path, path_extension)?;
+        if route_paths.len() > 1 && !path_extension.is_empty() && !extended_path.is_file() {
+            continue;
+        }
+
+        let source = read_file_to_string_or_err(
+            &extended_path,
+            Error::ProblemOpeningFile(Box::from(extended_path.as_path())),
+        )?;
+        let file_routes = match parse_routes(&source) {
+            Ok(file_routes) => file_routes,
+            Err(error) => return Err(Error::InvalidRouteInventory(route_path.clone(), error.to_string())),
+        };
+        // the same route can be listed by several files or formats
+        for route in file_routes {
+            if !routes.iter().any(|x| x.method == route.method && x.path == route.path) {
+                routes.push(route);
+            }
+        }
+    }
+    get_route_endpoints(&routes, runtime)
+}
+
+/// Generates an endpoint for every status code of the routes, each route is represented by an
+/// operation of its own so it can be selected by its name.
+pub fn get_route_endpoints(routes: &[Route], runtime: Arc<Runtime>) -> Result<Vec<EndpointConfiguration>, Error> {
+    let mut endpoints = vec![];
+    for route in routes {
+        let operation = Arc::new(Operation {
+            method: route.method.clone(),
+            path: route.path.clone(),
+            operation_id: route.name.clone(),
+            summary: None,
+            deprecated: false,
+            servers: vec![],
+            parameters: vec![],
+            request_media_types: vec![],
+            responses: route
+                .status_codes
+                .iter()
+                .map(|x| Response {
+                    status_code: *x,
+                    media_types: vec![],
+                })
+                .collect(),
+            security: None,
+            tags: vec![],
+            extensions: BTreeMap::new(),
+            location: route.location,
+        });
+
+        for status_code in &route.status_codes {
+            endpoints.push(
+                EndpointConfiguration::new(route.method.clone(), &route.path, *status_code, runtime.clone(), false)?
+                    .with_operation(&operation),
+            );
+        }
+    }
+    Ok(endpoints)
+}
+
+pub fn detect_route_format(source: &str) -> RouteFormat {
+    let first_line = source
+        .lines()
+        .map(|x| x.trim())
+        .find(|x| !x.is_empty() && !x.starts_with(COMMENT_PREFIX))
+        .unwrap_or_default();
+
+    if first_line.starts_with('{') {
+        RouteFormat::SpringActuator
+    } else if first_line.contains(RAILS_HEADER) {
+        RouteFormat::Rails
+    } else if first_line.starts_with(FLASK_HEADER) && first_line.contains(FLASK_RULE_HEADER) {
+        RouteFormat::Flask
+    } else {
+        RouteFormat::Plain
+    }
+}
+
+pub fn parse_routes(source: &str) -> Result<Vec<Route>, RouteSyntaxError> {
+    match detect_route_format(source) {
+        RouteFormat::SpringActuator => parse_spring_actuator_mappings(source),
+        RouteFormat::Rails => Ok(parse_text_routes(source, parse_rails_line)),
+        RouteFormat::Flask => Ok(parse_text_routes(source, parse_flask_line)),
+        RouteFormat::Plain => {
+            let mut routes = vec![];
+            for (index, line) in source.lines().enumerate() {
+                let line = match line.split_once(COMMENT_PREFIX) {
+                    Some((line, _)) => line.trim(),
+                    None => line.trim(),
+                };
+                if line.is_empty() {
+                    continue;
+                }
+                match parse_plain_line(line) {
+                    Ok(mut route) => {
+                        route.location = get_line_location(index);
+                        routes.push(route);
+                    }
+                    Err(message) => {
+                        return Err(RouteSyntaxError {
+                            location: get_line_location(index),
+                            message,
+                        })
+                    }
+                }
+            }
+            Ok(routes)
+        }
+    }
+}
+
+fn get_line_location(index: usize) -> Option<SourceLocation> {
+    Some(SourceLocation {
+        line: index + 1,
+        column: 1,
+    })
+}
+
+/// Parses the routes of tools printing one route per line. Lines that don't describe a route,
+/// like headers and mounted applications without a verb, are skipped.
+fn parse_text_routes(source: &str, parse_line: fn(&[&str]) -> Vec<Route>) -> Vec<Route> {
+    let mut routes = vec![];
+    for (index, line) in source.lines().enumerate() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let mut line_routes = parse_line(&tokens);
+        line_routes
+            .iter_mut()
+            .for_each(|x| x.location = get_line_location(index));
+        routes.append(&mut line_routes);
+    }
+    routes
+}
+
+/// Parses a line like `user GET|POST /users/:id(.:format) users#show`, routes without a name
+/// start with the verb.
+fn parse_rails_line(tokens: &[&str]) -> Vec<Route> {
+    let verb_index = match tokens
+        .iter()
+        .position(|x| parse_methods(x, RAILS_VERB_SEPERATOR).is_some())
+    {
+        Some(verb_index) => verb_index,
+        None => return vec![],
+    };
+    let path = match tokens.get(verb_index + 1) {
+        Some(path) if path.starts_with('/') => convert_rails_path(path),
+        _ => return vec![],
+    };
+    let name = tokens.get(verb_index + 2).map(|x| x.to_string());
+
+    // unwrap is fine, since the verb was found by parsing it
+    parse_methods(tokens[verb_index], RAILS_VERB_SEPERATOR)
+        .unwrap()
+        .into_iter()
+        .map(|method| create_route(method, &path, name.clone()))
+        .collect()
+}
+
+/// Parses a line like `user.show  GET, POST  /users/<int:id>`.
+fn parse_flask_line(tokens: &[&str]) -> Vec<Route> {
+    let rule_index = match tokens.iter().position(|x| x.starts_with('/')) {
+        Some(rule_index) if rule_index >= 2 => rule_index,
+        _ => return vec![],
+    };
+    let methods = match parse_methods(&tokens[1..rule_index].join(""), ',') {
+        Some(methods) => methods,
+        None => return vec![],
+    };
+    let path = convert_flask_path(tokens[rule_index]);
+
+    methods
+        .into_iter()
+        // flask adds them to every route on its own
+        .filter(|x| *x != Method::HEAD && *x != Method::OPTIONS)
+        .map(|method| create_route(method, &path, Some(tokens[0].to_string())))
+        .collect()
+}
+
+/// Parses a line like `GET /users/{id} 200,404`, the status codes can also be enclosed in
+/// brackets and default to any successful response.
+fn parse_plain_line(line: &str) -> Result<Route, String> {
+    let mut tokens = line.splitn(3, char::is_whitespace).filter(|x| !x.is_empty());
+    let method_str = tokens.next().unwrap_or_default();
+    let method = match Method::from_str(method_str) {
+        Some(method) => method,
+        None => return Err(format!("invalid method \"{}\"", method_str)),
+    };
+    let path = match tokens.next() {
+        Some(path) if path.starts_with('/') => path,
+        _ => return Err("expected a path starting with \"/\"".to_string()),
+    };
+
+    let status_codes_str = tokens.next().unwrap_or_default().trim();
+    let status_codes_str = status_codes_str
+        .strip_prefix('[')
+        .and_then(|x| x.strip_suffix(']'))
+        .unwrap_or(status_codes_str);
+    let mut status_codes = vec![];
+    for status_code_str in status_codes_str
+        .split(STATUS_CODE_SEPERATOR)
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+    {
+        match StatusCode::from_str(status_code_str) {
+            Ok(status_code) => status_codes.push(status_code),
+            Err(_) => return Err(format!("invalid status code \"{}\"", status_code_str)),
+        }
+    }
+
+    let mut route = create_route(method, path, None);
+    if !status_codes.is_empty() {
+        route.status_codes = status_codes;
+    }
+    Ok(route)
+}
+
+/// Parses the mappings of all dispatcher servlets and handlers of all application contexts.
+/// Mappings without methods accept any method and are skipped, since the methods the
+/// application expects can't be told.
+fn parse_spring_actuator_mappings(source: &str) -> Result<Vec<Route>, RouteSyntaxError> {
+    let mappings = match json::parse(source) {
+        Ok(mappings) => mappings,
+        Err(error) => {
+            return Err(RouteSyntaxError {
+                location: None,
+                message: error.to_string(),
+            })
+        }
+    };
+    if !mappings["contexts"].is_object() {
+        return Err(RouteSyntaxError {
+            location: None,
+            message: "the actuator mappings don't contain any contexts".to_string(),
+        });
+    }
+
+    let mut routes = vec![];
+    for (_, context) in mappings["contexts"].entries() {
+        for dispatchers in [
+            &context["mappings"]["dispatcherServlets"],
+            &context["mappings"]["dispatcherHandlers"],
+        ] {
+            for (_, dispatcher) in dispatchers.entries() {
+                for mapping in dispatcher.members() {
+                    routes.append(&mut parse_spring_mapping(mapping));
+                }
+            }
+        }
+    }
+    Ok(routes)
+}
+
+fn parse_spring_mapping(mapping: &JsonValue) -> Vec<Route> {
+    let conditions = &mapping["details"]["requestMappingConditions"];
+    let handler_method = &mapping["details"]["handlerMethod"];
+    let name = match (handler_method["className"].as_str(), handler_method["name"].as_str()) {
+        (Some(class_name), Some(method_name)) => Some(format!(
+            "{}#{}",
+            class_name.rsplit('.').next().unwrap_or(class_name),
+            method_name
+        )),
+        _ => None,
+    };
+
+    let mut routes = vec![];
+    for pattern in conditions["patterns"].members().filter_map(|x| x.as_str()) {
+        // wildcards can match any number of segments, which paths can't express
+        if pattern.contains('*') {
+            continue;
+        }
+        let path = convert_spring_path(pattern);
+        for method in conditions["methods"]
+            .members()
+            .filter_map(|x| x.as_str())
+            .filter_map(Method::from_str)
+        {
+            routes.push(create_route(method, &path, name.clone()));
+        }
+    }
+    routes
+}
+
+fn create_route(method: Method, path: &str, name: Option<String>) -> Route {
+    Route {
+        method,
+        path: path.to_string(),
+        status_codes: vec![DEFAULT_STATUS_CODE],
+        name,
+        location: None,
+    }
+}
+
+fn parse_methods(methods_str: &str, seperator: char) -> Option<Vec<Method>> {
+    let mut methods = vec![];
+    for method_str in methods_str.split(seperator) {
+        // lowercase words are names of routes
+        if method_str.chars().any(|x| x.is_lowercase()) {
+            return None;
+        }
+        methods.push(Method::from_str(method_str)?);
+    }
+    Some(methods)
+}
+
+/// Converts `/users/:id(.:format)` into `/users/{id}`, optional segments like the format are left
+/// out and globs like `*path` are treated like a single variable.
+fn convert_rails_path(path: &str) -> String {
+    let mut converted_path = String::new();
+    let mut optional_depth = 0;
+    let mut is_variable = false;
+    for character in path.chars() {
+        if is_variable && !(character.is_alphanumeric() || character == '_') {
+            converted_path.push('}');
+            is_variable = false;
+        }
+        match character {
+            '(' => optional_depth += 1,
+            ')' => optional_depth -= 1,
+            _ if optional_depth > 0 => (),
+            ':' | '*' => {
+                converted_path.push('{');
+                is_variable = true;
+            }
+            character => converted_path.push(character),
+        }
+    }
+    if is_variable {
+        converted_path.push('}');
+    }
+    if converted_path.is_empty() {
+        converted_path.push('/');
+    }
+    converted_path
+}
+
+/// Converts `/users/<int:id>` into `/users/{id}`.
+fn convert_flask_path(path: &str) -> String {
+    convert_variables(path, '<', '>', |x| x.rsplit(':').next().unwrap_or(x))
+}
+
+/// Converts `/users/{id:\d+}` into `/users/{id}`.
+fn convert_spring_path(path: &str) -> String {
+    convert_variables(path, '{', '}', |x| x.split(':').next().unwrap_or(x))
+}
+
+fn convert_variables(path: &str, open: char, close: char, get_name: fn(&str) -> &str) -> String {
+    let mut converted_path = String::new();
+    let mut rest = path;
+    while let Some(start) = rest.find(open) {
+        let end = match rest[start..].find(close) {
+            Some(end) => start + end,
+            None => break,
+        };
+        converted_path.push_str(&rest[..start]);
+        converted_path.push_str(&format!("{{{}}}", get_name(&rest[start + 1..end])));
+        rest = &rest[end + 1..];
+    }
+    converted_path.push_str(rest);
+    converted_path
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{Method, SourceLocation, StatusCode};
+
+    use super::{
+        convert_flask_path, convert_rails_path, convert_spring_path, detect_route_format, parse_routes, RouteFormat,
+    };
+
+    const RAILS_ROUTES: &str = "
+                   Prefix Verb      URI Pattern                       Controller#Action
+                    users GET       /users(.:format)                  users#index
+                          POST      /users(.:format)                  users#create
+                     user GET       /users/:id(.:format)              users#show
+                          PATCH|PUT /users/:id(.:format)              users#update
+              sidekiq_web           /sidekiq                          Sidekiq::Web
+";
+
+    const FLASK_ROUTES: &str = "Endpoint    Methods           Rule
+----------  ----------------  -----------------------
+index       GET               /
+user.show   GET, POST         /users/<int:id>
+static      GET, HEAD         /static/<path:filename>
+";
+
+    const SPRING_MAPPINGS: &str = r#"{
+  "contexts": {
+    "application": {
+      "mappings": {
+        "dispatcherServlets": {
+          "dispatcherServlet": [
+            {
+              "handler": "com.example.UserController#getUser(Long)",
+              "predicate": "{GET [/users/{id}]}",
+              "details": {
+                "handlerMethod": { "className": "com.example.UserController", "name": "getUser" },
+                "requestMappingConditions": { "methods": ["GET", "DELETE"], "patterns": ["/users/{id:\\d+}"] }
+              }
+            },
+            {
+              "handler": "ResourceHttpRequestHandler [classpath [static/]]",
+              "predicate": "/**"
+            },
+            {
+              "handler": "org.springframework.boot.autoconfigure.web.servlet.error.BasicErrorController#error",
+              "predicate": "{ [/error]}",
+              "details": {
+                "handlerMethod": { "className": "BasicErrorController", "name": "error" },
+                "requestMappingConditions": { "methods": [], "patterns": ["/error"] }
+              }
+            }
+          ]
+        }
+      }
+    }
+  }
+}"#;
+
+    fn get_routes(source: &str) -> Vec<(Method, String)> {
+        parse_routes(source)
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.method, x.path))
+            .collect()
+    }
+
+    #[test]
+    fn detects_route_formats() {
+        assert_eq!(detect_route_format(RAILS_ROUTES), RouteFormat::Rails);
+        assert_eq!(detect_route_format(FLASK_ROUTES), RouteFormat::Flask);
+        assert_eq!(detect_route_format(SPRING_MAPPINGS), RouteFormat::SpringActuator);
+        assert_eq!(detect_route_format("# users\nGET /users"), RouteFormat::Plain);
+    }
+
+    #[test]
+    fn parses_rails_routes() {
+        assert_eq!(
+            get_routes(RAILS_ROUTES),
+            vec![
+                (Method::GET, "/users".to_string()),
+                (Method::POST, "/users".to_string()),
+                (Method::GET, "/users/{id}".to_string()),
+                (Method::PATCH, "/users/{id}".to_string()),
+                (Method::PUT, "/users/{id}".to_string()),
+            ]
+        );
+        let routes = parse_routes(RAILS_ROUTES).unwrap();
+        assert_eq!(routes[2].name, Some("users#show".to_string()));
+        assert_eq!(routes[2].location, Some(SourceLocation { line: 5, column: 1 }));
+    }
+
+    #[test]
+    fn parses_flask_routes() {
+        assert_eq!(
+            get_routes(FLASK_ROUTES),
+            vec![
+                (Method::GET, "/".to_string()),
+                (Method::GET, "/users/{id}".to_string()),
+                (Method::POST, "/users/{id}".to_string()),
+                (Method::GET, "/static/{filename}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_spring_actuator_mappings() {
+        assert_eq!(
+            get_routes(SPRING_MAPPINGS),
+            vec![
+                (Method::GET, "/users/{id}".to_string()),
+                (Method::DELETE, "/users/{id}".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_routes(SPRING_MAPPINGS).unwrap()[0].name,
+            Some("UserController#getUser".to_string())
+        );
+        assert!(parse_routes("{ \"mappings\": [] }").is_err());
+    }
+
+    #[test]
+    fn parses_plain_routes() {
+        let routes = parse_routes(
+            "GET /users\n\n# single users\nGET /users/{id} [200, 404]\nDELETE /users/{id} 2XX,default # admins only\n",
+        )
+        .unwrap();
+
+        assert_eq!(routes.len(), 3);
+        assert_eq!(routes[0].status_codes, vec![StatusCode::Range(2)]);
+        assert_eq!(
+            routes[1].status_codes,
+            vec![StatusCode::Exact(200), StatusCode::Exact(404)]
+        );
+        assert_eq!(routes[2].status_codes, vec![StatusCode::Range(2), StatusCode::Default]);
+        assert_eq!(routes[2].location, Some(SourceLocation { line: 5, column: 1 }));
+    }
+
+    #[test]
+    fn reports_invalid_plain_routes() {
+        let error = parse_routes("GET /users\nFETCH /users\n").unwrap_err();
+        assert_eq!(error.location, Some(SourceLocation { line: 2, column: 1 }));
+
+        assert!(parse_routes("GET users").is_err());
+        assert!(parse_routes("GET /users 200,abc").is_err());
+    }
+
+    #[test]
+    fn converts_framework_paths() {
+        assert_eq!(convert_rails_path("/users/:id(.:format)"), "/users/{id}");
+        assert_eq!(convert_rails_path("/files/*path"), "/files/{path}");
+        assert_eq!(convert_rails_path("/posts(/:page)"), "/posts");
+        assert_eq!(
+            convert_rails_path("/:locale/users/:id/edit"),
+            "/{locale}/users/{id}/edit"
+        );
+        assert_eq!(convert_rails_path("/"), "/");
+        assert_eq!(
+            convert_flask_path("/users/<int:id>/posts/<slug>"),
+            "/users/{id}/posts/{slug}"
+        );
+        assert_eq!(convert_spring_path("/users/{id:\\d+}/{name}"), "/users/{id}/{name}");
+    }
+}
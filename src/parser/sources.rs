@@ -0,0 +1,265 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use regex::Regex;
+
+use crate::{
+    models::{OpenapiDocument, Operation},
+    utils::Error,
+};
+
+const GLOB_CHARACTERS: [char; 3] = ['*', '?', '['];
+const OPENAPI_EXTENSIONS: [&str; 3] = ["json", "yaml", "yml"];
+
+/// Expands the configured spec paths into the files they describe. A path can either be a single
+/// file, a directory or a glob like `docs/*.yaml`, the latter two only include json and yaml
+/// files. All paths are relative to the mount point.
+pub fn find_openapi_files(mount_point: &Path, openapi_paths: &[Box<Path>]) -> Result<Vec<PathBuf>, Error> {
+    let mut openapi_files = vec![];
+
+    for openapi_path in openapi_paths {
+        let mut files = match openapi_path.to_str() {
+            Some(pattern) if is_glob(pattern) => {
+                let mut files = find_glob_matches(mount_point, pattern)?;
+                files.retain(|x| is_openapi_file(x));
+                if files.is_empty() {
+                    return Err(Error::NoMatchingOpenapiFiles(pattern.to_string()));
+                }
+                files
+            }
+            _ if mount_point.join(openapi_path).is_dir() => {
+                let mut files = vec![];
+                find_files(mount_point, openapi_path, &mut files);
+                files.retain(|x| is_openapi_file(x));
+                if files.is_empty() {
+                    return Err(Error::NoMatchingOpenapiFiles(openapi_path.display().to_string()));
+                }
+                files
+            }
+            _ => vec![openapi_path.to_path_buf()],
+        };
+
+        files.sort();
+        for file in files {
+            if !openapi_files.contains(&file) {
+                openapi_files.push(file);
+            }
+        }
+    }
+
+    Ok(openapi_files)
+}
+
+/// Combines the documents of several spec files into one. Since the security requirements of a
+/// document only apply to its own operations, they are moved to the operations beforehand.
+pub fn merge_documents(documents: Vec<(PathBuf, OpenapiDocument)>) -> Result<OpenapiDocument, Error> {
+    if documents.len() == 1 {
+        // unwrap is fine, since there is exactly one document
+        return Ok(documents.into_iter().next().unwrap().1);
+    }
+
+    let mut merged_document: Option<OpenapiDocument> = None;
+    let mut operation_files: Vec<PathBuf> = vec![];
+
+    for (file, mut document) in documents {
+        move_security_to_operations(&mut document);
+
+        let merged_document = match &mut merged_document {
+            Some(merged_document) => merged_document,
+            None => {
+                operation_files = document.operations.iter().map(|_| file.clone()).collect();
+                merged_document = Some(document);
+                continue;
+            }
+        };
+
+        for operation in document.operations {
+            let existing_operation = merged_document.operations.iter().position(|x| {
+                x.method == operation.method && x.path == operation.path && x.servers == operation.servers
+            });
+
+            match existing_operation {
+                Some(index) => {
+                    // the same operation can be defined in several files, as long as it's identical
+                    if !is_same_definition(&merged_document.operations[index], &operation) {
+                        return Err(Error::ConflictingOperation(
+                            format!("{} {}", operation.method, operation.path),
+                            operation_files[index].clone().into_boxed_path(),
+                            file.clone().into_boxed_path(),
+                        ));
+                    }
+                }
+                None => {
+                    merged_document.operations.push(operation);
+                    operation_files.push(file.clone());
+                }
+            }
+        }
+
+        if merged_document.host.is_none() {
+            merged_document.host = document.host;
+            merged_document.schemes = document.schemes;
+        }
+    }
+
+    merged_document.ok_or(Error::UnknownInternalError("no openapi document to merge".to_string()))
+}
+
+fn move_security_to_operations(document: &mut OpenapiDocument) {
+    let security = match document.security.take() {
+        Some(security) => security,
+        None => return,
+    };
+
+    for operation in &mut document.operations {
+        if operation.security.is_none() {
+            Arc::make_mut(operation).security = Some(security.clone());
+        }
+    }
+}
+
+/// Compares two operations while ignoring where they are located in their files.
+fn is_same_definition(a: &Operation, b: &Operation) -> bool {
+    let mut a = a.clone();
+    let mut b = b.clone();
+    a.location = None;
+    b.location = None;
+    a == b
+}
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(GLOB_CHARACTERS)
+}
+
+fn is_openapi_file(path: &Path) -> bool {
+    match path.extension().and_then(|x| x.to_str()) {
+        Some(extension) => OPENAPI_EXTENSIONS.contains(&extension),
+        None => false,
+    }
+}
+
+fn find_glob_matches(mount_point: &Path, pattern: &str) -> Result<Vec<PathBuf>, Error> {
+    let pattern = pattern.trim_start_matches("./");
+    let glob_regex = glob_to_regex(pattern)?;
+
+    // only the directory in front of the first wildcard has to be searched
+    let search_directory = match pattern.find(GLOB_CHARACTERS) {
+        Some(index) => match pattern[..index].rfind('/') {
+            Some(index) => Path::new(&pattern[..index]),
+            None => Path::new(""),
+        },
+        None => Path::new(""),
+    };
+
+    let mut files = vec![];
+    find_files(mount_point, search_directory, &mut files);
+    files.retain(|x| match x.to_str() {
+        Some(file) => glob_regex.is_match(file),
+        None => false,
+    });
+    Ok(files)
+}
+
+/// Translates a glob into a regex, `**` matches any number of directories while `*` and `?` only
+/// match within a single path segment.
+fn glob_to_regex(pattern: &str) -> Result<Regex, Error> {
+    let mut regex_str = String::from("^");
+    let mut characters = pattern.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match character {
+            '*' if characters.peek() == Some(&'*') => {
+                characters.next();
+                if characters.peek() == Some(&'/') {
+                    characters.next();
+                    regex_str.push_str("(.*/)?");
+                } else {
+                    regex_str.push_str(".*");
+                }
+            }
+            '*' => regex_str.push_str("[^/]*"),
+            '?' => regex_str.push_str("[^/]"),
+            '[' => {
+                regex_str.push('[');
+                for character in characters.by_ref() {
+                    if character == ']' {
+                        break;
+                    }
+                    if character == '\\' {
+                        regex_str.push('\\');
+                    }
+                    regex_str.push(character);
+                }
+                regex_str.push(']');
+            }
+            character => regex_str.push_str(&regex::escape(&character.to_string())),
+        }
+    }
+    regex_str.push('$');
+
+    match Regex::new(&regex_str) {
+        Ok(regex) => Ok(regex),
+        Err(_) => Err(Error::NoMatchingOpenapiFiles(pattern.to_string())),
+    }
+}
+
+/// Collects all files within a directory of the mount point recursively, relative to the mount
+/// point.
+fn find_files(mount_point: &Path, directory: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(mount_point.join(directory)) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = directory.join(entry.file_name());
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => find_files(mount_point, &path, files),
+            Ok(file_type) if file_type.is_file() => files.push(path),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{find_openapi_files, glob_to_regex};
+
+    #[test]
+    fn translates_globs_to_regex() {
+        let regex = glob_to_regex("docs/*.yaml").unwrap();
+        assert!(regex.is_match("docs/users.yaml"));
+        assert!(!regex.is_match("docs/v1/users.yaml"));
+
+        let regex = glob_to_regex("docs/**/*.y?ml").unwrap();
+        assert!(regex.is_match("docs/users.yaml"));
+        assert!(regex.is_match("docs/v1/users.yml.yaml"));
+        assert!(!regex.is_match("other/users.yaml"));
+
+        let regex = glob_to_regex("docs/[ab].json").unwrap();
+        assert!(regex.is_match("docs/a.json"));
+        assert!(!regex.is_match("docs/c.json"));
+    }
+
+    #[test]
+    fn finds_each_file_once() {
+        let files = find_openapi_files(
+            Path::new("./"),
+            &[
+                Box::from(Path::new("dump/multi")),
+                Box::from(Path::new("dump/multi/*.json")),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            files,
+            vec![Path::new("dump/multi/orders.json"), Path::new("dump/multi/users.yaml")]
+        );
+    }
+}
@@ -1,108 +1,26 @@
-use std::sync::Arc;
+use yaml_rust::YamlLoader;
 
-use linked_hash_map::LinkedHashMap;
-use yaml_rust::{Yaml, YamlLoader};
+use crate::{models::OpenapiDocument, utils::Error};
 
-use crate::{
-    config::Runtime,
-    models::{EndpointConfiguration, Method},
-    parser::common::format_basepath,
-    utils::Error,
-};
+use super::{common::yaml_to_json, document::load_openapi_document, location::SourceMap, reference::ReferenceResolver};
 
-pub fn parse_yaml_doc(yaml_string: &str, runtime: Arc<Runtime>) -> Result<Vec<EndpointConfiguration>, Error> {
+pub fn load_yaml_doc(yaml_string: &str, mut resolver: ReferenceResolver) -> Result<OpenapiDocument, Error> {
     let spec = match YamlLoader::load_from_str(yaml_string) {
         Ok(spec) => spec,
         Err(_) => return Err(Error::InvalidParseSyntax),
     };
 
-    let spec = &spec[0];
-
-    let spec = match spec.as_hash() {
-        Some(spec) => spec,
-        None => return Err(Error::UnknownInternalError("yaml spec can't be serialized".to_string())),
-    };
-
-    let basepath = match spec.get(&Yaml::from_str("basePath")) {
-        Some(basepath) => match basepath.as_str() {
-            Some(basepath) => basepath,
-            None => return Err(Error::InvalidBasePath),
-        },
-        None => "",
-    };
-    let basepath = format_basepath(basepath);
-
-    let paths = match spec.get(&Yaml::from_str("paths")) {
-        Some(paths) => match paths.as_hash() {
-            Some(paths) => paths,
-            None => return Err(Error::InvalidParseSyntax),
-        },
+    let spec = match spec.first() {
+        Some(spec) if spec.as_hash().is_some() => spec,
+        Some(_) => return Err(Error::UnknownInternalError("yaml spec can't be serialized".to_string())),
         None => return Err(Error::InvalidParseSyntax),
     };
 
-    let mut endpoints = vec![];
-
-    for path_key in paths.keys() {
-        // unwrap is fine here, as we can expect keys to be strings
-        let path = format!("{}{}", basepath, path_key.as_str().unwrap());
-        let methods = retrive_value_as_hash_map(paths, path_key)?;
-
-        for method_key in methods.keys() {
-            // unwrap is fine here, as we can expect keys to be strings
-            let method = match Method::from_str(method_key.as_str().unwrap()) {
-                Some(method) => method,
-                None => return Err(Error::InvalidParseMethod(String::from(path_key.as_str().unwrap()))),
-            };
-
-            let method_infos = retrive_value_as_hash_map(methods, method_key)?;
-            let statuses = retrive_value_as_hash_map(method_infos, &Yaml::from_str("responses"))?;
-            if method_infos.get(&Yaml::from_str("security")).is_some() {
-                endpoints.push(EndpointConfiguration::new(
-                    method.clone(),
-                    &path,
-                    401,
-                    runtime.clone(),
-                    true,
-                )?);
-                endpoints.push(EndpointConfiguration::new(
-                    method.clone(),
-                    &path,
-                    403,
-                    runtime.clone(),
-                    true,
-                )?);
-            }
-
-            for status_key in statuses.keys() {
-                let status_code = match status_key.as_str().unwrap().parse() {
-                    Ok(status_code) => status_code,
-                    Err(_) => return Err(Error::InvalidParseStatusCode(status_key.as_str().unwrap().to_string())),
-                };
-                endpoints.push(EndpointConfiguration::new(
-                    method.clone(),
-                    &path,
-                    status_code,
-                    runtime.clone(),
-                    false,
-                )?);
-            }
-        }
-    }
-
-    Ok(endpoints)
-}
-
-fn retrive_value_as_hash_map<'a>(
-    parent: &'a LinkedHashMap<Yaml, Yaml>,
-    key: &Yaml,
-) -> Result<&'a LinkedHashMap<Yaml, Yaml>, Error> {
-    match parent.get(key) {
-        Some(child) => match child.as_hash() {
-            Some(child) => Ok(child),
-            None => Err(Error::InvalidParseSyntax),
-        },
-        None => Err(Error::UnknownInternalError("yaml spec parent should exist".to_string())),
-    }
+    load_openapi_document(
+        &yaml_to_json(spec),
+        &mut resolver,
+        SourceMap::scan(yaml_string).as_ref(),
+    )
 }
 
 #[cfg(test)]
@@ -110,11 +28,18 @@ mod tests {
     use std::{str::FromStr, sync::Arc};
 
     use crate::{
-        models::{Method, OpenapiPath},
-        parser::yaml_parser::parse_yaml_doc,
-        utils::test::create_mock_runtime,
+        config::Runtime,
+        models::{EndpointConfiguration, Method, OpenapiPath, ParameterLocation, StatusCode},
+        parser::{document::get_document_endpoints, reference::ReferenceResolver},
+        utils::{test::create_mock_runtime, Error},
     };
 
+    use super::load_yaml_doc;
+
+    fn parse_yaml_doc(yaml_string: &str, runtime: Arc<Runtime>) -> Result<Vec<EndpointConfiguration>, Error> {
+        get_document_endpoints(&load_yaml_doc(yaml_string, ReferenceResolver::detached())?, runtime)
+    }
+
     const YAML_STRING: &str = "
 basePath: /
 paths:
@@ -209,4 +134,165 @@ paths:
             1
         );
     }
+
+    #[test]
+    fn marks_security_responses_as_generated() {
+        let endpoints = parse_yaml_doc(YAML_STRING, Arc::from(create_mock_runtime())).unwrap();
+        assert!(endpoints
+            .iter()
+            .filter(|x| x.status_code == 401 || x.status_code == 403)
+            .all(|x| x.is_generated));
+        assert!(endpoints
+            .iter()
+            .filter(|x| x.status_code == 200)
+            .all(|x| !x.is_generated));
+    }
+
+    const YAML_STRING_OPENAPI_3: &str = "
+openapi: 3.0.0
+servers:
+  - url: https://example.com/api
+  - url: /api
+paths:
+  /users:
+    post:
+      responses:
+        \"201\":
+          description: Created
+          content:
+            application/json:
+              schema:
+                $ref: '#/components/schemas/User'
+components:
+  schemas:
+    User:
+      type: object
+";
+
+    #[test]
+    fn parses_openapi_3_document() {
+        let endpoints = parse_yaml_doc(YAML_STRING_OPENAPI_3, Arc::from(create_mock_runtime())).unwrap();
+        assert_eq!(endpoints.len(), 1);
+        assert_eq!(endpoints[0].path, OpenapiPath::from_str("/api/users").unwrap());
+        assert_eq!(endpoints[0].method, Method::POST);
+        assert_eq!(endpoints[0].status_code, 201);
+    }
+
+    #[test]
+    fn parses_unquoted_openapi_version() {
+        assert!(parse_yaml_doc(
+            "openapi: 3.1\ncomponents:\n  schemas: {}\n",
+            Arc::from(create_mock_runtime())
+        )
+        .unwrap()
+        .is_empty());
+    }
+
+    const YAML_STRING_SERVER_OVERRIDES: &str = "
+openapi: 3.0.0
+servers:
+  - url: https://{env}.example.com/api/{version}
+    variables:
+      env:
+        default: prod
+      version:
+        default: v1
+paths:
+  /users:
+    get:
+      servers:
+        - url: /legacy
+      responses:
+        \"200\":
+          description: OK
+  /health:
+    servers:
+      - url: /
+    get:
+      responses:
+        \"200\":
+          description: OK
+";
+
+    #[test]
+    fn applies_server_variables_and_overrides() {
+        let mut runtime = create_mock_runtime();
+        runtime.server_variables.insert("version".to_string(), "v2".to_string());
+        let endpoints = parse_yaml_doc(YAML_STRING_SERVER_OVERRIDES, Arc::from(runtime)).unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert!(endpoints
+            .iter()
+            .any(|x| x.path == OpenapiPath::from_str("/legacy/users").unwrap()));
+        assert!(endpoints
+            .iter()
+            .any(|x| x.path == OpenapiPath::from_str("/health").unwrap()));
+    }
+
+    #[test]
+    fn substitutes_server_variables() {
+        let endpoints = parse_yaml_doc(
+            "openapi: 3.0.0\nservers:\n  - url: /api/{version}\n    variables:\n      version:\n        default: v3\npaths:\n  /foo:\n    get:\n      responses:\n        \"200\":\n          description: OK\n",
+            Arc::from(create_mock_runtime()),
+        )
+        .unwrap();
+        assert_eq!(endpoints[0].path, OpenapiPath::from_str("/api/v3/foo").unwrap());
+    }
+
+    const YAML_STRING_PATH_ITEM_FIELDS: &str = "
+swagger: \"2.0\"
+paths:
+  /users/{id}:
+    summary: A single user
+    x-owner: team-users
+    parameters:
+      - name: id
+        in: path
+        type: string
+      - name: expand
+        in: query
+        type: boolean
+    get:
+      responses:
+        \"200\":
+          description: OK
+";
+
+    #[test]
+    fn merges_path_level_parameters_and_keeps_extensions() {
+        let endpoints = parse_yaml_doc(YAML_STRING_PATH_ITEM_FIELDS, Arc::from(create_mock_runtime())).unwrap();
+        assert_eq!(endpoints.len(), 1);
+
+        let operation = endpoints[0].operation.clone().unwrap();
+        assert!(operation
+            .parameters
+            .iter()
+            .any(|x| x.name == "id" && x.location == ParameterLocation::Path && x.required));
+        assert!(operation
+            .parameters
+            .iter()
+            .any(|x| x.name == "expand" && x.location == ParameterLocation::Query));
+        assert_eq!(operation.extensions.get("x-owner").unwrap(), "\"team-users\"");
+    }
+
+    #[test]
+    fn parses_default_and_range_status_codes() {
+        let endpoints = parse_yaml_doc(
+            "
+swagger: \"2.0\"
+paths:
+  /foo:
+    get:
+      responses:
+        4XX:
+          description: Client Error
+        default:
+          description: Unexpected Error
+",
+            Arc::from(create_mock_runtime()),
+        )
+        .unwrap();
+
+        assert!(endpoints.iter().any(|x| x.status_code == StatusCode::Range(4)));
+        assert!(endpoints.iter().any(|x| x.status_code == StatusCode::Default));
+    }
 }
@@ -0,0 +1,379 @@
+use std::{
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use reqwest::blocking::Client;
+use tiny_http::{Header, Request, Response, Server};
+use url::Url;
+
+use crate::{
+    config::{CoveAPIConfig, Runtime},
+    models::{EndpointConfiguration, Exchange},
+    parser::parse_exchange,
+    utils::{print_debug_message, Error},
+};
+
+/// Headers that only concern a single connection, proxies don't forward them.
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+// set again by the http client and server for the forwarded messages
+const RECOMPUTED_HEADERS: [&str; 2] = ["host", "content-length"];
+const GATEWAY_ERROR_STATUS: u16 = 502;
+const GATEWAY_ERROR_MESSAGE: &str =
+    "CoveAPI could not connect to your service, please double check that you specified the correct uri.";
+// matches the default read timeout of nginx
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Reverse proxy forwarding the requests to the ports of the runtimes to their applications,
+/// every exchange is recorded as the endpoints it called.
+pub struct ReverseProxy {
+    servers: Vec<Arc<Server>>,
+    listeners: Vec<JoinHandle<()>>,
+    is_stopped: Arc<AtomicBool>,
+    recorded_endpoints: Arc<Mutex<Vec<EndpointConfiguration>>>,
+}
+
+/// Everything the threads handling requests share.
+#[derive(Clone)]
+struct ProxyContext {
+    client: Client,
+    graphql_path: String,
+    recorded_endpoints: Arc<Mutex<Vec<EndpointConfiguration>>>,
+}
+
+impl ReverseProxy {
+    pub fn start(config: &CoveAPIConfig) -> Result<ReverseProxy, Error> {
+        let mut listeners = vec![];
+        for runtime in &config.runtimes {
+            match TcpListener::bind(("0.0.0.0", runtime.port)) {
+                Ok(listener) => listeners.push((runtime.clone(), listener)),
+                Err(why) => return Err(Error::ProblemBindingPort(runtime.port, why.to_string())),
+            }
+        }
+        ReverseProxy::start_on_listeners(listeners, &config.graphql_path)
+    }
+
+    fn start_on_listeners(
+        listeners: Vec<(Arc<Runtime>, TcpListener)>,
+        graphql_path: &str,
+    ) -> Result<ReverseProxy, Error> {
+        let context = ProxyContext {
+            client: build_client()?,
+            graphql_path: graphql_path.to_string(),
+            recorded_endpoints: Arc::new(Mutex::new(vec![])),
+        };
+        let is_stopped = Arc::new(AtomicBool::new(false));
+
+        let mut servers = vec![];
+        let mut listener_handles = vec![];
+        for (runtime, listener) in listeners {
+            let server = match Server::from_listener(listener, None) {
+                Ok(server) => Arc::new(server),
+                Err(why) => return Err(Error::ProblemBindingPort(runtime.port, why.to_string())),
+            };
+            print_debug_message(format!(
+                "Forwarding requests to port {} to {}",
+                runtime.port, runtime.app_base_url
+            ));
+
+            let listener_server = server.clone();
+            let listener_context = context.clone();
+            let listener_is_stopped = is_stopped.clone();
+            listener_handles.push(thread::spawn(move || {
+                listen(listener_server, runtime, listener_context, listener_is_stopped)
+            }));
+            servers.push(server);
+        }
+
+        Ok(ReverseProxy {
+            servers,
+            listeners: listener_handles,
+            is_stopped,
+            recorded_endpoints: context.recorded_endpoints,
+        })
+    }
+
+    /// Stops accepting requests and returns the recorded endpoints, once the requests that are
+    /// still being forwarded are answered.
+    pub fn stop(self) -> Vec<EndpointConfiguration> {
+        self.is_stopped.store(true, Ordering::SeqCst);
+        for server in &self.servers {
+            server.unblock();
+        }
+        for listener in self.listeners {
+            if listener.join().is_err() {
+                print_debug_message("A thread of the proxy panicked");
+            }
+        }
+
+        match self.recorded_endpoints.lock() {
+            Ok(recorded_endpoints) => recorded_endpoints.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+}
+
+fn build_client() -> Result<Client, Error> {
+    // redirects and proxies are left to the client sending the request
+    let client = Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .no_proxy()
+        .timeout(FORWARD_TIMEOUT)
+        .build();
+    match client {
+        Ok(client) => Ok(client),
+        Err(why) => Err(Error::UnknownInternalError(format!("proxy client {}", why))),
+    }
+}
+
+/// Hands every request to its own thread, so slow applications don't hold up other requests.
+fn listen(server: Arc<Server>, runtime: Arc<Runtime>, context: ProxyContext, is_stopped: Arc<AtomicBool>) {
+    let mut request_handles: Vec<JoinHandle<()>> = vec![];
+    loop {
+        let request = match server.recv() {
+            Ok(request) => request,
+            Err(_) if is_stopped.load(Ordering::SeqCst) => break,
+            Err(why) => {
+                print_debug_message(format!("Accepting a request failed: {}", why));
+                continue;
+            }
+        };
+
+        request_handles.retain(|x| !x.is_finished());
+        let runtime = runtime.clone();
+        let context = context.clone();
+        request_handles.push(thread::spawn(move || handle_request(request, runtime, context)));
+    }
+
+    for request_handle in request_handles {
+        if request_handle.join().is_err() {
+            print_debug_message("A thread of the proxy panicked");
+        }
+    }
+}
+
+fn handle_request(mut request: Request, runtime: Arc<Runtime>, context: ProxyContext) {
+    let mut request_body = vec![];
+    if let Err(why) = request.as_reader().read_to_end(&mut request_body) {
+        print_debug_message(format!("Reading a request body failed: {}", why));
+    }
+    let mut exchange = Exchange {
+        method: request.method().as_str().to_string(),
+        url: request.url().to_string(),
+        request_headers: request
+            .headers()
+            .iter()
+            .map(|x| (x.field.as_str().to_string(), x.value.to_string()))
+            .collect(),
+        request_body,
+        status: GATEWAY_ERROR_STATUS,
+        response_headers: vec![],
+    };
+
+    let response = match forward_request(&context.client, &runtime.app_base_url, &exchange) {
+        Ok((status, response_headers, response_body)) => {
+            exchange.status = status;
+            exchange.response_headers = response_headers;
+            build_response(status, &exchange.response_headers, response_body)
+        }
+        Err(why) => {
+            print_debug_message(format!(
+                "Forwarding {} {} failed: {}",
+                exchange.method, exchange.url, why
+            ));
+            Response::from_string(GATEWAY_ERROR_MESSAGE).with_status_code(GATEWAY_ERROR_STATUS)
+        }
+    };
+    if let Err(why) = request.respond(response) {
+        print_debug_message(format!(
+            "Answering {} {} failed: {}",
+            exchange.method, exchange.url, why
+        ));
+    }
+
+    record_exchange(runtime, &exchange, &context);
+}
+
+type ForwardedResponse = (u16, Vec<(String, String)>, Vec<u8>);
+
+fn forward_request(client: &Client, app_base_url: &Url, exchange: &Exchange) -> Result<ForwardedResponse, String> {
+    let method = match reqwest::Method::from_bytes(exchange.method.as_bytes()) {
+        Ok(method) => method,
+        Err(why) => return Err(why.to_string()),
+    };
+    // like nginx, the path of the request is appended to the one of the application
+    let url = format!("{}{}", app_base_url.as_str().trim_end_matches('/'), exchange.url);
+
+    let mut forwarded_request = client.request(method, url).body(exchange.request_body.clone());
+    for (name, value) in &exchange.request_headers {
+        if is_forwarded_header(name) {
+            forwarded_request = forwarded_request.header(name, value);
+        }
+    }
+
+    let response = match forwarded_request.send() {
+        Ok(response) => response,
+        Err(why) => return Err(why.to_string()),
+    };
+    let status = response.status().as_u16();
+    let response_headers = response
+        .headers()
+        .iter()
+        .filter(|(name, _)| is_forwarded_header(name.as_str()))
+        .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).to_string()))
+        .collect();
+    match response.bytes() {
+        Ok(response_body) => Ok((status, response_headers, response_body.to_vec())),
+        Err(why) => Err(why.to_string()),
+    }
+}
+
+fn is_forwarded_header(name: &str) -> bool {
+    let name = name.to_lowercase();
+    !HOP_BY_HOP_HEADERS.contains(&name.as_str()) && !RECOMPUTED_HEADERS.contains(&name.as_str())
+}
+
+fn build_response(
+    status: u16,
+    response_headers: &[(String, String)],
+    response_body: Vec<u8>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let mut response = Response::from_data(response_body).with_status_code(status);
+    for (name, value) in response_headers {
+        match Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+            Ok(header) => response.add_header(header),
+            Err(_) => print_debug_message(format!("Dropping invalid response header {}", name)),
+        }
+    }
+    response
+}
+
+fn record_exchange(runtime: Arc<Runtime>, exchange: &Exchange, context: &ProxyContext) {
+    let mut endpoints = match parse_exchange(runtime, exchange, &context.graphql_path) {
+        Ok(endpoints) => endpoints,
+        Err(error) => {
+            print_debug_message(format!(
+                "Ignoring request {} {}: {}",
+                exchange.method,
+                exchange.url,
+                error.get_error_msg()
+            ));
+            return;
+        }
+    };
+    match context.recorded_endpoints.lock() {
+        Ok(mut recorded_endpoints) => recorded_endpoints.append(&mut endpoints),
+        Err(poisoned) => poisoned.into_inner().append(&mut endpoints),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        str::FromStr,
+        sync::Arc,
+        thread,
+    };
+
+    use url::Url;
+
+    use crate::{models::MediaType, utils::test::create_mock_runtime};
+
+    use super::ReverseProxy;
+
+    /// Answers a single request with the given response and returns the request received.
+    fn serve_response(response: &'static str) -> (Url, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::from_str(&format!(
+            "http://127.0.0.1:{}/api",
+            listener.local_addr().unwrap().port()
+        ))
+        .unwrap();
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 4096];
+            let length = stream.read(&mut buffer).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&buffer[..length]).to_string()
+        });
+        (url, handle)
+    }
+
+    fn send_request(port: u16, request: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn start_proxy(app_base_url: Url) -> (ReverseProxy, u16) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut runtime = create_mock_runtime();
+        runtime.port = listener.local_addr().unwrap().port();
+        runtime.app_base_url = app_base_url;
+        let port = runtime.port;
+        (
+            ReverseProxy::start_on_listeners(vec![(Arc::new(runtime), listener)], "/graphql").unwrap(),
+            port,
+        )
+    }
+
+    #[test]
+    fn forwards_and_records_requests() {
+        let (app_base_url, handle) = serve_response(
+            "HTTP/1.1 201 Created\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+        );
+        let (proxy, port) = start_proxy(app_base_url);
+
+        let response = send_request(
+            port,
+            "POST /users?notify=true HTTP/1.1\r\nHost: coveapi\r\nX-Tenant: acme\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+        );
+        assert!(response.starts_with("HTTP/1.1 201"));
+        assert!(response.ends_with("{}"));
+
+        let forwarded_request = handle.join().unwrap();
+        assert!(forwarded_request.starts_with("POST /api/users?notify=true HTTP/1.1"));
+        assert!(forwarded_request.to_lowercase().contains("x-tenant: acme"));
+
+        let endpoints = proxy.stop();
+        assert_eq!(endpoints.len(), 1);
+        assert_eq!(endpoints[0].status_code, 201);
+        assert_eq!(
+            endpoints[0].response_media_type,
+            Some(MediaType::new("application/json"))
+        );
+    }
+
+    #[test]
+    fn answers_with_gateway_error_if_application_is_unreachable() {
+        // the port is free again once the listener is dropped
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let (proxy, proxy_port) = start_proxy(Url::from_str(&format!("http://127.0.0.1:{}", port)).unwrap());
+
+        let response = send_request(
+            proxy_port,
+            "GET / HTTP/1.1\r\nHost: coveapi\r\nConnection: close\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 502"));
+        assert_eq!(proxy.stop()[0].status_code, 502);
+    }
+}
@@ -14,6 +14,7 @@ pub enum Error {
     InvalidMethodString(String),
     InvalidParseStatusCode(String),
     InvalidParseMethod(String),
+    InvalidParseParameter(String),
     ProblemOpeningFile(Box<Path>),
     UnknownInternalError(String),
     UnknownOpenApiFormat,
@@ -28,10 +29,36 @@ pub enum Error {
     OpenapiPathIsAbsolute(Box<Path>),
     MappingDuplicatePorts,
     InvalidPath(String),
+    UnsupportedOpenapiVersion(String),
+    InvalidServerVariable(String),
+    UnresolvedReference(String, String),
+    CyclicReference(String),
+    MissingApplicationURL,
+    NoMatchingOpenapiFiles(String),
+    InvalidEndpointSelector(String),
+    InvalidThreshold(String),
+    InvalidDeprecatedPolicy(String),
+    ConflictingOperation(String, Box<Path>, Box<Path>),
+    InvalidGraphqlSyntax(Box<Path>, String),
+    InvalidRouteInventory(Box<Path>, String),
+    InvalidOpenapiHeader(String),
+    InvalidHostRewrite(String),
+    InvalidDuration(String),
+    InvalidCaBundle(Box<Path>),
+    OpenapiFetchFailedStatus(String, u16),
+    InvalidOpenapiSnapshot(Box<Path>),
+    ProblemWritingFile(Box<Path>),
+    InvalidProxy(String),
+    ProblemBindingPort(u16, String),
+    InvalidLogFormat(String),
+    InvalidLogLine(Box<Path>, usize, String),
+    InvalidHarFile(Box<Path>),
+    InvalidHarEntry(Box<Path>, usize, String),
+    MissingHarFiles,
 }
 
 impl Error {
-    fn get_error_msg(&self) -> String {
+    pub fn get_error_msg(&self) -> String {
         match self {
             Error::InvalidApplicationURL(err_msg) => format!("Invalid application URL provided: {}", err_msg),
             Error::MissingConfiguration => "Your configuration is missing wither a mapping or an openapi source with it's respective application URL.".to_string(),
@@ -40,6 +67,7 @@ impl Error {
             Error::ProblemOpeningFile(path) => format!("An issue opening the openapi ({:?}) file occured.", path),
             Error::InvalidParseSyntax => "The syntax of the openapi file is incorrect.".to_string(),
             Error::InvalidParseMethod(method) => format!("The openapi file contains an invalid method: {}", method),
+            Error::InvalidParseParameter(parameter) => format!("The openapi file contains an invalid parameter: {}", parameter),
             Error::InvalidParseStatusCode(code) => format!("The openapi file contains an invalid status code: {}", code),
             Error::UnknownInternalError(err) => format!("An unknown internal error occured, please open an issue on github for this [{}].", err),
             Error::InvalidBasePath => "Basepath provided in openapi spec isn't valid.".to_string(),
@@ -57,6 +85,32 @@ impl Error {
             Error::OpenapiPathIsAbsolute(path) => format!("The following path is absolute, please only specify relative paths: {}", path.to_str().unwrap_or("<empty>")),
             Error::MappingDuplicatePorts => "The mapping contains duplicate ports, every port can only be used once.".to_string(),
             Error::InvalidPath(path) => format!("The following path failed to parse: {}", path),
+            Error::InvalidServerVariable(variable) => format!("The following server variable is invalid, please follow the 'name; value;' syntax: {}", variable),
+            Error::UnresolvedReference(reference, reason) => format!("The reference \"{}\" in the openapi file can't be resolved, {}.", reference, reason),
+            Error::CyclicReference(references) => format!("The openapi file contains a cyclic reference: {}", references),
+            Error::MissingApplicationURL => "No application URL was provided and the openapi spec doesn't define a host to derive it from.".to_string(),
+            Error::InvalidEndpointSelector(selector) => format!("The following selector is invalid, please provide a path, \"operationId:<id>\" or \"tag:<tag>\": {}", selector),
+            Error::InvalidThreshold(threshold) => format!("The following threshold is invalid, please follow the 'selector; test coverage;' syntax: {}", threshold),
+            Error::InvalidDeprecatedPolicy(policy) => format!("The deprecated operations policy has to be either \"include\", \"exclude\" or \"report-only\", found: {}", policy),
+            Error::NoMatchingOpenapiFiles(pattern) => format!("No openapi files were found for the following path or glob: {}", pattern),
+            Error::ConflictingOperation(operation, first_path, second_path) => format!("The operation {} is defined differently in {:?} and {:?}.", operation, first_path, second_path),
+            Error::UnsupportedOpenapiVersion(version) => format!("CoveAPI only supports Swagger 2.0 and OpenAPI 3.x specs, found version: {}", version),
+            Error::InvalidGraphqlSyntax(path, err_msg) => format!("The GraphQL schema {:?} is invalid at {}.", path, err_msg),
+            Error::InvalidRouteInventory(path, err_msg) => format!("The route inventory {:?} is invalid: {}", path, err_msg),
+            Error::InvalidOpenapiHeader(header) => format!("The following openapi header is invalid, please follow the 'name; value;' syntax: {}", header),
+            Error::InvalidHostRewrite(host_rewrite) => format!("The following host rewrite is invalid, please follow the 'host; replacement;' syntax: {}", host_rewrite),
+            Error::InvalidDuration(duration) => format!("The following duration is invalid, please provide a number of seconds: {}", duration),
+            Error::InvalidCaBundle(path) => format!("The CA bundle {:?} doesn't contain valid PEM certificates.", path),
+            Error::OpenapiFetchFailedStatus(url, status) => format!("Fetching the openapi spec from {} failed with status {}.", url, status),
+            Error::InvalidOpenapiSnapshot(path) => format!("The openapi snapshot {:?} is invalid, snapshots can only be taken of specs fetched from a url and need to be .json, .yaml or .yml files.", path),
+            Error::ProblemWritingFile(path) => format!("An issue writing the file {:?} occured.", path),
+            Error::InvalidProxy(proxy) => format!("The proxy has to be either \"nginx\", \"builtin\" or \"none\", found: {}", proxy),
+            Error::ProblemBindingPort(port, err_msg) => format!("CoveAPI couldn't listen on port {}: {}", port, err_msg),
+            Error::InvalidLogFormat(log_format) => format!("The following log format is invalid, please provide an nginx log_format containing $request (or $request_method and $request_uri), $status and $server_port: {}", log_format),
+            Error::InvalidLogLine(path, line_number, reason) => format!("Line {} of the log {:?} can't be parsed, it's a line {}.", line_number, path, reason),
+            Error::InvalidHarFile(path) => format!("The HAR file {:?} can't be parsed, it has to be a JSON document with a list of entries at log.entries.", path),
+            Error::InvalidHarEntry(path, entry_number, reason) => format!("Entry {} of the HAR file {:?} can't be imported, it's an entry {}.", entry_number, path, reason),
+            Error::MissingHarFiles => "Without a proxy, CoveAPI needs at least one HAR file to import the requests from.".to_string(),
         }
     }
 
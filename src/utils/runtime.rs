@@ -20,7 +20,7 @@ mod tests {
     use std::sync::Arc;
 
     use crate::{
-        models::{EndpointConfiguration, Method},
+        models::{EndpointConfiguration, Method, StatusCode},
         utils::test::create_mock_runtime,
     };
 
@@ -37,9 +37,9 @@ mod tests {
         let runtime_b = Arc::from(runtime_b);
 
         let endpoint_configs = vec![
-            EndpointConfiguration::new(Method::GET, "/", 200, runtime_a.clone(), false).unwrap(),
-            EndpointConfiguration::new(Method::GET, "/", 502, runtime_a.clone(), false).unwrap(),
-            EndpointConfiguration::new(Method::GET, "/", 404, runtime_b.clone(), false).unwrap(),
+            EndpointConfiguration::new(Method::GET, "/", StatusCode::Exact(200), runtime_a.clone(), false).unwrap(),
+            EndpointConfiguration::new(Method::GET, "/", StatusCode::Exact(502), runtime_a.clone(), false).unwrap(),
+            EndpointConfiguration::new(Method::GET, "/", StatusCode::Exact(404), runtime_b.clone(), false).unwrap(),
         ];
 
         let sorted = sort_by_runtime(&endpoint_configs);
@@ -1,8 +1,14 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
 
 use reqwest::Url;
 
-use crate::config::{CoveAPIConfig, OpenapiSource, Runtime};
+use crate::{
+    config::{CoveAPIConfig, OpenapiSource, PathNormalization, Runtime},
+    models::{Method, Operation},
+};
 
 pub fn create_mock_config() -> CoveAPIConfig {
     let mut env_vars = HashMap::new();
@@ -19,5 +25,27 @@ pub fn create_mock_runtime() -> Runtime {
         openapi_source: OpenapiSource::Url(Url::from_str("https://example.com").unwrap()),
         app_base_url: Url::from_str("https://example.com").unwrap(),
         port: 8080,
+        server_variables: BTreeMap::new(),
+        base_openapi_source: None,
+        openapi_snapshot: None,
+        path_normalization: PathNormalization::default(),
+    }
+}
+
+pub fn create_mock_operation(method: Method, path: &str) -> Operation {
+    Operation {
+        method,
+        path: path.to_string(),
+        operation_id: None,
+        summary: None,
+        deprecated: false,
+        servers: vec![],
+        parameters: vec![],
+        request_media_types: vec![],
+        responses: vec![],
+        security: None,
+        tags: vec![],
+        extensions: BTreeMap::new(),
+        location: None,
     }
 }